@@ -0,0 +1,42 @@
+#![no_main]
+
+use json_parser_lib::lazy::LazyValue;
+use json_parser_lib::pull::JsonReader;
+use json_parser_lib::tape::Tape;
+use json_parser_lib::{
+    diagnose, parse_lenient_with, parse_reader_with, parse_with, parse_with_recovery,
+    validate_with, BorrowedValue, Documents, ParseOptions, ParseProfile, PushParser,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let strict = ParseOptions::default();
+    let relaxed = strict.clone().profile(ParseProfile::Relaxed);
+    for options in [strict, relaxed] {
+        let _ = parse_with(input, &options);
+        let _ = validate_with(input, &options);
+        let _ = BorrowedValue::parse_with(input, &options);
+        let _ = LazyValue::parse_with(input, &options).map(|value| value.to_value());
+        let _ = Tape::parse_with(input, &options).map(|tape| tape.to_value());
+
+        let mut push = PushParser::with_options(options.clone());
+        for chunk in data.chunks(7) {
+            if push.feed(chunk).is_err() {
+                break;
+            }
+        }
+        let _ = push.finish();
+        let _ = parse_reader_with(data, &options);
+
+        let mut reader = JsonReader::new(input, options.clone());
+        while let Ok(Some(_)) = reader.next_event() {}
+        for _ in Documents::new(input, options.clone()) {}
+
+        let _ = parse_with_recovery(input, &options);
+        let _ = parse_lenient_with(input, &options);
+        let _ = diagnose(input, &options);
+    }
+});
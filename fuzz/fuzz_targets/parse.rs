@@ -0,0 +1,21 @@
+#![no_main]
+
+use json_parser_lib::{parse_bytes_with, render_error, ParseOptions, ParseProfile};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let strict = ParseOptions::default();
+    let relaxed = strict.clone().profile(ParseProfile::Relaxed);
+    for options in [strict, relaxed] {
+        match parse_bytes_with(data, &options) {
+            Ok(value) => {
+                let _ = value.to_string();
+            }
+            Err(error) => {
+                if let Ok(input) = std::str::from_utf8(data) {
+                    let _ = render_error(input, &error);
+                }
+            }
+        }
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use json_parser_lib::{tokenize_spanned, ParseOptions, ParseProfile};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let relaxed = ParseOptions::new().profile(ParseProfile::Relaxed);
+    for options in [ParseOptions::default(), relaxed] {
+        if let Ok(tokens) = tokenize_spanned(input, &options) {
+            // every span lies within the input, on character boundaries
+            for token in tokens {
+                let _ = &input[token.span];
+            }
+        }
+    }
+});
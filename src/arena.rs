@@ -0,0 +1,360 @@
+//! Parsing into a bump allocator, see [`ParseArena`]
+
+use std::collections::{HashMap, HashSet};
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+
+use crate::pull::{Event, JsonReader};
+use crate::{
+    DefaultMapKind, DuplicateKeyPolicy, Error, MapKind, Number, ObjectMap, ParseOptions,
+    TokenParseError, Value,
+};
+
+/// Memory that parsed documents are allocated in, see [`ParseArena::parse`].
+///
+/// Each string, array and object of a document is a bump of a pointer
+/// rather than a separate allocation, and everything is freed at once when
+/// the arena is dropped or [`reset`](Self::reset), without visiting each
+/// value.
+#[derive(Debug, Default)]
+pub struct ParseArena {
+    bump: Bump,
+}
+
+/// A parsed document whose contents live in a [`ParseArena`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaValue<'arena> {
+    Null,
+    Boolean(bool),
+
+    Number(ArenaNumber<'arena>),
+    String(&'arena str),
+    Array(&'arena [ArenaValue<'arena>]),
+
+    /// Entries of an object in source order, where a repeated key has been
+    /// handled as [`ParseOptions::duplicate_keys`] says
+    Object(&'arena [(&'arena str, ArenaValue<'arena>)]),
+}
+
+/// A [`Number`] whose literal text is in the arena, since the arena would
+/// never free the `String` of a [`Number::Literal`], see
+/// [`ArenaValue::as_number`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaNumber<'arena> {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Literal(&'arena str),
+}
+
+impl ParseArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `input` with the default options into a value allocated in
+    /// the arena, which doesn't borrow the input
+    pub fn parse<'arena>(&'arena self, input: &str) -> Result<ArenaValue<'arena>, Error> {
+        self.parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses `input` with the given options into a value allocated in the
+    /// arena
+    pub fn parse_with<'arena>(
+        &'arena self,
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<ArenaValue<'arena>, Error> {
+        let mut reader = JsonReader::new(input, options.clone());
+        let event = reader.next_event()?.ok_or(TokenParseError::EarlyEOF)?;
+        let value = self.read_value(&mut reader, event, options)?;
+        reader.check_trailing_content()?;
+        Ok(value)
+    }
+
+    /// Number of bytes that the arena has reserved for values
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    /// Frees every value parsed into the arena, keeping its largest block of
+    /// memory for the next documents
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// Combines the value of a repeated key with the value it already has,
+    /// as [`ParseOptions::duplicate_keys`] says
+    fn merge<'arena>(
+        &'arena self,
+        existing: &mut ArenaValue<'arena>,
+        value: ArenaValue<'arena>,
+        key: &'arena str,
+        collected: &mut HashSet<&'arena str>,
+        options: &ParseOptions,
+    ) {
+        match options.duplicate_keys {
+            DuplicateKeyPolicy::LastWins => *existing = value,
+            DuplicateKeyPolicy::FirstWins | DuplicateKeyPolicy::Error => {}
+            DuplicateKeyPolicy::CollectAll => {
+                let mut values = BumpVec::new_in(&self.bump);
+                match *existing {
+                    ArenaValue::Array(earlier) if collected.contains(key) => {
+                        values.extend_from_slice(earlier);
+                    }
+                    first => {
+                        values.push(first);
+                        collected.insert(key);
+                    }
+                }
+                values.push(value);
+                *existing = ArenaValue::Array(values.into_bump_slice());
+            }
+        }
+    }
+
+    /// Builds the value that starts with `event`
+    fn read_value<'arena>(
+        &'arena self,
+        reader: &mut JsonReader,
+        event: Event,
+        options: &ParseOptions,
+    ) -> Result<ArenaValue<'arena>, Error> {
+        Ok(match event {
+            Event::Null => ArenaValue::Null,
+            Event::Boolean(b) => ArenaValue::Boolean(b),
+            Event::Number(number) => ArenaValue::Number(match number {
+                Number::I64(n) => ArenaNumber::I64(n),
+                Number::U64(n) => ArenaNumber::U64(n),
+                Number::F64(n) => ArenaNumber::F64(n),
+                Number::Literal(literal) => ArenaNumber::Literal(self.bump.alloc_str(&literal)),
+            }),
+            Event::String(s) => ArenaValue::String(self.bump.alloc_str(&s)),
+            Event::StartArray => {
+                let mut elements = BumpVec::new_in(&self.bump);
+                loop {
+                    match reader
+                        .next_event()?
+                        .ok_or(TokenParseError::UnclosedBracket)?
+                    {
+                        Event::EndArray => break,
+                        event => elements.push(self.read_value(reader, event, options)?),
+                    }
+                }
+                ArenaValue::Array(elements.into_bump_slice())
+            }
+            Event::StartObject => {
+                let mut entries: BumpVec<(&str, _)> = BumpVec::new_in(&self.bump);
+                // where each key is in `entries`, and the keys whose values
+                // have been collected into an array
+                let mut indices: HashMap<&str, usize> = HashMap::new();
+                let mut collected = HashSet::new();
+                while let Some(Event::Key(key)) = reader.next_event()? {
+                    let key = &*self.bump.alloc_str(&key);
+                    let existing = indices.get(key).copied();
+                    if existing.is_some() && options.duplicate_keys == DuplicateKeyPolicy::Error {
+                        let offset = reader.offset();
+                        let err = TokenParseError::DuplicateKey(key.to_string());
+                        return Err(err.locate(reader.input(), || offset));
+                    }
+                    let event = reader.next_event()?.ok_or(TokenParseError::UnclosedBrace)?;
+                    let value = self.read_value(reader, event, options)?;
+                    match existing {
+                        Some(index) => {
+                            let existing = &mut entries[index].1;
+                            self.merge(existing, value, key, &mut collected, options);
+                        }
+                        None => {
+                            indices.insert(key, entries.len());
+                            entries.push((key, value));
+                        }
+                    }
+                }
+                ArenaValue::Object(entries.into_bump_slice())
+            }
+            Event::Key(_) | Event::EndArray | Event::EndObject => {
+                return Err(TokenParseError::ExpectedValue.into())
+            }
+        })
+    }
+}
+
+impl<'arena> ArenaValue<'arena> {
+    /// The value of a key in an object, or `None` when this isn't an object
+    /// or doesn't have the key
+    pub fn get(&self, key: &str) -> Option<&ArenaValue<'arena>> {
+        let ArenaValue::Object(entries) = self else {
+            return None;
+        };
+        entries
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// An element of an array, or `None` when this isn't an array or the
+    /// index is out of bounds
+    pub fn get_index(&self, index: usize) -> Option<&ArenaValue<'arena>> {
+        match self {
+            ArenaValue::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'arena str> {
+        match self {
+            ArenaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Copies a number out of the arena, as [`parse`](crate::parse) would
+    /// have stored it
+    pub fn as_number(&self) -> Option<Number> {
+        match *self {
+            ArenaValue::Number(ArenaNumber::I64(n)) => Some(Number::I64(n)),
+            ArenaValue::Number(ArenaNumber::U64(n)) => Some(Number::U64(n)),
+            ArenaValue::Number(ArenaNumber::F64(n)) => Some(Number::F64(n)),
+            ArenaValue::Number(ArenaNumber::Literal(literal)) => {
+                Some(Number::Literal(literal.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Copies the document into a [`Value`] that doesn't borrow the arena
+    pub fn to_value(self) -> Value {
+        self.to_value_of_kind::<DefaultMapKind>()
+    }
+
+    fn to_value_of_kind<K: MapKind>(self) -> Value<K> {
+        match self {
+            ArenaValue::Null => Value::Null,
+            ArenaValue::Boolean(b) => Value::Boolean(b),
+            ArenaValue::Number(_) => Value::Number(self.as_number().expect("this is a number")),
+            ArenaValue::String(s) => Value::String(s.into()),
+            ArenaValue::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(|element| element.to_value_of_kind())
+                    .collect(),
+            ),
+            ArenaValue::Object(entries) => {
+                let mut map = K::Map::default();
+                for (key, value) in entries {
+                    map.insert((*key).into(), value.to_value_of_kind());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArenaNumber, ArenaValue, ParseArena};
+    use crate::{
+        parse, parse_with, DuplicateKeyPolicy, Number, ParseOptions, ParseProfile, TokenParseError,
+    };
+
+    #[test]
+    fn parses_into_arena() {
+        let input = r#"{"a": [1, -2.5, "x\ny"], "b": {"c": null}, "b": true}"#;
+        let arena = ParseArena::new();
+        let value = arena.parse(input).unwrap();
+
+        assert_eq!(value.get("b"), Some(&ArenaValue::Boolean(true)));
+        let ArenaValue::Object(entries) = value else {
+            panic!("{value:?}");
+        };
+        assert_eq!(entries.len(), 2);
+        let array = &entries[0].1;
+        let one = ArenaValue::Number(ArenaNumber::I64(1));
+        assert_eq!(array.get_index(0), Some(&one));
+        assert_eq!(
+            array.get_index(1).and_then(ArenaValue::as_number),
+            Some(Number::F64(-2.5))
+        );
+        assert_eq!(
+            array.get_index(2).and_then(ArenaValue::as_str),
+            Some("x\ny")
+        );
+        assert_eq!(value.to_value(), parse(input).unwrap());
+        assert!(arena.allocated_bytes() > 0);
+    }
+
+    #[test]
+    fn reuses_memory_after_reset() {
+        let mut arena = ParseArena::new();
+        let input = r#"["some", "strings", "to", "allocate"]"#;
+
+        assert_eq!(
+            arena.parse(input).unwrap().get_index(3).unwrap().as_str(),
+            Some("allocate")
+        );
+        arena.reset();
+        assert_eq!(arena.parse("[]").unwrap(), ArenaValue::Array(&[]));
+    }
+
+    #[test]
+    fn follows_options() {
+        let comments = ParseOptions {
+            allow_comments: true,
+            ..ParseOptions::default()
+        };
+        let json5 = ParseOptions::new().profile(ParseProfile::Json5);
+        let relaxed = ParseOptions::new().profile(ParseProfile::Relaxed);
+        let literals = ParseOptions {
+            preserve_number_literals: true,
+            ..ParseOptions::default()
+        };
+        let duplicates = |policy| ParseOptions::new().duplicate_keys(policy);
+        let cases = [
+            ("[1/*c*/]", comments.clone()),
+            ("[1//c\n]", comments),
+            ("[0x10, +1, NaN, -Infinity]", json5),
+            ("1fD83D", relaxed),
+            ("[1.50, 1e400, -0]", literals),
+            (
+                r#"{"a": 1, "b": 2, "a": 3}"#,
+                duplicates(DuplicateKeyPolicy::FirstWins),
+            ),
+            (
+                r#"{"a": 1, "b": 2, "a": 3}"#,
+                duplicates(DuplicateKeyPolicy::LastWins),
+            ),
+            (
+                r#"{"a": [1], "a": 2, "a": 3}"#,
+                duplicates(DuplicateKeyPolicy::CollectAll),
+            ),
+            (
+                r#"{"a": 1, "b": 2, "a": 3}"#,
+                duplicates(DuplicateKeyPolicy::Error),
+            ),
+        ];
+        for (input, options) in cases {
+            let arena = ParseArena::new();
+            let value = arena.parse_with(input, &options).map(ArenaValue::to_value);
+            assert_eq!(value, parse_with(input, &options), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let arena = ParseArena::new();
+
+        assert_eq!(
+            arena.parse(""),
+            Err(TokenParseError::EmptyInput.locate("", || 0))
+        );
+        assert_eq!(
+            arena.parse("[1, 2"),
+            Err(TokenParseError::UnclosedBracket.locate("[1, 2", || 5))
+        );
+        assert_eq!(
+            arena.parse("1 2"),
+            Err(TokenParseError::UnexpectedTrailingTokens { offset: 2 }.locate("1 2", || 2))
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Support for the [`assert_json_eq!`](crate::assert_json_eq) and
+//! [`assert_json_include!`](crate::assert_json_include) macros
+
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use crate::map::{DefaultMapKind, MapKind};
+use crate::{diff, Value};
+
+/// Something that can be compared as JSON by
+/// [`assert_json_eq!`](crate::assert_json_eq) and
+/// [`assert_json_include!`](crate::assert_json_include): a [`Value`], or JSON
+/// text that is parsed on the fly.
+///
+/// Text is parsed into a `Value` with the [`DefaultMapKind`], so it can only
+/// be compared with values of that kind.
+pub trait JsonOperand {
+    type Kind: MapKind;
+
+    /// The value to compare. Panics if this is text that isn't valid JSON.
+    fn json(&self) -> Cow<'_, Value<Self::Kind>>;
+}
+
+impl<K: MapKind> JsonOperand for Value<K> {
+    type Kind = K;
+
+    fn json(&self) -> Cow<'_, Value<K>> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl JsonOperand for str {
+    type Kind = DefaultMapKind;
+
+    fn json(&self) -> Cow<'_, Value> {
+        match self.parse() {
+            Ok(value) => Cow::Owned(value),
+            Err(err) => panic!("invalid JSON {self:?}: {err:?}"),
+        }
+    }
+}
+
+impl JsonOperand for String {
+    type Kind = DefaultMapKind;
+
+    fn json(&self) -> Cow<'_, Value> {
+        self.as_str().json()
+    }
+}
+
+impl<T: JsonOperand + ?Sized> JsonOperand for &T {
+    type Kind = T::Kind;
+
+    fn json(&self) -> Cow<'_, Value<Self::Kind>> {
+        (**self).json()
+    }
+}
+
+/// Asserts that two JSON documents are equal, see
+/// [`assert_json_eq!`](crate::assert_json_eq)
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_json_eq<L, R>(left: &L, right: &R, message: Option<fmt::Arguments<'_>>)
+where
+    L: JsonOperand + ?Sized,
+    R: JsonOperand<Kind = L::Kind> + ?Sized,
+{
+    let left = left.json();
+    let right = right.json();
+    if left != right {
+        fail("left == right", diff::diff(&left, &right), message);
+    }
+}
+
+/// Asserts that a JSON document includes a pattern, see
+/// [`assert_json_include!`](crate::assert_json_include)
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_json_include<A, P>(actual: &A, pattern: &P, message: Option<fmt::Arguments<'_>>)
+where
+    A: JsonOperand + ?Sized,
+    P: JsonOperand<Kind = A::Kind> + ?Sized,
+{
+    let actual = actual.json();
+    let pattern = pattern.json();
+    let differences = diff::includes(&actual, &pattern);
+    if !differences.is_empty() {
+        fail("actual includes pattern", differences, message);
+    }
+}
+
+#[track_caller]
+fn fail<K: MapKind>(
+    assertion: &str,
+    differences: Vec<diff::Difference<'_, K>>,
+    message: Option<fmt::Arguments<'_>>,
+) -> ! {
+    let mut report = format!("assertion `{assertion}` failed");
+    if let Some(message) = message {
+        let _ = write!(report, ": {message}");
+    }
+    report.push_str("\ndifferences:");
+    for difference in differences {
+        let _ = write!(report, "\n  {difference}");
+    }
+    panic!("{report}");
+}
+
+/// Asserts that two JSON documents are equal, like `assert_eq!`.
+///
+/// Either side may be a [`Value`] or JSON text, which is parsed first. On
+/// failure, the panic message lists each place where the documents differ
+/// (see [`diff`](crate::diff::diff)) rather than printing both documents in
+/// full:
+///
+/// ```text
+/// assertion `left == right` failed
+/// differences:
+///   tls.enabled: changed from false to true
+///   port: number 80 became string "80"
+/// ```
+///
+/// A custom message can be added with format arguments, as with `assert_eq!`.
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert::assert_json_eq(&$left, &$right, None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::assert::assert_json_eq(&$left, &$right, Some(format_args!($($arg)+)))
+    };
+}
+
+/// Asserts that a JSON document includes everything in a pattern, ignoring
+/// any other keys and trailing array elements, see
+/// [`includes`](crate::diff::includes).
+///
+/// This is useful for checking only the fields of a response that a test
+/// cares about. Either side may be a [`Value`] or JSON text, and the panic
+/// message lists what doesn't match:
+///
+/// ```text
+/// assertion `actual includes pattern` failed
+/// differences:
+///   user.email: missing, expected "d@example.com"
+/// ```
+#[macro_export]
+macro_rules! assert_json_include {
+    ($actual:expr, $pattern:expr $(,)?) => {
+        $crate::assert::assert_json_include(&$actual, &$pattern, None)
+    };
+    ($actual:expr, $pattern:expr, $($arg:tt)+) => {
+        $crate::assert::assert_json_include(&$actual, &$pattern, Some(format_args!($($arg)+)))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Value};
+
+    #[test]
+    fn equal_text_and_values() {
+        let value = parse(String::from(r#"{"a": [1, 2], "b": null}"#)).unwrap();
+
+        assert_json_eq!(value, r#"{"b": null, "a": [1, 2]}"#);
+        assert_json_eq!(
+            String::from("[true]"),
+            Value::Array(vec![Value::Boolean(true)])
+        );
+        assert_json_eq!("1", "1", "numbers should match");
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion `left == right` failed
+differences:
+  a[1]: changed from 2 to 3")]
+    fn reports_differences() {
+        assert_json_eq!(r#"{"a": [1, 2]}"#, r#"{"a": [1, 3]}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion `left == right` failed: config 7
+differences:
+  b: removed true")]
+    fn custom_message() {
+        assert_json_eq!(r#"{"b": true}"#, "{}", "config {}", 7);
+    }
+
+    #[test]
+    fn includes_pattern() {
+        let response = r#"{"id": 7, "user": {"name": "ken", "admin": false}}"#;
+
+        assert_json_include!(response, r#"{"user": {"name": "ken"}}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion `actual includes pattern` failed
+differences:
+  user.email: missing, expected \"ken@example.com\"")]
+    fn reports_missing() {
+        let response = r#"{"id": 7, "user": {"name": "ken"}}"#;
+
+        assert_json_include!(response, r#"{"user": {"email": "ken@example.com"}}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid JSON")]
+    fn invalid_text() {
+        assert_json_eq!("[1, @]", "[1]");
+    }
+}
@@ -0,0 +1,193 @@
+//! A document tree that borrows its strings from the input, see
+//! [`BorrowedValue`]
+
+use std::borrow::Cow;
+
+use crate::map::{DefaultMapKind, MapKind};
+use crate::pull::{Event, JsonReader};
+use crate::{Error, Number, ObjectMap, ParseOptions, TokenParseError, Value};
+
+/// A parsed document whose strings and object keys borrow from the input
+/// text when they have no escapes, rather than each being copied into a new
+/// `String`. Only strings that contain escapes are allocated, to hold their
+/// unescaped text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedValue<'a> {
+    Null,
+    Boolean(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<BorrowedValue<'a>>),
+
+    /// Entries of an object in source order, including any duplicate keys
+    Object(Vec<(Cow<'a, str>, BorrowedValue<'a>)>),
+}
+
+impl<'a> BorrowedValue<'a> {
+    /// Parses `input` with the default options
+    pub fn parse(input: &'a str) -> Result<Self, Error> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses `input` with the given options
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<Self, Error> {
+        let mut reader = JsonReader::new(input, options.clone());
+        let event = reader.next_event()?.ok_or(TokenParseError::EarlyEOF)?;
+        let value = read_value(&mut reader, event)?;
+        reader.check_trailing_content()?;
+        Ok(value)
+    }
+
+    /// The value of a key in an object, or `None` when this isn't an object
+    /// or doesn't have the key. When the key appears more than once the last
+    /// value is used, as with [`parse`](crate::parse).
+    pub fn get(&self, key: &str) -> Option<&BorrowedValue<'a>> {
+        let BorrowedValue::Object(entries) = self else {
+            return None;
+        };
+        entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// An element of an array, or `None` when this isn't an array or the
+    /// index is out of bounds
+    pub fn get_index(&self, index: usize) -> Option<&BorrowedValue<'a>> {
+        match self {
+            BorrowedValue::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BorrowedValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Copies the document into a [`Value`] that doesn't borrow the input
+    pub fn to_value(&self) -> Value {
+        self.to_value_of_kind::<DefaultMapKind>()
+    }
+
+    fn to_value_of_kind<K: MapKind>(&self) -> Value<K> {
+        match self {
+            BorrowedValue::Null => Value::Null,
+            BorrowedValue::Boolean(b) => Value::Boolean(*b),
+            BorrowedValue::Number(n) => Value::Number(n.clone()),
+            BorrowedValue::String(s) => Value::String(s.as_ref().into()),
+            BorrowedValue::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(BorrowedValue::to_value_of_kind)
+                    .collect(),
+            ),
+            BorrowedValue::Object(entries) => {
+                let mut map = K::Map::default();
+                for (key, value) in entries {
+                    map.insert(key.as_ref().into(), value.to_value_of_kind());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+/// Builds the value that starts with `event`
+pub(crate) fn read_value<'a>(
+    reader: &mut JsonReader<'a>,
+    event: Event<'a>,
+) -> Result<BorrowedValue<'a>, Error> {
+    Ok(match event {
+        Event::Null => BorrowedValue::Null,
+        Event::Boolean(b) => BorrowedValue::Boolean(b),
+        Event::Number(n) => BorrowedValue::Number(n),
+        Event::String(s) => BorrowedValue::String(s),
+        Event::StartArray => {
+            let mut elements = Vec::new();
+            loop {
+                match reader
+                    .next_event()?
+                    .ok_or(TokenParseError::UnclosedBracket)?
+                {
+                    Event::EndArray => break,
+                    event => elements.push(read_value(reader, event)?),
+                }
+            }
+            BorrowedValue::Array(elements)
+        }
+        Event::StartObject => {
+            let mut entries = Vec::new();
+            while let Some(Event::Key(key)) = reader.next_event()? {
+                let event = reader.next_event()?.ok_or(TokenParseError::UnclosedBrace)?;
+                entries.push((key, read_value(reader, event)?));
+            }
+            BorrowedValue::Object(entries)
+        }
+        Event::Key(_) | Event::EndArray | Event::EndObject => {
+            return Err(TokenParseError::ExpectedValue.into())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::BorrowedValue;
+    use crate::{parse, TokenParseError};
+
+    #[test]
+    fn borrows_strings_without_escapes() {
+        let input = r#"{"plain": "text", "esc\naped": ["a\"b", "c"]}"#;
+        let value = BorrowedValue::parse(input).unwrap();
+
+        let BorrowedValue::Object(entries) = &value else {
+            panic!("{value:?}");
+        };
+        assert!(matches!(entries[0].0, Cow::Borrowed("plain")));
+        assert!(matches!(
+            entries[0].1,
+            BorrowedValue::String(Cow::Borrowed("text"))
+        ));
+        assert!(matches!(&entries[1].0, Cow::Owned(key) if key == "esc\naped"));
+
+        let array = value.get("esc\naped").unwrap();
+        assert!(matches!(
+            array.get_index(0),
+            Some(BorrowedValue::String(Cow::Owned(_)))
+        ));
+        assert_eq!(
+            array.get_index(1).and_then(BorrowedValue::as_str),
+            Some("c")
+        );
+    }
+
+    #[test]
+    fn converts_to_value() {
+        let input = r#"{"a": [1, 2.5, true, null], "b": {"c": "d"}, "a": 3}"#;
+
+        let value = BorrowedValue::parse(input).unwrap();
+        assert_eq!(value.get("a"), Some(&BorrowedValue::Number(3.into())));
+        assert_eq!(value.to_value(), parse(input).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            BorrowedValue::parse(""),
+            Err(TokenParseError::EmptyInput.locate("", || 0))
+        );
+        assert_eq!(
+            BorrowedValue::parse("[1, 2"),
+            Err(TokenParseError::UnclosedBracket.locate("[1, 2", || 5))
+        );
+        assert_eq!(
+            BorrowedValue::parse("[1] 2"),
+            Err(TokenParseError::UnexpectedTrailingTokens { offset: 4 }.locate("[1] 2", || 4))
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! A `tokio-util` codec that reads and writes framed JSON documents, for
+//! use with `FramedRead`, `FramedWrite` and `Framed`
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::map::MapKind;
+use crate::{parse_bytes, ReadError, Value};
+
+/// How the documents in a byte stream are separated from each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Each document is on its own line, as in JSON Lines. Blank lines are
+    /// skipped and the last line doesn't need a newline.
+    Newline,
+
+    /// Each document is preceded by its length in bytes, as a big-endian
+    /// `u32`
+    LengthPrefixed,
+}
+
+/// Decodes a byte stream into [`Value`]s and encodes `Value`s as compact
+/// JSON, one document per frame
+#[derive(Debug, Clone)]
+pub struct JsonCodec {
+    framing: Framing,
+    max_length: usize,
+    /// Where to resume searching for a newline, since the bytes before it
+    /// were already searched
+    next_index: usize,
+}
+
+impl JsonCodec {
+    /// A codec for newline-delimited documents
+    pub fn new() -> Self {
+        Self::with_framing(Framing::Newline)
+    }
+
+    /// A codec for length-prefixed documents
+    pub fn length_prefixed() -> Self {
+        Self::with_framing(Framing::LengthPrefixed)
+    }
+
+    fn with_framing(framing: Framing) -> Self {
+        Self {
+            framing,
+            max_length: usize::MAX,
+            next_index: 0,
+        }
+    }
+
+    /// Limits the length of a single document in bytes, so that a peer
+    /// can't make the codec buffer an unbounded amount of input. Longer
+    /// frames are an error, in either direction.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    fn too_long(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame is longer than {} bytes", self.max_length),
+        )
+    }
+}
+
+impl Default for JsonCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for JsonCodec {
+    type Item = Value;
+    type Error = ReadError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Value>, ReadError> {
+        match self.framing {
+            Framing::Newline => loop {
+                let Some(offset) = src[self.next_index..].iter().position(|&b| b == b'\n') else {
+                    if src.len() > self.max_length {
+                        return Err(self.too_long().into());
+                    }
+                    self.next_index = src.len();
+                    return Ok(None);
+                };
+                let line = src.split_to(self.next_index + offset + 1);
+                self.next_index = 0;
+                if line.len() - 1 > self.max_length {
+                    return Err(self.too_long().into());
+                }
+                let line = line.trim_ascii();
+                if !line.is_empty() {
+                    return Ok(Some(parse_bytes(line)?));
+                }
+            },
+            Framing::LengthPrefixed => {
+                let Some(prefix) = src.get(..4) else {
+                    return Ok(None);
+                };
+                let len = u32::from_be_bytes(prefix.try_into().expect("four bytes")) as usize;
+                if len > self.max_length {
+                    return Err(self.too_long().into());
+                }
+                if src.len() < 4 + len {
+                    src.reserve(4 + len - src.len());
+                    return Ok(None);
+                }
+                src.advance(4);
+                let frame = src.split_to(len);
+                Ok(Some(parse_bytes(&frame)?))
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Value>, ReadError> {
+        if let Some(value) = self.decode(src)? {
+            return Ok(Some(value));
+        }
+        // the last line may be missing its newline
+        if self.framing == Framing::Newline {
+            self.next_index = 0;
+            let line = src.split();
+            let line = line.trim_ascii();
+            if !line.is_empty() {
+                return Ok(Some(parse_bytes(line)?));
+            }
+        }
+        if src.is_empty() {
+            Ok(None)
+        } else {
+            Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended within a frame").into())
+        }
+    }
+}
+
+impl<K: MapKind> Encoder<&Value<K>> for JsonCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, value: &Value<K>, dst: &mut BytesMut) -> io::Result<()> {
+        // strings are escaped, so the compact text never contains a newline
+        let text = value.to_string();
+        if text.len() > self.max_length {
+            return Err(self.too_long());
+        }
+        match self.framing {
+            Framing::Newline => {
+                dst.reserve(text.len() + 1);
+                dst.put_slice(text.as_bytes());
+                dst.put_u8(b'\n');
+            }
+            Framing::LengthPrefixed => {
+                let len = u32::try_from(text.len()).map_err(|_| self.too_long())?;
+                dst.reserve(text.len() + 4);
+                dst.put_u32(len);
+                dst.put_slice(text.as_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K: MapKind> Encoder<Value<K>> for JsonCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, value: Value<K>, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(&value, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::JsonCodec;
+    use crate::{ReadError, Value};
+
+    fn decode_all(codec: &mut JsonCodec, input: &[u8]) -> Vec<Value> {
+        // feed one byte at a time so that every frame is split
+        let mut src = BytesMut::new();
+        let mut values = Vec::new();
+        for &byte in input {
+            src.extend_from_slice(&[byte]);
+            while let Some(value) = codec.decode(&mut src).unwrap() {
+                values.push(value);
+            }
+        }
+        while let Some(value) = codec.decode_eof(&mut src).unwrap() {
+            values.push(value);
+        }
+        values
+    }
+
+    #[test]
+    fn round_trips_newline_frames() {
+        let values = [
+            Value::object([("a", Value::string("line\nbreak"))]),
+            Value::Array(vec![1.into(), Value::Null]),
+        ];
+        let mut codec = JsonCodec::new();
+        let mut dst = BytesMut::new();
+        for value in &values {
+            codec.encode(value, &mut dst).unwrap();
+        }
+
+        assert_eq!(&dst[..], b"{\"a\":\"line\\nbreak\"}\n[1,null]\n");
+        assert_eq!(decode_all(&mut codec, &dst), values);
+    }
+
+    #[test]
+    fn skips_blank_lines() {
+        let mut codec = JsonCodec::new();
+        let values = decode_all(&mut codec, b"1\r\n\n  \n2");
+
+        assert_eq!(values, [1.into(), 2.into()]);
+    }
+
+    #[test]
+    fn round_trips_length_prefixed_frames() {
+        let mut codec = JsonCodec::length_prefixed();
+        let mut dst = BytesMut::new();
+        codec
+            .encode(Value::<crate::DefaultMapKind>::Boolean(true), &mut dst)
+            .unwrap();
+        codec
+            .encode(Value::<crate::DefaultMapKind>::string("x"), &mut dst)
+            .unwrap();
+
+        assert_eq!(&dst[..], b"\0\0\0\x04true\0\0\0\x03\"x\"");
+        assert_eq!(
+            decode_all(&mut codec, &dst),
+            [Value::Boolean(true), Value::string("x")]
+        );
+    }
+
+    #[test]
+    fn rejects_long_frames() {
+        let mut codec = JsonCodec::new().max_length(4);
+        let mut src = BytesMut::from(&b"[1, 2, 3]"[..]);
+
+        assert!(matches!(codec.decode(&mut src), Err(ReadError::Io(_))));
+    }
+
+    #[test]
+    fn reports_invalid_documents() {
+        let mut codec = JsonCodec::new();
+        let mut src = BytesMut::from(&b"[1, @]\n"[..]);
+
+        assert!(matches!(codec.decode(&mut src), Err(ReadError::Parse(_))));
+    }
+}
@@ -0,0 +1,310 @@
+//! Rust type definitions generated from sample documents
+
+use std::collections::HashSet;
+
+use crate::map::MapKind;
+use crate::schema::{ObjectShape, Shape};
+use crate::Value;
+
+/// Words that can't be used as plain identifiers
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Keywords that can't be written as raw identifiers either
+const RESERVED: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+/// Generates Rust struct definitions that can hold `value`, with the root
+/// type called `name`.
+///
+/// Field types are inferred the same way as [`schema::infer`](crate::schema::infer):
+/// integers become `i64`, other numbers `f64`, and the elements of an array
+/// are merged, so a field missing from some elements or sometimes `null` is
+/// an `Option`. Nested objects become their own structs, named after their
+/// key. Places where values of several types were seen, or where only
+/// `null` was seen, use this crate's [`Value`].
+///
+/// Keys are converted to `snake_case` field names, and a field whose name
+/// differs from its key is documented with the original key.
+pub fn generate<K: MapKind>(value: &Value<K>, name: &str) -> String {
+    let mut generator = Generator::default();
+    let root = pascal_case(name);
+    generator.names.insert(root.clone());
+
+    // a single value has a single type, so the root is either a struct or an
+    // alias of some other type
+    match Shape::of(value) {
+        Shape {
+            object: Some(object),
+            ..
+        } => generator.define_struct(root, object),
+        shape => {
+            let index = generator.definitions.len();
+            generator.definitions.push(String::new());
+            let ty = generator.rust_type(shape, &root);
+            generator.definitions[index] = format!("pub type {root} = {ty};\n");
+        }
+    }
+
+    let mut output = String::new();
+    if generator.uses_value {
+        output.push_str(concat!("use ", env!("CARGO_CRATE_NAME"), "::Value;\n\n"));
+    }
+    output.push_str(&generator.definitions.join("\n"));
+    output
+}
+
+#[derive(Default)]
+struct Generator {
+    /// Rendered definitions, parents before the structs of their fields
+    definitions: Vec<String>,
+    /// Type names already used
+    names: HashSet<String>,
+    uses_value: bool,
+}
+
+impl Generator {
+    fn rust_type(&mut self, shape: Shape, hint: &str) -> String {
+        let kinds = [
+            shape.boolean,
+            shape.integer || shape.number,
+            shape.string,
+            shape.array.is_some(),
+            shape.object.is_some(),
+        ];
+        let ty = if kinds.iter().filter(|&&seen| seen).count() != 1 {
+            self.uses_value = true;
+            String::from("Value")
+        } else if shape.boolean {
+            String::from("bool")
+        } else if shape.number {
+            String::from("f64")
+        } else if shape.integer {
+            String::from("i64")
+        } else if shape.string {
+            String::from("String")
+        } else if let Some(items) = shape.array {
+            let items = match items {
+                Some(items) => self.rust_type(*items, &format!("{hint}Item")),
+                None => {
+                    self.uses_value = true;
+                    String::from("Value")
+                }
+            };
+            format!("Vec<{items}>")
+        } else {
+            let object = shape.object.expect("the only remaining kind");
+            let name = self.unique_name(hint);
+            self.define_struct(name.clone(), object);
+            name
+        };
+
+        if shape.null {
+            format!("Option<{ty}>")
+        } else {
+            ty
+        }
+    }
+
+    fn define_struct(&mut self, name: String, object: ObjectShape) {
+        // reserve the position so the struct comes before those of its fields
+        let index = self.definitions.len();
+        self.definitions.push(String::new());
+
+        let mut fields = String::new();
+        let mut field_names = HashSet::new();
+        for (key, shape) in object.properties {
+            let required = object.required.get(&key).copied().unwrap_or(false);
+            let mut ty = self.rust_type(shape, &pascal_case(&key));
+            if !required && !ty.starts_with("Option<") {
+                ty = format!("Option<{ty}>");
+            }
+
+            let base = field_name(&key);
+            let mut field = base.clone();
+            let mut suffix = 2;
+            while !field_names.insert(field.clone()) {
+                field = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            if field.trim_start_matches("r#") != key {
+                fields.push_str(&format!("    /// `{key}` in JSON\n"));
+            }
+            fields.push_str(&format!("    pub {field}: {ty},\n"));
+        }
+
+        self.definitions[index] =
+            format!("#[derive(Debug, Clone, PartialEq)]\npub struct {name} {{\n{fields}}}\n");
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut suffix = 2;
+        while !self.names.insert(name.clone()) {
+            name = format!("{hint}{suffix}");
+            suffix += 1;
+        }
+        name
+    }
+}
+
+/// Splits a key into lowercase words at punctuation and at the start of each
+/// capitalized word, ex. `userID-list` into `["user", "id", "list"]`
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = key.chars().collect();
+    for (i, &ch) in chars.iter().enumerate() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        // `aB` and the `B` of `ABc` start new words
+        let starts_word = ch.is_uppercase()
+            && previous.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if starts_word && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn field_name(key: &str) -> String {
+    let name = words(key).join("_");
+    let name = match name.chars().next() {
+        None => String::from("field"),
+        Some(first) if first.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    };
+    if RESERVED.contains(&name.as_str()) {
+        format!("{name}_")
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{name}")
+    } else {
+        name
+    }
+}
+
+fn pascal_case(key: &str) -> String {
+    let name: String = words(key)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .concat();
+    match name.chars().next() {
+        None => String::from("Field"),
+        Some(first) if first.is_ascii_digit() => format!("T{name}"),
+        Some(_) if name == "Self" => String::from("Self_"),
+        Some(_) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_name, generate, pascal_case};
+    use crate::{OrderedMapKind, Value};
+
+    fn check(sample: &str, name: &str, expected: &str) {
+        let sample: Value<OrderedMapKind> = sample.parse().unwrap();
+
+        assert_eq!(generate(&sample, name), expected);
+    }
+
+    #[test]
+    fn generates_structs() {
+        check(
+            r#"{"id": 1, "userName": "ken", "score": 2.5, "active": true, "address": {"city": "Oslo"}}"#,
+            "user",
+            "\
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub id: i64,
+    /// `userName` in JSON
+    pub user_name: String,
+    pub score: f64,
+    pub active: bool,
+    pub address: Address,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub city: String,
+}
+",
+        );
+    }
+
+    #[test]
+    fn optional_fields_from_arrays() {
+        check(
+            r#"{"items": [{"id": 1, "note": null}, {"id": 2, "note": "x", "tags": ["a"]}]}"#,
+            "Order",
+            "\
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    pub items: Vec<ItemsItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemsItem {
+    pub id: i64,
+    pub note: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+",
+        );
+    }
+
+    #[test]
+    fn mixed_types_use_value() {
+        check(
+            r#"[{"type": 1, "data": []}, {"type": "a", "data": []}]"#,
+            "Events",
+            "\
+use json_parser_lib::Value;
+
+pub type Events = Vec<EventsItem>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventsItem {
+    pub r#type: Value,
+    pub data: Vec<Value>,
+}
+",
+        );
+    }
+
+    #[test]
+    fn converts_names() {
+        assert_eq!(field_name("userID"), "user_id");
+        assert_eq!(field_name("HTTPStatus"), "http_status");
+        assert_eq!(field_name("first-name"), "first_name");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(field_name(""), "field");
+        assert_eq!(pascal_case("line_items"), "LineItems");
+        assert_eq!(pascal_case("9lives"), "T9lives");
+    }
+}
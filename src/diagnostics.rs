@@ -0,0 +1,433 @@
+//! Warnings about input that parses but probably isn't what its author
+//! meant, see [`parse_with_diagnostics`], and [`Diagnostic`]s for tools
+
+use std::fmt;
+
+use crate::lint::loses_precision;
+use crate::parse::parse_document;
+use crate::tokenize::{
+    closing_quote, tokenize_spanned, trailing_offset, KeyTracker, SpannedToken, Token,
+};
+use crate::{Error, ErrorKind, Location, Number, ParseOptions, Value};
+
+/// Collects the warnings from one or more calls to [`parse_with_diagnostics`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    warnings: Vec<Warning>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The warnings collected so far, in the order found in the input
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn into_warnings(self) -> Vec<Warning> {
+        self.warnings
+    }
+
+    fn push(&mut self, kind: WarningKind, offset: usize) {
+        self.warnings.push(Warning { kind, offset });
+    }
+}
+
+/// Something questionable in the input that didn't stop it from parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+
+    /// Byte offset in the input of the token with the issue
+    pub offset: usize,
+}
+
+/// One of the issues that [`parse_with_diagnostics`] warns about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// The number (as written) was rounded to fit in an `f64`
+    PrecisionLoss(String),
+
+    /// The key repeats an earlier key of the same object, which starts at
+    /// the byte offset `first`. Which value is kept depends on
+    /// [`ParseOptions::duplicate_keys`].
+    DuplicateKey { key: String, first: usize },
+
+    /// An escape in a string that was accepted but is likely a mistake,
+    /// as written: either one that JSON doesn't define, accepted because of
+    /// [`ParseOptions::allow_invalid_escapes`], or `\u0000`, which ends the
+    /// string early for consumers written in C
+    SuspiciousEscape(String),
+}
+
+impl WarningKind {
+    /// A name for this kind of warning that stays the same between versions,
+    /// for tools to match on, ex. `duplicate-key`
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::PrecisionLoss(_) => "precision-loss",
+            WarningKind::DuplicateKey { .. } => "duplicate-key",
+            WarningKind::SuspiciousEscape(_) => "suspicious-escape",
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::PrecisionLoss(n) => write!(f, "number {n} is rounded as a 64-bit float"),
+            WarningKind::DuplicateKey { key, .. } => write!(f, "duplicate key {key:?}"),
+            WarningKind::SuspiciousEscape(escape) => write!(f, "suspicious escape `{escape}`"),
+        }
+    }
+}
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The input couldn't be parsed
+    Error,
+
+    /// The input parsed, but likely not as its author meant
+    Warning,
+}
+
+/// A problem in the input, in a form for tools to consume, such as an
+/// editor's language server or a CI annotator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Names the kind of problem, see [`ErrorKind::code`] and
+    /// [`WarningKind::code`]
+    pub code: &'static str,
+
+    pub severity: Severity,
+
+    pub message: String,
+
+    /// Where the problem starts. An error without a location is placed at
+    /// the end of the input when the input ended too soon, and at its start
+    /// otherwise.
+    pub start: Location,
+
+    /// Where the problem ends, just after its last character
+    pub end: Location,
+
+    /// A hint for fixing the problem, see [`Error::suggestion`]
+    pub suggestion: Option<&'static str>,
+}
+
+impl Error {
+    /// Describes the error for tools, given the `input` that failed to parse
+    pub fn to_diagnostic(&self, input: &str) -> Diagnostic {
+        let at_end = matches!(
+            self.kind,
+            ErrorKind::EmptyInput
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::UnclosedArray
+                | ErrorKind::UnclosedObject
+        );
+        let start = match self.location {
+            Some(location) => location,
+            None if at_end => Location::new(input, input.len()),
+            None => Location::default(),
+        };
+        Diagnostic {
+            code: self.kind.code(),
+            severity: Severity::Error,
+            message: self.message(),
+            start,
+            end: start.advance(input, token_end(input, start.offset)),
+            suggestion: self.suggestion,
+        }
+    }
+}
+
+impl Warning {
+    /// Describes the warning for tools, given the `input` it was found in
+    pub fn to_diagnostic(&self, input: &str) -> Diagnostic {
+        let end = match &self.kind {
+            WarningKind::PrecisionLoss(text) | WarningKind::SuspiciousEscape(text) => {
+                self.offset + text.len()
+            }
+            WarningKind::DuplicateKey { .. } => token_end(input, self.offset),
+        };
+        let start = Location::new(input, self.offset);
+        Diagnostic {
+            code: self.kind.code(),
+            severity: Severity::Warning,
+            message: self.kind.to_string(),
+            start,
+            end: start.advance(input, end.min(input.len())),
+            suggestion: None,
+        }
+    }
+}
+
+/// Parses `input` with the given options, returning a diagnostic for each
+/// warning and for the error, if any, in the order found
+pub fn diagnose(input: &str, options: &ParseOptions) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostics::new();
+    let result = parse_with_diagnostics(input, options, &mut diagnostics);
+    let mut found: Vec<_> = diagnostics
+        .warnings()
+        .iter()
+        .map(|warning| warning.to_diagnostic(input))
+        .collect();
+    if let Err(error) = result {
+        found.push(error.to_diagnostic(input));
+    }
+    found
+}
+
+/// Byte offset just after the token, or at least the character, that starts
+/// at `start` in `input`
+fn token_end(input: &str, start: usize) -> usize {
+    let Some(first) = input.get(start..).and_then(|rest| rest.chars().next()) else {
+        return input.len().min(start);
+    };
+    if first == '"' || first == '\'' {
+        return closing_quote(input.as_bytes(), start).map_or(input.len(), |quote| quote + 1);
+    }
+    let is_word = |ch: char| ch.is_alphanumeric() || matches!(ch, '_' | '$' | '-' | '+' | '.');
+    if !is_word(first) {
+        return start + first.len_utf8();
+    }
+    input[start..]
+        .find(|ch: char| !is_word(ch))
+        .map_or(input.len(), |length| start + length)
+}
+
+/// Parses JSON text with the given options, adding a warning to
+/// `diagnostics` for each issue that doesn't stop the parse.
+///
+/// Warnings found before a parse error are kept. Duplicate keys are only
+/// warned about when [`ParseOptions::duplicate_keys`] accepts them, as it
+/// does by default.
+pub fn parse_with_diagnostics(
+    input: &str,
+    options: &ParseOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<Value, Error> {
+    let spans = tokenize_spanned(input, options)?;
+
+    let mut keys = KeyTracker::default();
+    for SpannedToken { token, span, .. } in &spans {
+        match token {
+            Token::Number(Number::F64(_)) if loses_precision(&input[span.clone()]) => {
+                diagnostics.push(
+                    WarningKind::PrecisionLoss(input[span.clone()].to_string()),
+                    span.start,
+                );
+            }
+            Token::String(raw) => {
+                // unquoted keys have no quote to skip
+                let text_start = match input.as_bytes()[span.start] {
+                    b'"' | b'\'' => span.start + 1,
+                    _ => span.start,
+                };
+                for (index, escape) in suspicious_escapes(raw) {
+                    diagnostics.push(
+                        WarningKind::SuspiciousEscape(escape.to_string()),
+                        text_start + index,
+                    );
+                }
+            }
+            _ => {}
+        }
+        if let Some(duplicate) = keys.duplicate(token, span.start, options) {
+            diagnostics.push(
+                WarningKind::DuplicateKey {
+                    key: duplicate.key,
+                    first: duplicate.first,
+                },
+                duplicate.second,
+            );
+        }
+    }
+
+    let (tokens, offsets): (Vec<Token>, Vec<usize>) = spans
+        .into_iter()
+        .map(|spanned| (spanned.token, spanned.span.start))
+        .unzip();
+    let value = parse_document(&tokens[..], options, |_, _| trailing_offset(input, options))
+        .map_err(|(err, index)| {
+            err.locate(input, || offsets.get(index).copied().unwrap_or(input.len()))
+        })?;
+    Ok(value)
+}
+
+/// The suspicious escapes in the text of a string, with their byte offsets
+/// in it
+fn suspicious_escapes(raw: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut index = 0;
+    std::iter::from_fn(move || {
+        while let Some(backslash) = raw[index..].find('\\') {
+            let start = index + backslash;
+            let escaped = raw[start + 1..].chars().next()?;
+            index = start + 1 + escaped.len_utf8();
+            match escaped {
+                'u' => {
+                    index = (start + 6).min(raw.len());
+                    if raw[start..index].eq_ignore_ascii_case("\\u0000") {
+                        return Some((start, &raw[start..index]));
+                    }
+                }
+                '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | '\'' => {}
+                _ => return Some((start, &raw[start..index])),
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diagnose, parse_with_diagnostics, Diagnostic, Diagnostics, Severity, Warning, WarningKind,
+    };
+    use crate::{parse, Location, ParseOptions, TokenParseError, Value};
+
+    fn warnings(input: &str, options: &ParseOptions) -> Vec<Warning> {
+        let mut diagnostics = Diagnostics::new();
+        parse_with_diagnostics(input, options, &mut diagnostics).unwrap();
+        diagnostics.into_warnings()
+    }
+
+    #[test]
+    fn valid_input_has_no_warnings() {
+        let input = r#"{"a": [1, 2.5, "tab\t\u00e9"], "b": 18446744073709551615}"#;
+        let mut diagnostics = Diagnostics::new();
+        let value = parse_with_diagnostics(input, &ParseOptions::default(), &mut diagnostics);
+        assert_eq!(value, parse(input));
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            warnings(r#"["\/", "\u0041"]"#, &ParseOptions::default()),
+            []
+        );
+    }
+
+    #[test]
+    fn warns_without_failing() {
+        let input = r#"{"a": 1, "a": "x\u0000\q"}"#;
+        let options = ParseOptions::new().allow_invalid_escapes(true);
+
+        let mut diagnostics = Diagnostics::new();
+        let value = parse_with_diagnostics(input, &options, &mut diagnostics).unwrap();
+        assert_eq!(value, Value::object([("a", Value::string("x\0q"))]));
+        assert_eq!(
+            diagnostics.warnings(),
+            [
+                Warning {
+                    kind: WarningKind::DuplicateKey {
+                        key: "a".to_string(),
+                        first: 1,
+                    },
+                    offset: 9,
+                },
+                Warning {
+                    kind: WarningKind::SuspiciousEscape("\\u0000".to_string()),
+                    offset: 16,
+                },
+                Warning {
+                    kind: WarningKind::SuspiciousEscape("\\q".to_string()),
+                    offset: 22,
+                },
+            ]
+        );
+    }
+
+    // numbers are stored exactly with `arbitrary_precision`
+    #[cfg(not(feature = "arbitrary_precision"))]
+    #[test]
+    fn warns_about_precision_loss() {
+        // `1e400` is kept as written, so nothing about it is lost
+        let input = "[0.1000000000000000055511151231257827, 0.1, 1e400]";
+        assert_eq!(
+            warnings(input, &ParseOptions::default()),
+            [Warning {
+                kind: WarningKind::PrecisionLoss(
+                    "0.1000000000000000055511151231257827".to_string()
+                ),
+                offset: 1,
+            },]
+        );
+        let options = ParseOptions::new().preserve_number_literals(true);
+        assert_eq!(warnings(input, &options), []);
+    }
+
+    #[test]
+    fn keeps_warnings_before_an_error() {
+        let options = ParseOptions::new();
+        let mut diagnostics = Diagnostics::new();
+        let input = r#"[{"k": 1, "k": 2}, "\u0000""#;
+        assert_eq!(
+            parse_with_diagnostics(input, &options, &mut diagnostics),
+            Err(TokenParseError::UnclosedBracket.locate(input, || input.len()))
+        );
+        assert_eq!(diagnostics.warnings().len(), 2);
+
+        // collected across documents
+        parse_with_diagnostics(r#""\u0000""#, &options, &mut diagnostics).unwrap();
+        assert_eq!(diagnostics.warnings().len(), 3);
+    }
+
+    #[test]
+    fn diagnostics_for_tools() {
+        let input = "{\"a\": 1,\n \"a\": \"\\u0000\"";
+        let found = diagnose(input, &ParseOptions::default());
+        let summary: Vec<_> = found
+            .iter()
+            .map(|diagnostic| {
+                let Diagnostic {
+                    code,
+                    severity,
+                    start,
+                    end,
+                    ..
+                } = diagnostic;
+                (
+                    *code,
+                    *severity,
+                    start.offset..end.offset,
+                    start.line,
+                    start.column,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("duplicate-key", Severity::Warning, 10..13, 1, 1),
+                ("suspicious-escape", Severity::Warning, 16..22, 1, 7),
+                ("unclosed-object", Severity::Error, 23..23, 1, 14),
+            ]
+        );
+        assert_eq!(found[1].message, "suspicious escape `\\u0000`");
+
+        let input = "{\"a\": 1,\n name: 2}";
+        let error = parse(input).unwrap_err();
+        let location = |offset, column| Location {
+            offset,
+            line: 1,
+            column,
+        };
+        assert_eq!(
+            error.to_diagnostic(input),
+            Diagnostic {
+                code: "invalid-literal",
+                severity: Severity::Error,
+                message: "expected `true`, `false` or `null`".to_string(),
+                start: location(10, 1),
+                end: location(14, 5),
+                suggestion: Some("object keys must be in double quotes"),
+            }
+        );
+        assert_eq!(diagnose("[1, 2]", &ParseOptions::default()), []);
+    }
+}
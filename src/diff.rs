@@ -0,0 +1,342 @@
+//! Structural comparison of two documents, reporting each place where they
+//! differ
+
+use std::fmt;
+
+use crate::map::{DefaultMapKind, MapKind, ObjectMap};
+use crate::Value;
+
+/// How a value differs between the two documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// Only the right document has a value at the path
+    Added,
+
+    /// Only the left document has a value at the path
+    Removed,
+
+    /// Both documents have a value of the same type at the path, but the
+    /// values are not equal
+    Changed,
+
+    /// Both documents have a value at the path, but of different types
+    TypeMismatch,
+
+    /// The pattern passed to [`includes`] has a value at the path, but the
+    /// document doesn't
+    Missing,
+}
+
+/// A place where two documents differ, see [`diff`]
+pub struct Difference<'a, K: MapKind = DefaultMapKind> {
+    /// Location of the difference, in the notation of [`Value::flatten`],
+    /// ex. `users[0].name`. The root is the empty path.
+    pub path: String,
+
+    /// How the values at the path differ
+    pub kind: DifferenceKind,
+
+    /// The value in the left document, `None` when it was added
+    pub left: Option<&'a Value<K>>,
+
+    /// The value in the right document, `None` when it was removed
+    pub right: Option<&'a Value<K>>,
+}
+
+/// Compares two documents and returns every place where they differ.
+///
+/// Objects are compared key by key and arrays index by index, with any extra
+/// elements at the end of an array reported as added or removed. Values that
+/// are not containers, or are containers of different types, are reported as
+/// a single difference without looking inside them.
+pub fn diff<'a, K: MapKind>(left: &'a Value<K>, right: &'a Value<K>) -> Vec<Difference<'a, K>> {
+    let mut differences = Vec::new();
+    diff_into(left, right, String::new(), Mode::Equal, &mut differences);
+    differences
+}
+
+/// Checks that `actual` includes everything in `pattern`, and returns every
+/// place where it doesn't.
+///
+/// Object keys that are not in the pattern are ignored, as are elements
+/// beyond the end of an array in the pattern; array elements are matched by
+/// index. Anything in the pattern that `actual` lacks is reported as
+/// [`DifferenceKind::Missing`], and the remaining differences are the same as
+/// from [`diff`], with `actual` on the left.
+pub fn includes<'a, K: MapKind>(
+    actual: &'a Value<K>,
+    pattern: &'a Value<K>,
+) -> Vec<Difference<'a, K>> {
+    let mut differences = Vec::new();
+    diff_into(
+        actual,
+        pattern,
+        String::new(),
+        Mode::Include,
+        &mut differences,
+    );
+    differences
+}
+
+impl<K: MapKind> Value<K> {
+    /// Whether this document is a structural superset of `pattern`, see
+    /// [`includes`] for the rules and to find out what doesn't match
+    pub fn contains(&self, pattern: &Value<K>) -> bool {
+        includes(self, pattern).is_empty()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// Everything in either document must be in the other
+    Equal,
+    /// Everything on the right must be on the left
+    Include,
+}
+
+fn diff_into<'a, K: MapKind>(
+    left: &'a Value<K>,
+    right: &'a Value<K>,
+    path: String,
+    mode: Mode,
+    differences: &mut Vec<Difference<'a, K>>,
+) {
+    let only_right = match mode {
+        Mode::Equal => DifferenceKind::Added,
+        Mode::Include => DifferenceKind::Missing,
+    };
+
+    let kind = match (left, right) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, left) in a.iter() {
+                let path = key_path(&path, key);
+                match b.get(key) {
+                    Some(right) => diff_into(left, right, path, mode, differences),
+                    None if mode == Mode::Include => {}
+                    None => differences.push(Difference {
+                        path,
+                        kind: DifferenceKind::Removed,
+                        left: Some(left),
+                        right: None,
+                    }),
+                }
+            }
+            for (key, right) in b.iter() {
+                if !a.contains_key(key) {
+                    differences.push(Difference {
+                        path: key_path(&path, key),
+                        kind: only_right,
+                        left: None,
+                        right: Some(right),
+                    });
+                }
+            }
+            return;
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            let len = match mode {
+                Mode::Equal => a.len().max(b.len()),
+                Mode::Include => b.len(),
+            };
+            for i in 0..len {
+                let path = format!("{path}[{i}]");
+                match (a.get(i), b.get(i)) {
+                    (Some(left), Some(right)) => diff_into(left, right, path, mode, differences),
+                    (left, right) => differences.push(Difference {
+                        path,
+                        kind: if left.is_some() {
+                            DifferenceKind::Removed
+                        } else {
+                            only_right
+                        },
+                        left,
+                        right,
+                    }),
+                }
+            }
+            return;
+        }
+        _ if left == right => return,
+        _ if type_name(left) == type_name(right) => DifferenceKind::Changed,
+        _ => DifferenceKind::TypeMismatch,
+    };
+
+    differences.push(Difference {
+        path,
+        kind,
+        left: Some(left),
+        right: Some(right),
+    });
+}
+
+fn key_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn type_name<K: MapKind>(value: &Value<K>) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Boolean(_) => "boolean",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Writes a one-line description, ex. `users[0].name: changed from "a" to "b"`
+impl<K: MapKind> fmt::Display for Difference<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str("(root)")?;
+        } else {
+            f.write_str(&self.path)?;
+        }
+
+        match (self.kind, self.left, self.right) {
+            (DifferenceKind::Added, _, Some(right)) => write!(f, ": added {right}"),
+            (DifferenceKind::Removed, Some(left), _) => write!(f, ": removed {left}"),
+            (DifferenceKind::Missing, _, Some(right)) => write!(f, ": missing, expected {right}"),
+            (DifferenceKind::Changed, Some(left), Some(right)) => {
+                write!(f, ": changed from {left} to {right}")
+            }
+            (DifferenceKind::TypeMismatch, Some(left), Some(right)) => write!(
+                f,
+                ": {} {left} became {} {right}",
+                type_name(left),
+                type_name(right)
+            ),
+            (kind, _, _) => write!(f, ": {kind:?}"),
+        }
+    }
+}
+
+impl<K: MapKind> fmt::Debug for Difference<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Difference")
+            .field("path", &self.path)
+            .field("kind", &self.kind)
+            .field("left", &self.left)
+            .field("right", &self.right)
+            .finish()
+    }
+}
+
+impl<K: MapKind> PartialEq for Difference<'_, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.kind == other.kind
+            && self.left == other.left
+            && self.right == other.right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, includes, DifferenceKind};
+    use crate::{OrderedMapKind, Value};
+
+    fn check(left: &str, right: &str, expected: &[&str]) {
+        let left: Value<OrderedMapKind> = left.parse().unwrap();
+        let right: Value<OrderedMapKind> = right.parse().unwrap();
+
+        let differences: Vec<String> = diff(&left, &right)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(differences, expected);
+    }
+
+    #[test]
+    fn equal_documents() {
+        check(
+            r#"{"a": [1, {"b": null}]}"#,
+            r#"{"a": [1, {"b": null}]}"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn reports_paths() {
+        check(
+            r#"{"name": "api", "port": 80, "tls": {"enabled": false}, "old": 1}"#,
+            r#"{"name": "api", "port": "80", "tls": {"enabled": true}, "new": [2]}"#,
+            &[
+                r#"port: number 80 became string "80""#,
+                "tls.enabled: changed from false to true",
+                "old: removed 1",
+                "new: added [2]",
+            ],
+        );
+    }
+
+    #[test]
+    fn compares_arrays_by_index() {
+        check(
+            r#"{"hosts": ["a", "b"]}"#,
+            r#"{"hosts": ["a", "c", "d"]}"#,
+            &[
+                r#"hosts[1]: changed from "b" to "c""#,
+                r#"hosts[2]: added "d""#,
+            ],
+        );
+        check("[1, 2]", "[1]", &["[1]: removed 2"]);
+    }
+
+    #[test]
+    fn includes_subset() {
+        let actual: Value<OrderedMapKind> =
+            r#"{"id": 7, "user": {"name": "ken", "roles": ["admin", "dev"]}, "extra": true}"#
+                .parse()
+                .unwrap();
+        let pattern: Value<OrderedMapKind> =
+            r#"{"user": {"roles": ["admin"], "name": "ken"}}"#.parse().unwrap();
+
+        assert!(actual.contains(&pattern));
+        assert!(!pattern.contains(&actual));
+    }
+
+    #[test]
+    fn includes_reports_missing() {
+        let actual: Value<OrderedMapKind> =
+            r#"{"user": {"name": "ken", "roles": []}}"#.parse().unwrap();
+        let pattern: Value<OrderedMapKind> =
+            r#"{"user": {"name": "david", "roles": ["admin"], "email": "d@example.com"}, "id": 7}"#
+                .parse()
+                .unwrap();
+
+        let differences: Vec<String> = includes(&actual, &pattern)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let expected = [
+            r#"user.name: changed from "ken" to "david""#,
+            r#"user.roles[0]: missing, expected "admin""#,
+            r#"user.email: missing, expected "d@example.com""#,
+            "id: missing, expected 7",
+        ];
+        assert_eq!(differences, expected);
+    }
+
+    #[test]
+    fn root_difference() {
+        check("1", "2", &["(root): changed from 1 to 2"]);
+    }
+
+    #[test]
+    fn borrows_both_values() {
+        let left: Value = "[true]".parse().unwrap();
+        let right: Value = r#"["true"]"#.parse().unwrap();
+        let differences = diff(&left, &right);
+
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path, "[0]");
+        assert_eq!(differences[0].kind, DifferenceKind::TypeMismatch);
+        assert_eq!(differences[0].left, Some(&Value::Boolean(true)));
+        assert_eq!(differences[0].right, Some(&Value::string("true")));
+    }
+}
@@ -0,0 +1,318 @@
+//! Runs the same input through every parser backend, for tests that check
+//! they agree
+
+use crate::lazy::LazyValue;
+use crate::tape::Tape;
+use crate::{
+    parse_reader_with, parse_with, validate_with, BorrowedValue, Error, ErrorKind, ParseOptions,
+    PushParser, ReadError, Value,
+};
+
+/// One of the ways of parsing a whole document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Backend {
+    /// [`parse_with`], which tokenizes the input as it parses it
+    Tokens,
+    /// [`parse_reader_with`], which tokenizes a chunk at a time
+    Reader,
+    /// [`PushParser`], fed one byte at a time
+    Push,
+    /// [`BorrowedValue`], built from the events of the pull parser
+    Borrowed,
+    Lazy,
+    Tape,
+    #[cfg(feature = "arena")]
+    Arena,
+    /// [`validate_with`], which only has validity to compare
+    Validate,
+}
+
+impl Backend {
+    pub(crate) const ALL: &'static [Backend] = &[
+        Backend::Tokens,
+        Backend::Reader,
+        Backend::Push,
+        Backend::Borrowed,
+        Backend::Lazy,
+        Backend::Tape,
+        #[cfg(feature = "arena")]
+        Backend::Arena,
+        Backend::Validate,
+    ];
+
+    /// Whether the backend accepts everything that `options` allow. The lazy
+    /// and tape backends don't have the JSON5 extensions to the grammar.
+    pub(crate) fn supports(self, options: &ParseOptions) -> bool {
+        let json5 = options.allow_comments
+            || options.allow_single_quotes
+            || options.allow_unquoted_keys
+            || options.allow_hex_numbers
+            || options.allow_leading_plus
+            || options.allow_unicode_whitespace;
+        !(json5 && matches!(self, Backend::Lazy | Backend::Tape))
+    }
+
+    /// Parses `input` as a whole document, where a valid one gives `None`
+    /// for [`Backend::Validate`]
+    pub(crate) fn parse(self, input: &str, options: &ParseOptions) -> Result<Option<Value>, Error> {
+        let value = match self {
+            Backend::Tokens => parse_with(input, options)?,
+            Backend::Reader => {
+                parse_reader_with(input.as_bytes(), options).map_err(|err| match err {
+                    ReadError::Parse(err) => err,
+                    err => panic!("reading from a slice failed: {err}"),
+                })?
+            }
+            Backend::Push => {
+                let mut parser = PushParser::with_options(options.clone());
+                for byte in input.as_bytes() {
+                    parser.feed(std::slice::from_ref(byte))?;
+                }
+                parser.finish()?
+            }
+            Backend::Borrowed => BorrowedValue::parse_with(input, options)?.to_value(),
+            Backend::Lazy => LazyValue::parse_with(input, options)?.to_value()?,
+            Backend::Tape => Tape::parse_with(input, options)?.to_value(),
+            #[cfg(feature = "arena")]
+            Backend::Arena => crate::arena::ParseArena::new()
+                .parse_with(input, options)?
+                .to_value(),
+            Backend::Validate => {
+                validate_with(input, options)?;
+                return Ok(None);
+            }
+        };
+        Ok(Some(value))
+    }
+}
+
+/// The kind of an error and the byte offset where it's located
+pub(crate) type Located = (ErrorKind, Option<usize>);
+
+/// What each backend that supports `options` gives for `input`, starting
+/// with [`Backend::Tokens`]
+pub(crate) fn parse_everywhere(
+    input: &str,
+    options: &ParseOptions,
+) -> Vec<(Backend, Result<Option<Value>, Located>)> {
+    Backend::ALL
+        .iter()
+        .filter(|backend| backend.supports(options))
+        .map(|&backend| {
+            let result = backend
+                .parse(input, options)
+                .map_err(|err| (err.kind, err.location.map(|location| location.offset)));
+            (backend, result)
+        })
+        .collect()
+}
+
+/// Checks that every backend gives the value that [`parse_with`] does, or
+/// fails with the same kind of error at the same place, returning that
+/// result
+pub(crate) fn assert_agree(input: &str, options: &ParseOptions) -> Result<Value, ErrorKind> {
+    let results = parse_everywhere(input, options);
+    let expected = results[0].1.clone();
+    if let Err((_, location)) = &expected {
+        assert!(location.is_some(), "{input:?} isn't located: {expected:?}");
+    }
+    for (backend, result) in &results[1..] {
+        let agrees = match (result, &expected) {
+            (Ok(None), Ok(_)) => true,
+            (result, expected) => result == expected,
+        };
+        assert!(agrees, "{input:?} with {backend:?}: {results:#?}");
+    }
+    expected
+        .map(|value| value.expect("parse_with gives a value"))
+        .map_err(|(kind, _)| kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_agree;
+    use crate::{ErrorKind, Number, ParseOptions, ParseProfile, Value};
+
+    fn check(inputs: &[&str], options: &ParseOptions) {
+        for input in inputs {
+            let _ = assert_agree(input, options);
+        }
+    }
+
+    /// Checks `inputs` with the options of each [`ParseProfile`]
+    fn check_profiles(inputs: &[&str]) {
+        for profile in [
+            ParseProfile::Rfc8259Strict,
+            ParseProfile::Relaxed,
+            ParseProfile::Json5,
+        ] {
+            check(inputs, &ParseOptions::new().profile(profile));
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let inputs = [
+            "0",
+            "-0",
+            "1.0",
+            "1e2",
+            "-2.5E-3",
+            "9223372036854775807",
+            "9223372036854775808",
+            "-9223372036854775809",
+            "18446744073709551616",
+            "1e400",
+            "01",
+            "1.",
+            ".5",
+            "-",
+            "1e",
+            "[1.5e+3, -0.0]",
+        ];
+        check(&inputs, &ParseOptions::default());
+
+        let u64 = Value::Number(Number::U64(9223372036854775808));
+        assert_eq!(
+            assert_agree("9223372036854775808", &ParseOptions::default()),
+            Ok(u64)
+        );
+        let options = ParseOptions {
+            preserve_number_literals: true,
+            require_exact_integers: true,
+            ..ParseOptions::default()
+        };
+        check(&inputs, &options);
+        check(&["9007199254740993", "1.50"], &options);
+    }
+
+    #[test]
+    fn escapes() {
+        let inputs = [
+            r#""\"\\\/\b\f\n\r\t""#,
+            r#""é😀""#,
+            r#""\ud800""#,
+            r#""\udc00\ud800""#,
+            r#""\x""#,
+            r#""\'""#,
+            r#""\u12""#,
+            r#""\uZZZZ""#,
+            "\"a\tb\"",
+            "\"one \\\ntwo\"",
+            r#"{"a": 1, "a": 2}"#,
+        ];
+        check_profiles(&inputs);
+
+        let relaxed = ParseOptions::new().profile(ParseProfile::Relaxed);
+        assert_eq!(assert_agree(r#""\q""#, &relaxed), Ok(Value::string("q")));
+        let strict = ParseOptions::default();
+        let invalid = ErrorKind::InvalidUnicodeEscape;
+        assert_eq!(assert_agree(r#""\u12""#, &strict), Err(invalid));
+    }
+
+    #[test]
+    fn trailing_commas_and_content() {
+        let inputs = [
+            "[1,]",
+            r#"{"a": 1,}"#,
+            "[1,,2]",
+            "[,]",
+            "{,}",
+            "[1] [2]",
+            "{} 1",
+            "1 2",
+            "[1 2]",
+            "true @",
+            "1 \"open",
+            "{} 01",
+            "[1] 01",
+            "[1] 2 @",
+            "[1] /* x",
+            "\"a\" \"\\q",
+        ];
+        check_profiles(&inputs);
+
+        let trailing = ErrorKind::TrailingContent;
+        assert_eq!(
+            assert_agree("[1] [2]", &ParseOptions::default()),
+            Err(trailing.clone())
+        );
+        // only the first token after the value is looked at, and none when
+        // trailing content is allowed
+        assert_eq!(
+            assert_agree("[1] 2 @", &ParseOptions::default()),
+            Err(trailing)
+        );
+        let relaxed = ParseOptions::new().profile(ParseProfile::Relaxed);
+        assert_eq!(assert_agree("true @", &relaxed), Ok(Value::Boolean(true)));
+    }
+
+    #[test]
+    fn extensions() {
+        let inputs = [
+            "[1/*c*/]",
+            "[1//c\n]",
+            "/*c*/ 1 //c",
+            "[1 /* c",
+            "[1 / 2]",
+            "[0x10, -0x1F]",
+            "0x",
+            "0xG",
+            "NaN",
+            "[Infinity, -Infinity]",
+            "-NaN",
+            "Inf",
+            "'a'",
+            "{'a': 'b'}",
+            "'a\"b'",
+            "'open",
+            "{a: 1, $b_2: 2}",
+            "{a b: 1}",
+            "+1",
+            "[+1.5, +0x10]",
+            "+",
+            "++1",
+            "[1\u{c}]",
+            "\u{a0}1",
+            "[1x]",
+            "1fD83D",
+            "[1] @",
+            "{} 'open",
+            "1 /",
+        ];
+        check_profiles(&inputs);
+
+        let json5 = ParseOptions::new().profile(ParseProfile::Json5);
+        let expected = Value::Array(vec![16.into(), (-16).into()]);
+        assert_eq!(assert_agree("[0x10, -0x10]", &json5), Ok(expected));
+        assert_eq!(assert_agree("'a'", &json5), Ok(Value::string("a")));
+        let strict = ParseOptions::default();
+        assert!(assert_agree("[1/*c*/]", &strict).is_err());
+    }
+
+    #[test]
+    fn structure() {
+        let inputs = [
+            "",
+            " ",
+            "\u{feff}[]",
+            "[",
+            "{",
+            "[1}",
+            r#"{"a":1]"#,
+            r#"{"a" 1}"#,
+            r#"{"a":}"#,
+            "{1: 2}",
+            "[:]",
+            "[1,",
+            r#"{"a""#,
+            "\"",
+            "tru",
+            "nul",
+            r#"{"a": [1, {"b": null}], "c": "d"}"#,
+        ];
+        check(&inputs, &ParseOptions::default());
+        check(&inputs, &ParseOptions::new().profile(ParseProfile::Relaxed));
+        check(&inputs, &ParseOptions::new().max_depth(1));
+    }
+}
@@ -0,0 +1,320 @@
+//! Sequences of JSON documents written one after another in a single input
+
+use std::io::{self, Read};
+
+use crate::parse::parse_tokens;
+use crate::tokenize::{tokenize_value, ChunkTokenizer, Token, READ_CHUNK};
+use crate::{DefaultMapKind, Error, ParseOptions, ReadError, Value};
+
+/// Iterates over the top-level values of an input made of JSON documents
+/// placed one after another, ex. `{"a":1}{"b":2} [3]`. Whitespace between
+/// documents is optional, except where it's needed to separate them, such as
+/// between two numbers.
+///
+/// Each document is tokenized only when the iterator reaches it. The first
+/// error ends the iteration, since it's unknown where the next document
+/// would start.
+pub struct Documents<'a> {
+    input: &'a str,
+    options: ParseOptions,
+    /// Position in `input` after the last document
+    offset: usize,
+    failed: bool,
+}
+
+/// Parses the documents in `input` one at a time, see [`Documents`]
+pub fn parse_documents(input: &str) -> Documents<'_> {
+    Documents::new(input, ParseOptions::default())
+}
+
+impl<'a> Documents<'a> {
+    pub fn new(input: &'a str, options: ParseOptions) -> Self {
+        Self {
+            input,
+            options,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    /// Byte offset in the input just after the last document returned, which
+    /// is 0 before any have been. It doesn't move past a document that failed
+    /// to parse.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The part of the input after [`byte_offset`](Self::byte_offset)
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.offset..]
+    }
+
+    fn next_document(&mut self) -> Result<Option<Value>, Error> {
+        let mut end = self.offset;
+        let Some((tokens, offsets)) = tokenize_value(self.input, &mut end, &self.options)? else {
+            return Ok(None);
+        };
+        let mut index = 0;
+        let value = parse_tokens(&tokens, &mut index, &self.options)
+            .map_err(|err| err.locate(self.input, || offsets.get(index).copied().unwrap_or(end)))?;
+
+        self.offset = end;
+        Ok(Some(value))
+    }
+}
+
+impl Iterator for Documents<'_> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.next_document().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Reads successive top-level values from `reader` until it ends, such as
+/// the messages of a protocol that sends JSON documents over a socket.
+///
+/// Documents are separated the same way as for [`Documents`], and each is
+/// returned as soon as the text that completes it has been read, without
+/// waiting for the next one. A number at the top level can't be known to be
+/// complete until something follows it, though, so a protocol sending bare
+/// numbers should end each one with whitespace.
+///
+/// The first error ends the iteration.
+pub struct ValueStream<R> {
+    reader: R,
+    tokenizer: ChunkTokenizer,
+    chunk: Vec<u8>,
+    options: ParseOptions,
+    /// Number of buffered tokens that have been checked for the end of the
+    /// first document
+    scanned: usize,
+    depth: usize,
+    eof: bool,
+    failed: bool,
+}
+
+impl<R: Read> ValueStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            tokenizer: ChunkTokenizer::documents(options.clone()),
+            chunk: vec![0; READ_CHUNK],
+            options,
+            scanned: 0,
+            depth: 0,
+            eof: false,
+            failed: false,
+        }
+    }
+
+    /// Consumes the stream, returning the reader. Text that was read but not
+    /// yet returned as part of a value is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn next_value(&mut self) -> Result<Option<Value>, ReadError> {
+        loop {
+            if let Some(len) = self.document_len() {
+                let mut index = 0;
+                let value = parse_tokens(&self.tokenizer.tokens[..len], &mut index, &self.options)
+                    .map_err(|err| self.tokenizer.locate_parsed(err, index))?;
+                self.tokenizer.tokens.drain(..len);
+                self.tokenizer.locations.drain(..len);
+                self.scanned = 0;
+                return Ok(Some(value));
+            }
+            if self.eof {
+                if self.tokenizer.tokens.is_empty() {
+                    return Ok(None);
+                }
+                // the document is missing its closing brackets, which the
+                // parser reports as it does for a whole input
+                let mut index = 0;
+                let err = parse_tokens::<DefaultMapKind>(
+                    &self.tokenizer.tokens,
+                    &mut index,
+                    &self.options,
+                )
+                .expect_err("the document isn't closed");
+                return Err(self.tokenizer.locate_parsed(err, index).into());
+            }
+
+            let len = match self.reader.read(&mut self.chunk) {
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(ReadError::Io(err)),
+            };
+            self.eof = len == 0;
+            self.tokenizer.push(&self.chunk[..len])?;
+        }
+    }
+
+    /// Number of buffered tokens that make up the first document, if all of
+    /// them have been read
+    fn document_len(&mut self) -> Option<usize> {
+        let tokens = &self.tokenizer.tokens;
+        while self.scanned < tokens.len() {
+            match tokens[self.scanned] {
+                Token::LeftBrace | Token::LeftBracket => self.depth += 1,
+                Token::RightBrace | Token::RightBracket => {
+                    self.depth = self.depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+            self.scanned += 1;
+            if self.depth == 0 {
+                return Some(self.scanned);
+            }
+        }
+        None
+    }
+}
+
+impl<R: Read> Iterator for ValueStream<R> {
+    type Item = Result<Value, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.next_value().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use super::{parse_documents, ValueStream};
+    use crate::{Error, ErrorKind, Location, ReadError, TokenParseError, Value};
+
+    #[test]
+    fn parses_concatenated_documents() {
+        let values: Vec<_> = parse_documents(r#"{"a":1}{"b":2} [3]"#).collect();
+
+        let expected = [
+            Ok(Value::object([("a", 1.into())])),
+            Ok(Value::object([("b", 2.into())])),
+            Ok(Value::Array(vec![3.into()])),
+        ];
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn whitespace_separated_scalars() {
+        let values: Vec<_> = parse_documents(" 1\n\"é\"\ttrue null ").collect();
+
+        let expected = [
+            Ok(1.into()),
+            Ok(Value::string("é")),
+            Ok(Value::Boolean(true)),
+            Ok(Value::Null),
+        ];
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn tracks_byte_offsets() {
+        let input = r#"["é"] {"k": null}  "#;
+        let mut documents = parse_documents(input);
+
+        assert_eq!(documents.byte_offset(), 0);
+        documents.next().unwrap().unwrap();
+        assert_eq!(documents.byte_offset(), 6);
+        documents.next().unwrap().unwrap();
+        assert_eq!(documents.byte_offset(), 18);
+        assert_eq!(documents.remaining(), "  ");
+        assert!(documents.next().is_none());
+    }
+
+    #[test]
+    fn stops_at_error() {
+        let mut documents = parse_documents("[1] [2");
+
+        assert_eq!(documents.next(), Some(Ok(Value::Array(vec![1.into()]))));
+        assert_eq!(
+            documents.next(),
+            Some(Err(TokenParseError::UnclosedBracket.locate("[1] [2", || 6)))
+        );
+        assert_eq!(documents.remaining(), " [2");
+        assert_eq!(documents.next(), None);
+    }
+
+    #[test]
+    fn streams_values_from_reader() {
+        let input = "{\"id\": 1}\n[\"é\"] 2 true\n";
+        let values: Vec<_> = ValueStream::new(input.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+
+        let expected = [
+            Value::object([("id", 1.into())]),
+            Value::Array(vec![Value::string("é")]),
+            2.into(),
+            Value::Boolean(true),
+        ];
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn returns_values_before_more_input_arrives() {
+        /// Returns one message per read, then fails as if the connection
+        /// was still open and had nothing more to send
+        struct Messages(Vec<&'static str>);
+
+        impl Read for Messages {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                let message = self.0.remove(0);
+                buf[..message.len()].copy_from_slice(message.as_bytes());
+                Ok(message.len())
+            }
+        }
+
+        let mut stream = ValueStream::new(Messages(vec![r#"{"a": "#, "[1]}", r#"{"b": null}"#]));
+
+        assert_eq!(
+            stream.next().unwrap().unwrap(),
+            Value::object([("a", Value::Array(vec![1.into()]))])
+        );
+        assert_eq!(
+            stream.next().unwrap().unwrap(),
+            Value::object([("b", Value::Null)])
+        );
+        assert!(matches!(stream.next(), Some(Err(ReadError::Io(_)))));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn stream_ends_within_document() {
+        let mut stream = ValueStream::new(&b"[1] [2"[..]);
+
+        assert_eq!(
+            stream.next().unwrap().unwrap(),
+            Value::Array(vec![1.into()])
+        );
+        assert!(matches!(
+            stream.next(),
+            Some(Err(ReadError::Parse(Error {
+                kind: ErrorKind::UnclosedArray,
+                location: Some(Location { offset: 6, .. }),
+                ..
+            })))
+        ));
+    }
+}
@@ -0,0 +1,407 @@
+//! Detection and decoding of the Unicode encodings that JSON text may use
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// The encoding of JSON text, see [`detect`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, the only encoding allowed by RFC 8259 for text exchanged
+    /// between systems
+    Utf8,
+    /// UTF-16, big endian
+    Utf16Be,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-32, big endian
+    Utf32Be,
+    /// UTF-32, little endian
+    Utf32Le,
+}
+
+/// The input was not valid in its detected encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingError {
+    /// The encoding that the input was detected to be in
+    pub encoding: Encoding,
+
+    /// Byte offset of the first invalid code unit, counting any byte order
+    /// mark
+    pub offset: usize,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf32Be => "UTF-32BE",
+            Encoding::Utf32Le => "UTF-32LE",
+        })
+    }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} at byte {}", self.encoding, self.offset)
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Detects the encoding of JSON text, returning it along with the length of
+/// the byte order mark (BOM) to skip, if any.
+///
+/// A BOM is used when there is one. Otherwise, because JSON text starts with
+/// two ASCII characters (RFC 4627, section 3), the pattern of zero bytes in
+/// the first four bytes tells the encodings apart:
+///
+/// ```text
+/// 00 00 00 xx  UTF-32BE
+/// 00 xx 00 xx  UTF-16BE
+/// xx 00 00 00  UTF-32LE
+/// xx 00 xx 00  UTF-16LE
+/// xx xx xx xx  UTF-8
+/// ```
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0x00, 0x00, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+        [0xFF, 0xFE, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        [0x00, 0x00, 0x00, _, ..] => (Encoding::Utf32Be, 0),
+        [0x00, _, 0x00, _, ..] => (Encoding::Utf16Be, 0),
+        [_, 0x00, 0x00, 0x00, ..] => (Encoding::Utf32Le, 0),
+        [_, 0x00, _, 0x00, ..] => (Encoding::Utf16Le, 0),
+        // shorter inputs only distinguish UTF-16
+        [0x00, _] => (Encoding::Utf16Be, 0),
+        [_, 0x00] => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+/// Decodes JSON text in any of the encodings that [`detect`] recognizes,
+/// without the byte order mark. UTF-8 input is borrowed rather than copied.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, EncodingError> {
+    let (encoding, bom) = detect(bytes);
+    let error = |offset: usize| EncodingError {
+        encoding,
+        offset: bom + offset,
+    };
+    let content = &bytes[bom..];
+
+    match encoding {
+        Encoding::Utf8 => std::str::from_utf8(content)
+            .map(Cow::Borrowed)
+            .map_err(|err| error(err.valid_up_to())),
+        Encoding::Utf16Be | Encoding::Utf16Le => {
+            let units = content.chunks(2).map(|pair| match (encoding, pair) {
+                (Encoding::Utf16Be, &[a, b]) => Some(u16::from_be_bytes([a, b])),
+                (_, &[a, b]) => Some(u16::from_le_bytes([a, b])),
+                _ => None,
+            });
+            let mut decoded = String::with_capacity(content.len() / 2);
+            let mut offset = 0;
+            // a trailing odd byte ends the units early and is reported below
+            for ch in char::decode_utf16(units.map_while(|unit| unit)) {
+                let ch = ch.map_err(|_| error(offset))?;
+                decoded.push(ch);
+                offset += ch.len_utf16() * 2;
+            }
+            if offset < content.len() {
+                return Err(error(offset));
+            }
+            Ok(Cow::Owned(decoded))
+        }
+        Encoding::Utf32Be | Encoding::Utf32Le => {
+            let mut decoded = String::with_capacity(content.len() / 4);
+            for (i, quad) in content.chunks(4).enumerate() {
+                let unit = match (encoding, quad) {
+                    (Encoding::Utf32Be, &[a, b, c, d]) => u32::from_be_bytes([a, b, c, d]),
+                    (_, &[a, b, c, d]) => u32::from_le_bytes([a, b, c, d]),
+                    _ => return Err(error(i * 4)),
+                };
+                decoded.push(char::from_u32(unit).ok_or_else(|| error(i * 4))?);
+            }
+            Ok(Cow::Owned(decoded))
+        }
+    }
+}
+
+/// Decodes JSON text into UTF-8 a chunk at a time, for input that arrives in
+/// pieces or that shouldn't be transcoded all at once.
+///
+/// The encoding is detected from the first four bytes like [`decode`], and
+/// a byte order mark is skipped. A chunk may end partway through a code unit
+/// or a surrogate pair, which is finished by the next chunk.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    encoding: Option<Encoding>,
+    /// Bytes that can't be decoded until more input arrives
+    pending: Vec<u8>,
+    /// Offset in the input of the first pending byte
+    offset: usize,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The detected encoding, once enough input has been seen
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Decodes `bytes`, appending the text to `out`. An empty chunk marks
+    /// the end of the input, when anything left unfinished is an error.
+    pub fn decode(&mut self, bytes: &[u8], out: &mut String) -> Result<(), EncodingError> {
+        let eof = bytes.is_empty();
+        self.pending.extend_from_slice(bytes);
+
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None if self.pending.len() < 4 && !eof => return Ok(()),
+            None => {
+                let (encoding, bom) = detect(&self.pending);
+                self.encoding = Some(encoding);
+                self.consume(bom);
+                encoding
+            }
+        };
+        let error = |offset: usize| EncodingError { encoding, offset };
+
+        let used = match encoding {
+            Encoding::Utf8 => match std::str::from_utf8(&self.pending) {
+                Ok(text) => {
+                    out.push_str(text);
+                    text.len()
+                }
+                Err(err) if err.error_len().is_none() && !eof => {
+                    let valid = &self.pending[..err.valid_up_to()];
+                    out.push_str(std::str::from_utf8(valid).expect("valid up to here"));
+                    valid.len()
+                }
+                Err(err) => return Err(error(self.offset + err.valid_up_to())),
+            },
+            Encoding::Utf16Be | Encoding::Utf16Le => {
+                let mut units: Vec<u16> = self
+                    .pending
+                    .chunks_exact(2)
+                    .map(|pair| match encoding {
+                        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+                        _ => u16::from_le_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+                // a high surrogate may be paired by the next chunk
+                if !eof
+                    && units
+                        .last()
+                        .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+                {
+                    units.pop();
+                }
+                let mut used = 0;
+                for ch in char::decode_utf16(units) {
+                    let ch = ch.map_err(|_| error(self.offset + used))?;
+                    out.push(ch);
+                    used += ch.len_utf16() * 2;
+                }
+                used
+            }
+            Encoding::Utf32Be | Encoding::Utf32Le => {
+                let mut used = 0;
+                for quad in self.pending.chunks_exact(4) {
+                    let unit = match encoding {
+                        Encoding::Utf32Be => {
+                            u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]])
+                        }
+                        _ => u32::from_le_bytes([quad[0], quad[1], quad[2], quad[3]]),
+                    };
+                    out.push(char::from_u32(unit).ok_or_else(|| error(self.offset + used))?);
+                    used += 4;
+                }
+                used
+            }
+        };
+        self.consume(used);
+
+        if eof && !self.pending.is_empty() {
+            return Err(error(self.offset));
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.pending.drain(..len);
+        self.offset += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, detect, Decoder, Encoding, EncodingError};
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    fn utf32(text: &str, big_endian: bool) -> Vec<u8> {
+        text.chars()
+            .flat_map(|ch| {
+                if big_endian {
+                    u32::from(ch).to_be_bytes()
+                } else {
+                    u32::from(ch).to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_without_bom() {
+        assert_eq!(detect(b"[1]"), (Encoding::Utf8, 0));
+        assert_eq!(detect(&utf16("[1]", true)), (Encoding::Utf16Be, 0));
+        assert_eq!(detect(&utf16("[1]", false)), (Encoding::Utf16Le, 0));
+        assert_eq!(detect(&utf32("[1]", true)), (Encoding::Utf32Be, 0));
+        assert_eq!(detect(&utf32("[1]", false)), (Encoding::Utf32Le, 0));
+        assert_eq!(detect(&utf16("1", false)), (Encoding::Utf16Le, 0));
+        assert_eq!(detect(b""), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn detects_bom() {
+        assert_eq!(detect(b"\xEF\xBB\xBF[]"), (Encoding::Utf8, 3));
+        assert_eq!(detect(&utf16("\u{FEFF}[]", true)), (Encoding::Utf16Be, 2));
+        assert_eq!(detect(&utf16("\u{FEFF}[]", false)), (Encoding::Utf16Le, 2));
+        assert_eq!(detect(&utf32("\u{FEFF}[]", true)), (Encoding::Utf32Be, 4));
+        assert_eq!(detect(&utf32("\u{FEFF}[]", false)), (Encoding::Utf32Le, 4));
+    }
+
+    #[test]
+    fn decodes_each_encoding() {
+        let text = r#"{"emoji": "😀", "é": 1}"#;
+
+        assert_eq!(decode(text.as_bytes()).unwrap(), text);
+        assert_eq!(decode(&utf16(text, true)).unwrap(), text);
+        assert_eq!(
+            decode(&utf16(&format!("\u{FEFF}{text}"), false)).unwrap(),
+            text
+        );
+        assert_eq!(decode(&utf32(text, true)).unwrap(), text);
+        assert_eq!(
+            decode(&utf32(&format!("\u{FEFF}{text}"), false)).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn reports_invalid_offsets() {
+        assert_eq!(
+            decode(b"\xEF\xBB\xBF[\"\xFF\"]"),
+            Err(EncodingError {
+                encoding: Encoding::Utf8,
+                offset: 5
+            })
+        );
+
+        // an unpaired high surrogate
+        let mut bytes = utf16("[\"", false);
+        bytes.extend([0x3D, 0xD8]);
+        bytes.extend(utf16("\"]", false));
+        assert_eq!(
+            decode(&bytes),
+            Err(EncodingError {
+                encoding: Encoding::Utf16Le,
+                offset: 4
+            })
+        );
+
+        let mut bytes = utf16("[1]", true);
+        bytes.push(0);
+        assert_eq!(
+            decode(&bytes),
+            Err(EncodingError {
+                encoding: Encoding::Utf16Be,
+                offset: 6
+            })
+        );
+    }
+
+    fn decode_in_chunks(bytes: &[u8], size: usize) -> Result<String, EncodingError> {
+        let mut decoder = Decoder::new();
+        let mut text = String::new();
+        for chunk in bytes.chunks(size) {
+            decoder.decode(chunk, &mut text)?;
+        }
+        decoder.decode(&[], &mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn decodes_chunks_split_anywhere() {
+        let text = r#"{"emoji": "😀", "é": 1}"#;
+        let inputs = [
+            text.as_bytes().to_vec(),
+            utf16(&format!("\u{FEFF}{text}"), true),
+            utf16(text, false),
+            utf32(text, true),
+            utf32(&format!("\u{FEFF}{text}"), false),
+        ];
+
+        for input in inputs {
+            for size in 1..=input.len() {
+                assert_eq!(decode_in_chunks(&input, size).unwrap(), text, "{size}");
+            }
+        }
+        assert_eq!(decode_in_chunks(b"1", 1).unwrap(), "1");
+        assert_eq!(decode_in_chunks(b"", 1).unwrap(), "");
+    }
+
+    #[test]
+    fn decoder_reports_invalid_offsets() {
+        let mut bytes = utf16("[\"", false);
+        bytes.extend([0x3D, 0xD8]);
+        bytes.extend(utf16("\"]", false));
+        for size in 1..=bytes.len() {
+            assert_eq!(
+                decode_in_chunks(&bytes, size),
+                Err(EncodingError {
+                    encoding: Encoding::Utf16Le,
+                    offset: 4
+                })
+            );
+        }
+
+        // a high surrogate at the end of the input
+        let mut bytes = utf16("\u{FEFF}\"", true);
+        bytes.extend([0xD8, 0x3D]);
+        assert_eq!(
+            decode_in_chunks(&bytes, 3),
+            Err(EncodingError {
+                encoding: Encoding::Utf16Be,
+                offset: 4
+            })
+        );
+
+        let mut bytes = utf32("[1]", true);
+        bytes.push(0);
+        assert_eq!(
+            decode_in_chunks(&bytes, 5),
+            Err(EncodingError {
+                encoding: Encoding::Utf32Be,
+                offset: 12
+            })
+        );
+    }
+}
@@ -0,0 +1,831 @@
+//! The error returned by every parsing function, see [`Error`]
+
+use std::fmt::{self, Write};
+use std::num::ParseFloatError;
+
+use crate::encoding::EncodingError;
+use crate::parse::TokenParseError;
+use crate::tokenize::{first_token, TokenizeError};
+
+/// The input could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    /// What was wrong with the input
+    pub kind: ErrorKind,
+
+    /// Where in the input the problem is, when that's known. Every error
+    /// found while parsing is located, at the start of the token it was
+    /// found at unless a more precise spot is known, or at the end of the
+    /// input when it ended too soon. Errors that aren't about input text,
+    /// such as those converting a [`Value`](crate::Value) to a type, aren't.
+    pub location: Option<Location>,
+
+    /// A hint for fixing the common mistake that probably caused the error,
+    /// ex. "object keys must be in double quotes"
+    pub suggestion: Option<&'static str>,
+
+    /// What was found where a colon, comma, value or key was expected, ex.
+    /// `` `]` `` or `end of input`, when the error is located
+    pub found: Option<String>,
+}
+
+/// A position in the input text, where the default is its start.
+///
+/// The fields count from 0, as offsets do and as language servers expect,
+/// while [`line_number`](Self::line_number), [`column_number`](Self::column_number)
+/// and the `Display` output count from 1, as editors show them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the input
+    pub offset: usize,
+
+    /// Line number, counting from 0
+    pub line: usize,
+
+    /// Column number in characters, counting from 0
+    pub column: usize,
+}
+
+impl Location {
+    /// The location of the byte `offset` in `input`
+    pub fn new(input: &str, offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            offset,
+            line: before.matches('\n').count(),
+            column: before[line_start..].chars().count(),
+        }
+    }
+
+    /// Line number, counting from 1
+    pub fn line_number(&self) -> usize {
+        self.line + 1
+    }
+
+    /// Column number in characters, counting from 1
+    pub fn column_number(&self) -> usize {
+        self.column + 1
+    }
+
+    /// The location of the byte `offset` in `input`, counting from this
+    /// location, which is at or before it
+    pub(crate) fn advance(self, input: &str, offset: usize) -> Self {
+        self.within(&input[self.offset..], offset - self.offset)
+    }
+
+    /// The location of the byte `offset` of `text`, which starts at this
+    /// location
+    pub(crate) fn within(self, text: &str, offset: usize) -> Self {
+        let between = Location::new(text, offset);
+        Self {
+            offset: self.offset + offset,
+            line: self.line + between.line,
+            column: match between.line {
+                0 => self.column + between.column,
+                _ => between.column,
+            },
+        }
+    }
+}
+
+/// One of the problems that can stop a parse
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// The input isn't valid in the encoding it was detected to be in
+    Encoding(EncodingError),
+
+    /// The input has no value, only whitespace or comments if anything
+    EmptyInput,
+
+    /// The input ended partway through a value
+    UnexpectedEof,
+
+    /// A character that can't start a token
+    UnexpectedCharacter(char),
+
+    /// A string was never closed
+    UnclosedString,
+
+    /// A `/*` comment was never closed
+    UnclosedComment,
+
+    /// An array was never closed
+    UnclosedArray,
+
+    /// An object was never closed
+    UnclosedObject,
+
+    /// Something that starts like `true`, `false` or `null` but isn't
+    InvalidLiteral,
+
+    /// A number that couldn't be converted
+    InvalidNumber(ParseFloatError),
+
+    /// A number has no digits before its fraction or exponent, ex. `-`
+    MissingIntegerDigits,
+
+    /// A number's integer part has a zero followed by more digits, ex. `01`
+    LeadingZero,
+
+    /// A number's decimal point isn't followed by digits, ex. `1.`
+    MissingFractionDigits,
+
+    /// A number's exponent has no digits, ex. `1e` or `1e+`
+    MissingExponentDigits,
+
+    /// A number runs straight into a character that can't follow it,
+    /// ex. the second `-` of `1-2`
+    UnexpectedCharInNumber(char),
+
+    /// An integer can't be represented exactly as an `f64`, see
+    /// [`ParseOptions::require_exact_integers`](crate::ParseOptions::require_exact_integers)
+    PrecisionLoss(String),
+
+    /// A control character (U+0000 to U+001F) that wasn't escaped in a
+    /// string
+    ControlCharacter(char),
+
+    /// A string ends with a backslash
+    UnfinishedEscape,
+
+    /// A backslash followed by a character that isn't a valid escape, where
+    /// `offset` is the byte offset of the backslash in the input
+    InvalidEscape { escape: char, offset: usize },
+
+    /// A `\u` escape isn't followed by four hexadecimal digits
+    InvalidUnicodeEscape,
+
+    /// A `\u` escape of a UTF-16 surrogate that isn't part of a pair, where
+    /// `offset` is the byte offset of its backslash in the input,
+    /// see [`ParseOptions::lone_surrogates`](crate::ParseOptions::lone_surrogates)
+    LoneSurrogate { surrogate: u16, offset: usize },
+
+    /// An object key isn't followed by a colon
+    ExpectedColon,
+
+    /// Two elements of an array or object aren't separated by a comma
+    ExpectedComma,
+
+    /// A value is missing, ex. after a colon
+    ExpectedValue,
+
+    /// An object key is missing, or isn't a string
+    ExpectedKey,
+
+    /// A comma after the last element of an array or object, see
+    /// [`ParseOptions::allow_trailing_commas`](crate::ParseOptions::allow_trailing_commas)
+    TrailingComma,
+
+    /// A key appeared more than once in the same object, see
+    /// [`ParseOptions::duplicate_keys`](crate::ParseOptions::duplicate_keys)
+    DuplicateKey(String),
+
+    /// There is more than whitespace after the top-level value
+    TrailingContent,
+
+    /// The input starts with a byte order mark, see
+    /// [`ParseOptions::reject_byte_order_mark`](crate::ParseOptions::reject_byte_order_mark)
+    ByteOrderMark,
+
+    /// The parse was stopped by its [`CancelToken`](crate::CancelToken)
+    Cancelled,
+
+    /// The parsed value would use more memory than
+    /// [`ParseOptions::max_memory`](crate::ParseOptions::max_memory) allows
+    MemoryLimitExceeded,
+
+    /// A string or object key is longer than
+    /// [`ParseOptions::max_string_length`](crate::ParseOptions::max_string_length)
+    /// allows
+    StringTooLong,
+
+    /// An array has more elements than
+    /// [`ParseOptions::max_array_length`](crate::ParseOptions::max_array_length)
+    /// allows
+    TooManyElements,
+
+    /// An object has more entries than
+    /// [`ParseOptions::max_object_entries`](crate::ParseOptions::max_object_entries)
+    /// allows
+    TooManyEntries,
+
+    /// Arrays and objects are nested deeper than
+    /// [`ParseOptions::max_depth`](crate::ParseOptions::max_depth) allows
+    TooDeep,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            suggestion: suggest(&kind, ""),
+            kind,
+            location: None,
+            found: None,
+        }
+    }
+
+    /// Sets the location to the byte `offset` in `input`
+    pub(crate) fn at(mut self, input: &str, offset: Option<usize>) -> Self {
+        self.location = offset.map(|offset| Location::new(input, offset));
+        if let Some(offset) = offset {
+            let rest = &input[offset.min(input.len())..];
+            self.suggest_from(rest);
+            self.found = found(&self.kind, rest);
+        }
+        self
+    }
+
+    /// The kind of error and what was found instead, if known, ex. ``expected
+    /// `,` between elements but found `2` ``
+    pub(crate) fn message(&self) -> String {
+        match &self.found {
+            Some(found) => format!("{} but found {found}", self.kind),
+            None => self.kind.to_string(),
+        }
+    }
+
+    /// Looks for a suggestion in the `rest` of the input from where the
+    /// error is
+    pub(crate) fn suggest_from(&mut self, rest: &str) {
+        self.suggestion = self.suggestion.or_else(|| suggest(&self.kind, rest));
+    }
+}
+
+/// A hint for fixing a common mistake that causes an error of this `kind`,
+/// given the `rest` of the input from where the error is, if that's known
+fn suggest(kind: &ErrorKind, rest: &str) -> Option<&'static str> {
+    match kind {
+        ErrorKind::UnexpectedCharacter('\'') => Some("strings must be in double quotes"),
+        ErrorKind::UnexpectedCharacter('=') => Some("use `:` between a key and its value"),
+        ErrorKind::TrailingComma => Some("remove the comma after the last element"),
+        // a bare word, such as a key or a misspelled literal
+        ErrorKind::UnexpectedCharacter(_) | ErrorKind::InvalidLiteral => {
+            let length = rest
+                .find(|ch: char| !ch.is_alphanumeric() && ch != '_' && ch != '$')
+                .unwrap_or(rest.len());
+            let (word, after) = rest.split_at(length);
+            if word.is_empty() {
+                return None;
+            }
+            if after.trim_start().starts_with(':') {
+                return Some("object keys must be in double quotes");
+            }
+            match word.to_ascii_lowercase().as_str() {
+                "true" => Some("did you mean `true`?"),
+                "false" => Some("did you mean `false`?"),
+                "null" | "none" | "nil" => Some("did you mean `null`?"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Describes the token at the start of the `rest` of the input, for an error
+/// of this `kind` about what was expected there
+fn found(kind: &ErrorKind, rest: &str) -> Option<String> {
+    if !matches!(
+        kind,
+        ErrorKind::ExpectedColon
+            | ErrorKind::ExpectedComma
+            | ErrorKind::ExpectedValue
+            | ErrorKind::ExpectedKey
+    ) {
+        return None;
+    }
+    if rest.is_empty() {
+        return Some("end of input".to_string());
+    }
+    let text = match first_token(rest) {
+        Some(token) => token.to_string(),
+        // not a token, so the text up to where the next one could start
+        None => {
+            let length = rest
+                .find(|ch: char| ch.is_whitespace() || "{}[],:\"'".contains(ch))
+                .unwrap_or(rest.len())
+                .max(rest.chars().next().map_or(0, char::len_utf8));
+            rest[..length].to_string()
+        }
+    };
+    // a long string or number is cut short
+    let found = match text.char_indices().nth(20) {
+        Some((end, _)) => format!("`{}...`", &text[..end]),
+        None => format!("`{text}`"),
+    };
+    Some(found)
+}
+
+impl TokenizeError {
+    /// Byte offset in the tokenized text of the problem, given the byte
+    /// `start` of the token it was found in and the `end` of the text. Most
+    /// errors are reported at the start of their token.
+    pub(crate) fn offset(&self, start: usize, end: usize) -> usize {
+        match *self {
+            TokenizeError::InvalidUnicodeEscape { offset }
+            | TokenizeError::UnclosedComment { offset }
+            | TokenizeError::ControlCharacter { offset, .. } => offset,
+            TokenizeError::UnexpectedEof => end,
+            TokenizeError::ByteOrderMark => 0,
+            _ => start,
+        }
+    }
+
+    /// Converts the error, locating it in the `input` that was tokenized,
+    /// given the byte `start` of the token it was found in
+    pub(crate) fn locate(self, input: &str, start: usize) -> Error {
+        let offset = self.offset(start, input.len());
+        Error::from(self).at(input, Some(offset))
+    }
+}
+
+impl TokenParseError {
+    /// Converts the error, locating it in the `input` that was parsed.
+    /// `token_offset` finds the byte offset of the token the error was found
+    /// at, or of the end of the input when the tokens ran out.
+    pub(crate) fn locate(mut self, input: &str, token_offset: impl FnOnce() -> usize) -> Error {
+        let offset = match &mut self {
+            TokenParseError::UnexpectedTrailingTokens { offset } => *offset,
+            // escapes are found within the string's text, after its quote
+            TokenParseError::InvalidEscape { offset, .. }
+            | TokenParseError::InvalidHexValue { offset }
+            | TokenParseError::LoneSurrogate { offset, .. } => {
+                *offset += token_offset() + 1;
+                *offset
+            }
+            _ => token_offset(),
+        };
+        Error::from(self).at(input, Some(offset))
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<EncodingError> for Error {
+    fn from(err: EncodingError) -> Self {
+        Self::new(ErrorKind::Encoding(err))
+    }
+}
+
+impl From<TokenizeError> for Error {
+    fn from(err: TokenizeError) -> Self {
+        Self::new(match err {
+            TokenizeError::CharNotRecognized(ch) => ErrorKind::UnexpectedCharacter(ch),
+            TokenizeError::ParseNumberError(err) => ErrorKind::InvalidNumber(err),
+            TokenizeError::UnclosedQuotes => ErrorKind::UnclosedString,
+            TokenizeError::UnfinishedLiteralValue => ErrorKind::InvalidLiteral,
+            TokenizeError::UnexpectedEof => ErrorKind::UnexpectedEof,
+            TokenizeError::MissingIntegerDigits => ErrorKind::MissingIntegerDigits,
+            TokenizeError::LeadingZero => ErrorKind::LeadingZero,
+            TokenizeError::MissingFractionDigits => ErrorKind::MissingFractionDigits,
+            TokenizeError::MissingExponentDigits => ErrorKind::MissingExponentDigits,
+            TokenizeError::InvalidUnicodeEscape { .. } => ErrorKind::InvalidUnicodeEscape,
+            TokenizeError::ByteOrderMark => ErrorKind::ByteOrderMark,
+            TokenizeError::PrecisionLoss(n) => ErrorKind::PrecisionLoss(n),
+            TokenizeError::UnexpectedCharInNumber(ch) => ErrorKind::UnexpectedCharInNumber(ch),
+            TokenizeError::UnclosedComment { .. } => ErrorKind::UnclosedComment,
+            TokenizeError::ControlCharacter { character, .. } => {
+                ErrorKind::ControlCharacter(character)
+            }
+            TokenizeError::Cancelled => ErrorKind::Cancelled,
+            TokenizeError::TooDeep => ErrorKind::TooDeep,
+        })
+    }
+}
+
+impl From<TokenParseError> for Error {
+    fn from(err: TokenParseError) -> Self {
+        Self::new(match err {
+            TokenParseError::EmptyInput => ErrorKind::EmptyInput,
+            TokenParseError::EarlyEOF => ErrorKind::UnexpectedEof,
+            TokenParseError::UnclosedBracket => ErrorKind::UnclosedArray,
+            TokenParseError::UnclosedBrace => ErrorKind::UnclosedObject,
+            TokenParseError::UnfinishedEscape => ErrorKind::UnfinishedEscape,
+            TokenParseError::InvalidHexValue { .. } => ErrorKind::InvalidUnicodeEscape,
+            TokenParseError::ExpectedColon => ErrorKind::ExpectedColon,
+            TokenParseError::ExpectedComma => ErrorKind::ExpectedComma,
+            TokenParseError::ExpectedValue => ErrorKind::ExpectedValue,
+            TokenParseError::ExpectedProperty => ErrorKind::ExpectedKey,
+            TokenParseError::TrailingComma => ErrorKind::TrailingComma,
+            TokenParseError::DuplicateKey(key) => ErrorKind::DuplicateKey(key),
+            TokenParseError::InvalidEscape { escape, offset } => {
+                ErrorKind::InvalidEscape { escape, offset }
+            }
+            TokenParseError::LoneSurrogate { surrogate, offset } => {
+                ErrorKind::LoneSurrogate { surrogate, offset }
+            }
+            TokenParseError::UnexpectedTrailingTokens { .. } => ErrorKind::TrailingContent,
+            TokenParseError::MemoryLimitExceeded => ErrorKind::MemoryLimitExceeded,
+            TokenParseError::StringTooLong => ErrorKind::StringTooLong,
+            TokenParseError::TooManyElements => ErrorKind::TooManyElements,
+            TokenParseError::TooManyEntries => ErrorKind::TooManyEntries,
+        })
+    }
+}
+
+impl ErrorKind {
+    /// A name for this kind of error that stays the same between versions,
+    /// for tools to match on, ex. `unexpected-character`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::Encoding(_) => "encoding",
+            ErrorKind::EmptyInput => "empty-input",
+            ErrorKind::UnexpectedEof => "unexpected-eof",
+            ErrorKind::UnexpectedCharacter(_) => "unexpected-character",
+            ErrorKind::UnclosedString => "unclosed-string",
+            ErrorKind::UnclosedComment => "unclosed-comment",
+            ErrorKind::UnclosedArray => "unclosed-array",
+            ErrorKind::UnclosedObject => "unclosed-object",
+            ErrorKind::InvalidLiteral => "invalid-literal",
+            ErrorKind::InvalidNumber(_) => "invalid-number",
+            ErrorKind::MissingIntegerDigits => "missing-integer-digits",
+            ErrorKind::LeadingZero => "leading-zero",
+            ErrorKind::MissingFractionDigits => "missing-fraction-digits",
+            ErrorKind::MissingExponentDigits => "missing-exponent-digits",
+            ErrorKind::UnexpectedCharInNumber(_) => "unexpected-character-in-number",
+            ErrorKind::PrecisionLoss(_) => "precision-loss",
+            ErrorKind::ControlCharacter(_) => "control-character",
+            ErrorKind::UnfinishedEscape => "unfinished-escape",
+            ErrorKind::InvalidEscape { .. } => "invalid-escape",
+            ErrorKind::InvalidUnicodeEscape => "invalid-unicode-escape",
+            ErrorKind::LoneSurrogate { .. } => "lone-surrogate",
+            ErrorKind::ExpectedColon => "expected-colon",
+            ErrorKind::ExpectedComma => "expected-comma",
+            ErrorKind::ExpectedValue => "expected-value",
+            ErrorKind::ExpectedKey => "expected-key",
+            ErrorKind::TrailingComma => "trailing-comma",
+            ErrorKind::DuplicateKey(_) => "duplicate-key",
+            ErrorKind::TrailingContent => "trailing-content",
+            ErrorKind::ByteOrderMark => "byte-order-mark",
+            ErrorKind::Cancelled => "cancelled",
+            ErrorKind::MemoryLimitExceeded => "memory-limit-exceeded",
+            ErrorKind::StringTooLong => "string-too-long",
+            ErrorKind::TooManyElements => "too-many-elements",
+            ErrorKind::TooManyEntries => "too-many-entries",
+            ErrorKind::TooDeep => "too-deep",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Encoding(err) => write!(f, "{err}"),
+            ErrorKind::EmptyInput => f.write_str("input has no value"),
+            ErrorKind::UnexpectedEof => f.write_str("unexpected end of input"),
+            ErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected character {ch:?}"),
+            ErrorKind::UnclosedString => f.write_str("unclosed string"),
+            ErrorKind::UnclosedComment => f.write_str("unclosed comment"),
+            ErrorKind::UnclosedArray => f.write_str("unclosed array"),
+            ErrorKind::UnclosedObject => f.write_str("unclosed object"),
+            ErrorKind::InvalidLiteral => f.write_str("expected `true`, `false` or `null`"),
+            ErrorKind::InvalidNumber(_) => f.write_str("invalid number"),
+            ErrorKind::MissingIntegerDigits => f.write_str("number has no integer digits"),
+            ErrorKind::LeadingZero => f.write_str("number has a leading zero"),
+            ErrorKind::MissingFractionDigits => {
+                f.write_str("number has no digits after its decimal point")
+            }
+            ErrorKind::MissingExponentDigits => f.write_str("number has no exponent digits"),
+            ErrorKind::UnexpectedCharInNumber(ch) => {
+                write!(f, "unexpected character {ch:?} in number")
+            }
+            ErrorKind::PrecisionLoss(n) => {
+                write!(
+                    f,
+                    "integer {n} can't be represented exactly as a 64-bit float"
+                )
+            }
+            ErrorKind::ControlCharacter(ch) => {
+                write!(f, "unescaped control character {ch:?} in string")
+            }
+            ErrorKind::UnfinishedEscape => f.write_str("unfinished escape in string"),
+            ErrorKind::InvalidEscape { escape, .. } => {
+                write!(f, "invalid escape `\\{escape}` in string")
+            }
+            ErrorKind::InvalidUnicodeEscape => {
+                f.write_str("`\\u` escape without four hexadecimal digits")
+            }
+            ErrorKind::LoneSurrogate { surrogate, .. } => {
+                write!(f, "unpaired surrogate `\\u{surrogate:04X}` in string")
+            }
+            ErrorKind::ExpectedColon => f.write_str("expected `:` after object key"),
+            ErrorKind::ExpectedComma => f.write_str("expected `,` between elements"),
+            ErrorKind::ExpectedValue => f.write_str("expected a value"),
+            ErrorKind::ExpectedKey => f.write_str("expected an object key"),
+            ErrorKind::TrailingComma => f.write_str("trailing `,` after the last element"),
+            ErrorKind::DuplicateKey(key) => write!(f, "duplicate key {key:?}"),
+            ErrorKind::TrailingContent => f.write_str("unexpected content after the value"),
+            ErrorKind::ByteOrderMark => f.write_str("input starts with a byte order mark"),
+            ErrorKind::Cancelled => f.write_str("parsing was cancelled"),
+            ErrorKind::MemoryLimitExceeded => f.write_str("memory limit exceeded"),
+            ErrorKind::StringTooLong => f.write_str("string is longer than the limit"),
+            ErrorKind::TooManyElements => f.write_str("array has more elements than the limit"),
+            ErrorKind::TooManyEntries => f.write_str("object has more entries than the limit"),
+            ErrorKind::TooDeep => {
+                f.write_str("arrays and objects are nested deeper than the limit")
+            }
+        }
+    }
+}
+
+/// Writes the line and column counting from 1, ex. `line 3, column 8`
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}",
+            self.line_number(),
+            self.column_number()
+        )
+    }
+}
+
+/// Writes the kind of error, what was found instead and its location, if
+/// known, ex. ``expected `,` between elements but found `2` at line 3,
+/// column 8``
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message())?;
+        if let Some(location) = self.location {
+            write!(f, " at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Encoding(err) => Some(err),
+            ErrorKind::InvalidNumber(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Formats `error` for someone to read, with the line of `input` that it's
+/// on and a caret under the spot, like a compiler's diagnostics:
+///
+/// ```text
+/// error: unexpected character '\''
+///  --> line 2, column 3
+///   |
+/// 2 |   'a']
+///   |   ^
+///   = help: strings must be in double quotes
+/// ```
+///
+/// Lines and columns are counted from 1, like [`Location`]'s `Display`. An
+/// error without a location is only its message and suggestion.
+pub fn render_error(input: &str, error: &Error) -> String {
+    let mut rendered = format!("error: {}", error.message());
+    let Some(location) = error.location else {
+        if let Some(suggestion) = error.suggestion {
+            let _ = write!(rendered, "\nhelp: {suggestion}");
+        }
+        return rendered;
+    };
+    let line_start = location.offset.min(input.len());
+    let line_start = input[..line_start]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let line = input[line_start..].lines().next().unwrap_or("");
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let number = location.line_number().to_string();
+    let gutter = " ".repeat(number.len());
+    // tabs are kept so that the caret lines up however they're displayed
+    let indent: String = line
+        .chars()
+        .take(location.column)
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+    let _ = write!(
+        rendered,
+        "\n{gutter}--> {location}\n{gutter} |\n{number} | {line}\n{gutter} | {indent}^"
+    );
+    if let Some(suggestion) = error.suggestion {
+        let _ = write!(rendered, "\n{gutter} = help: {suggestion}");
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_error, Error, ErrorKind, Location};
+    use crate::parse::TokenParseError;
+    use crate::tokenize::TokenizeError;
+    use crate::{parse, parse_with, validate, DuplicateKeyPolicy, ParseOptions};
+
+    #[test]
+    fn locations() {
+        let input = "{\n  \"é\": [1,\n  2]\n}";
+        assert_eq!(
+            Location::new(input, 0),
+            Location {
+                offset: 0,
+                line: 0,
+                column: 0
+            }
+        );
+        // counts characters rather than bytes within the line
+        let offset = input.find('[').unwrap();
+        assert_eq!(
+            Location::new(input, offset),
+            Location {
+                offset,
+                line: 1,
+                column: 7
+            }
+        );
+        assert_eq!(
+            Location::new(input, input.len()),
+            Location {
+                offset: input.len(),
+                line: 3,
+                column: 1
+            }
+        );
+    }
+
+    #[test]
+    fn one_based_numbers() {
+        let input = "[\"日本\",\r\n  \"😀\", x]";
+        let offset = input.find('x').unwrap();
+        let location = Location::new(input, offset);
+        assert_eq!(location.offset, 22);
+        assert_eq!((location.line, location.column), (1, 7));
+        assert_eq!((location.line_number(), location.column_number()), (2, 8));
+        assert_eq!(location.to_string(), "line 2, column 8");
+        assert_eq!(Location::default().to_string(), "line 1, column 1");
+
+        // counting on from an earlier location gives the same result
+        let start = Location::new(input, input.find('😀').unwrap());
+        assert_eq!(start.advance(input, offset), location);
+        assert_eq!(Location::default().advance(input, offset), location);
+
+        let error = parse(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unexpected character 'x' at line 2, column 8"
+        );
+    }
+
+    #[test]
+    fn renders_errors() {
+        let input = "[1,\r\n  @]";
+        let rendered = render_error(input, &parse(input).unwrap_err());
+        let expected = "\
+error: unexpected character '@'
+ --> line 2, column 3
+  |
+2 |   @]
+  |   ^";
+        assert_eq!(rendered, expected);
+
+        let input = "[\n\n\n\n\n\n\n\n\n\t\"é\", tru]";
+        let rendered = render_error(input, &parse(input).unwrap_err());
+        let expected = "\
+error: expected `true`, `false` or `null`
+  --> line 10, column 7
+   |
+10 | \t\"é\", tru]
+   | \t     ^";
+        assert_eq!(rendered, expected);
+
+        let input = "{\"a\": 1,\n  'b': 2}";
+        let rendered = render_error(input, &parse(input).unwrap_err());
+        let expected = "\
+error: unexpected character '\\''
+ --> line 2, column 3
+  |
+2 |   'b': 2}
+  |   ^
+  = help: strings must be in double quotes";
+        assert_eq!(rendered, expected);
+
+        let error = Error::new(ErrorKind::EmptyInput);
+        assert_eq!(render_error("", &error), format!("error: {}", error.kind));
+        let error = Error::new(ErrorKind::TrailingComma);
+        let expected = "\
+error: trailing `,` after the last element
+help: remove the comma after the last element";
+        assert_eq!(render_error("[1,]", &error), expected);
+
+        let rendered = render_error("[1 2]", &parse("[1 2]").unwrap_err());
+        let expected = "\
+error: expected `,` between elements but found `2`
+ --> line 1, column 4
+  |
+1 | [1 2]
+  |    ^";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn expected_and_found() {
+        let cases = [
+            (
+                "[1 2]",
+                "expected `,` between elements but found `2` at line 1, column 4",
+            ),
+            (
+                r#"{"a" 1}"#,
+                "expected `:` after object key but found `1` at line 1, column 6",
+            ),
+            (
+                "{\"a\":\n}",
+                "expected a value but found `}` at line 2, column 1",
+            ),
+            (
+                "{null: 1}",
+                "expected an object key but found `null` at line 1, column 2",
+            ),
+            (
+                r#"[1 "x\n"]"#,
+                r#"expected `,` between elements but found `"x\n"` at line 1, column 4"#,
+            ),
+            ("[1", "unclosed array at line 1, column 3"),
+        ];
+        for (input, message) in cases {
+            assert_eq!(parse(input).unwrap_err().to_string(), message, "{input}");
+        }
+
+        let error = parse(r#"[1 "abcdefghijklmnopqrstuvwxyz"]"#).unwrap_err();
+        assert_eq!(error.found.as_deref(), Some(r#"`"abcdefghijklmnopqrs...`"#));
+        // other errors aren't about what was expected
+        assert_eq!(parse("[1,]").unwrap_err().found, None);
+    }
+
+    #[test]
+    fn suggestions() {
+        let cases = [
+            ("{'a': 1}", Some("strings must be in double quotes")),
+            ("{name: 1}", Some("object keys must be in double quotes")),
+            (
+                "{\"a\": 1, b_2 : 2}",
+                Some("object keys must be in double quotes"),
+            ),
+            ("{\"a\" = 1}", Some("use `:` between a key and its value")),
+            ("[1, 2,]", Some("remove the comma after the last element")),
+            ("[True]", Some("did you mean `true`?")),
+            ("[1, None]", Some("did you mean `null`?")),
+            ("[nul]", None),
+            ("[1, @]", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse(input).unwrap_err().suggestion, expected, "{input}");
+            assert_eq!(validate(input).unwrap_err().suggestion, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let error = parse("{\"a\": \"tab\there\"}").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ControlCharacter('\t'));
+        assert_eq!(error.location.map(|location| location.column), Some(10));
+        assert_eq!(
+            error.to_string(),
+            "unescaped control character '\\t' in string at line 1, column 11"
+        );
+
+        let error = parse("[1]\n[2]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TrailingContent);
+        assert_eq!(error.location.map(|location| location.line), Some(1));
+
+        let error = validate("[1,\n 2").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedArray);
+        let end = Location {
+            offset: 6,
+            line: 1,
+            column: 2,
+        };
+        assert_eq!(error.location, Some(end));
+
+        let options = ParseOptions::new()
+            .duplicate_keys(DuplicateKeyPolicy::Error)
+            .max_array_length(2);
+        let error = parse_with("{\"a\": 1,\n \"a\": 2}", &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DuplicateKey("a".to_string()));
+        assert_eq!(error.to_string(), "duplicate key \"a\" at line 2, column 2");
+        let error = parse_with("[[1, 2, 3]]", &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooManyElements);
+        assert_eq!(error.location.map(|location| location.offset), Some(8));
+    }
+
+    #[test]
+    fn kinds_are_merged() {
+        assert_eq!(
+            Error::from(TokenParseError::EarlyEOF),
+            Error::from(TokenizeError::UnexpectedEof)
+        );
+        assert_eq!(
+            Error::from(TokenParseError::InvalidHexValue { offset: 3 }),
+            Error::from(TokenizeError::InvalidUnicodeEscape { offset: 3 })
+        );
+    }
+}
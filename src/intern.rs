@@ -0,0 +1,165 @@
+//! Sharing one allocation between repeated object keys, see [`KeyInterner`]
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// A cache of object keys, so that a key repeated across a document, or
+/// across documents, is stored once and shared by every object that has it.
+///
+/// Set it as [`ParseOptions::key_interner`](crate::ParseOptions::key_interner)
+/// to use it while parsing. This suits arrays of records, where every row has
+/// the same keys. Clones of an interner share the same cache, so it can be
+/// reused for many documents and its [`stats`](Self::stats) checked after.
+#[derive(Debug, Clone, Default)]
+pub struct KeyInterner {
+    cache: Arc<Mutex<Cache>>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    keys: HashSet<Arc<str>>,
+    max_keys: Option<usize>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Counts of how an interner's cache has been used, see
+/// [`KeyInterner::stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InternStats {
+    /// Keys that were found in the cache and shared
+    pub hits: u64,
+    /// Keys that had to be allocated
+    pub misses: u64,
+    /// Number of distinct keys in the cache
+    pub len: usize,
+}
+
+impl KeyInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an interner that stops adding keys once it holds `max_keys`,
+    /// so that documents with many distinct keys, such as maps keyed by ID,
+    /// can't grow the cache without limit. Keys that aren't cached are still
+    /// allocated as usual.
+    pub fn with_max_keys(max_keys: usize) -> Self {
+        let cache = Cache {
+            max_keys: Some(max_keys),
+            ..Cache::default()
+        };
+        Self {
+            cache: Arc::new(Mutex::new(cache)),
+        }
+    }
+
+    /// The shared copy of `key`, which is added to the cache if it isn't
+    /// there yet and the cache has room
+    pub fn intern(&self, key: &str) -> Arc<str> {
+        let mut cache = self.lock();
+        if let Some(shared) = cache.keys.get(key) {
+            let shared = Arc::clone(shared);
+            cache.hits += 1;
+            return shared;
+        }
+        cache.misses += 1;
+        let key: Arc<str> = key.into();
+        if cache.max_keys.is_none_or(|max| cache.keys.len() < max) {
+            cache.keys.insert(Arc::clone(&key));
+        }
+        key
+    }
+
+    pub fn stats(&self) -> InternStats {
+        let cache = self.lock();
+        InternStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            len: cache.keys.len(),
+        }
+    }
+
+    /// Empties the cache and resets the statistics
+    pub fn clear(&self) {
+        let mut cache = self.lock();
+        cache.keys.clear();
+        cache.hits = 0;
+        cache.misses = 0;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Cache> {
+        // the cache is left consistent even if a thread panicked holding it
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Interners are equal when they share the same cache
+impl PartialEq for KeyInterner {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cache, &other.cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{InternStats, KeyInterner};
+    use crate::{parse_with, ParseOptions, Value};
+
+    #[test]
+    fn shares_repeated_keys() {
+        let interner = KeyInterner::new();
+        let options = ParseOptions {
+            key_interner: Some(interner.clone()),
+            ..ParseOptions::default()
+        };
+        let input = r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}, {"id": 3}]"#;
+
+        let value = parse_with(input, &options).unwrap();
+        assert_eq!(
+            interner.stats(),
+            InternStats {
+                hits: 3,
+                misses: 2,
+                len: 2
+            }
+        );
+
+        let Value::Array(rows) = value else {
+            panic!("{value:?}");
+        };
+        let id_key = |row: &Value| match row {
+            Value::Object(map) => map
+                .iter()
+                .find(|(key, _)| &***key == "id")
+                .unwrap()
+                .0
+                .clone(),
+            _ => panic!("{row:?}"),
+        };
+        assert!(Arc::ptr_eq(&id_key(&rows[0]), &id_key(&rows[2])));
+    }
+
+    #[test]
+    fn stops_caching_at_max_keys() {
+        let interner = KeyInterner::with_max_keys(1);
+
+        let a = interner.intern("a");
+        interner.intern("b");
+        interner.intern("b");
+        assert!(Arc::ptr_eq(&a, &interner.intern("a")));
+        assert_eq!(
+            interner.stats(),
+            InternStats {
+                hits: 1,
+                misses: 3,
+                len: 1
+            }
+        );
+
+        interner.clear();
+        assert_eq!(interner.stats(), InternStats::default());
+    }
+}
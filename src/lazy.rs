@@ -0,0 +1,594 @@
+//! A document tree whose strings and numbers are only decoded when they're
+//! accessed, see [`LazyValue`]
+
+use std::borrow::Cow;
+use std::cell::OnceCell;
+
+use crate::parse::unescape_string;
+use crate::simd::is_whitespace;
+use crate::tokenize::{
+    byte_order_mark, check_control_characters, non_finite, number_end, parse_number, token_error,
+    trailing_error,
+};
+use crate::{
+    DefaultMapKind, Error, LoneSurrogatePolicy, MapKind, Number, ObjectMap, ParseOptions,
+    TokenParseError, TokenizeError, Value,
+};
+
+/// A parsed document where objects and arrays are built up front, but
+/// strings, keys and numbers keep their source text until they're accessed.
+///
+/// When only a few fields of a large document are read, this skips most of
+/// the unescaping and number conversion that [`parse`](crate::parse) does.
+/// The structure of the document is checked by [`LazyValue::parse`], but an
+/// invalid escape or number is only found when that string or number is
+/// decoded.
+#[derive(Debug, Clone)]
+pub enum LazyValue<'a> {
+    Null,
+    Boolean(bool),
+    Number(LazyNumber<'a>),
+    String(LazyString<'a>),
+    Array(Vec<LazyValue<'a>>),
+
+    /// Entries of an object in source order
+    Object(Vec<(LazyString<'a>, LazyValue<'a>)>),
+}
+
+/// The text of a string, unescaped the first time it's needed
+#[derive(Debug, Clone)]
+pub struct LazyString<'a> {
+    /// The text between the quotes
+    raw: &'a str,
+    /// The whole input and the byte offset of the opening quote in it, to
+    /// locate an error in the string
+    input: &'a str,
+    start: usize,
+    decoding: Decoding,
+    unescaped: OnceCell<Result<String, TokenParseError>>,
+}
+
+/// The text of a number, converted the first time it's needed
+#[derive(Debug, Clone)]
+pub struct LazyNumber<'a> {
+    raw: &'a str,
+    /// The whole input and the byte offset of the number in it, to locate
+    /// an error in the number
+    input: &'a str,
+    start: usize,
+    decoding: Decoding,
+    parsed: OnceCell<Result<Number, TokenizeError>>,
+}
+
+/// The options that decoding a string or number follows, kept with each one
+/// since it's decoded after parsing has finished
+#[derive(Debug, Clone, Copy)]
+struct Decoding {
+    lone_surrogates: LoneSurrogatePolicy,
+    allow_invalid_escapes: bool,
+    allow_line_continuations: bool,
+    preserve_number_literals: bool,
+    require_exact_integers: bool,
+}
+
+impl Decoding {
+    fn new(options: &ParseOptions) -> Self {
+        Self {
+            lone_surrogates: options.lone_surrogates,
+            allow_invalid_escapes: options.allow_invalid_escapes,
+            allow_line_continuations: options.allow_line_continuations,
+            preserve_number_literals: options.preserve_number_literals,
+            require_exact_integers: options.require_exact_integers,
+        }
+    }
+
+    fn options(self) -> ParseOptions {
+        ParseOptions {
+            lone_surrogates: self.lone_surrogates,
+            allow_invalid_escapes: self.allow_invalid_escapes,
+            allow_line_continuations: self.allow_line_continuations,
+            preserve_number_literals: self.preserve_number_literals,
+            require_exact_integers: self.require_exact_integers,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+impl<'a> LazyString<'a> {
+    fn new(raw: &'a str, input: &'a str, start: usize, decoding: Decoding) -> Self {
+        Self {
+            raw,
+            input,
+            start,
+            decoding,
+            unescaped: OnceCell::new(),
+        }
+    }
+
+    /// The source text between the quotes, with any escapes as written
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// The unescaped string, which borrows the source text when it has no
+    /// escapes
+    pub fn as_str(&self) -> Result<&str, Error> {
+        if !self.raw.contains('\\') {
+            return Ok(self.raw);
+        }
+        let unescaped = self.unescaped.get_or_init(|| {
+            unescape_string(self.raw, &self.decoding.options()).map(Cow::into_owned)
+        });
+        match unescaped {
+            Ok(string) => Ok(string),
+            Err(err) => Err(err.clone().locate(self.input, || self.start)),
+        }
+    }
+}
+
+impl<'a> LazyNumber<'a> {
+    fn new(raw: &'a str, input: &'a str, start: usize, decoding: Decoding) -> Self {
+        Self {
+            raw,
+            input,
+            start,
+            decoding,
+            parsed: OnceCell::new(),
+        }
+    }
+
+    /// The source text of the number
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    pub fn get(&self) -> Result<&Number, Error> {
+        let parsed = self
+            .parsed
+            .get_or_init(|| match number_end(self.raw.as_bytes(), 0) {
+                Ok(_) => parse_number(self.raw.to_string(), &self.decoding.options()),
+                Err(err) => Err(err),
+            });
+        match parsed {
+            Ok(number) => Ok(number),
+            Err(err) => Err(err.clone().locate(self.input, self.start)),
+        }
+    }
+}
+
+impl<'a> LazyValue<'a> {
+    /// Parses the structure of `input`, keeping the text of its strings and
+    /// numbers to be decoded later
+    pub fn parse(input: &'a str) -> Result<Self, Error> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses the structure of `input` like [`parse`](Self::parse),
+    /// accepting what the given options allow, apart from the JSON5 syntax
+    /// of comments, single quotes, unquoted keys, hex numbers and leading `+`
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<Self, Error> {
+        let mut scanner = Scanner {
+            input,
+            index: byte_order_mark(input, options).map_err(|err| err.locate(input, 0))?,
+            options: options.clone(),
+            decoding: Decoding::new(options),
+            depth: 0,
+            invalid_number: None,
+        };
+        let result = scanner.value(None).and_then(|value| {
+            if !options.allow_trailing_content && scanner.next_byte(None).is_ok() {
+                return Err(trailing_error(input, scanner.index, options));
+            }
+            Ok(value)
+        });
+        // a number before the error that isn't one would have been found
+        // first by the parsers that tokenize first
+        match (result, scanner.invalid_number) {
+            (Err(_), Some(err)) => Err(err),
+            (result, _) => result,
+        }
+    }
+
+    /// The value of a key in an object, or `None` when this isn't an object
+    /// or doesn't have the key. When the key appears more than once the last
+    /// value is used, as with [`parse`](crate::parse).
+    ///
+    /// Keys with escapes are unescaped to compare them, and a key with an
+    /// invalid escape never matches.
+    pub fn get(&self, key: &str) -> Option<&LazyValue<'a>> {
+        let LazyValue::Object(entries) = self else {
+            return None;
+        };
+        entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k.as_str() == Ok(key))
+            .map(|(_, value)| value)
+    }
+
+    /// An element of an array, or `None` when this isn't an array or the
+    /// index is out of bounds
+    pub fn get_index(&self, index: usize) -> Option<&LazyValue<'a>> {
+        match self {
+            LazyValue::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    /// The unescaped string, or `None` when this isn't a string
+    pub fn as_str(&self) -> Option<Result<&str, Error>> {
+        match self {
+            LazyValue::String(string) => Some(string.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The number, or `None` when this isn't a number
+    pub fn as_number(&self) -> Option<Result<&Number, Error>> {
+        match self {
+            LazyValue::Number(number) => Some(number.get()),
+            _ => None,
+        }
+    }
+
+    /// Decodes everything to build a [`Value`], failing on the first string
+    /// or number that's invalid
+    pub fn to_value(&self) -> Result<Value, Error> {
+        self.to_value_of_kind::<DefaultMapKind>()
+    }
+
+    fn to_value_of_kind<K: MapKind>(&self) -> Result<Value<K>, Error> {
+        Ok(match self {
+            LazyValue::Null => Value::Null,
+            LazyValue::Boolean(b) => Value::Boolean(*b),
+            LazyValue::Number(number) => Value::Number(number.get()?.clone()),
+            LazyValue::String(string) => Value::String(string.as_str()?.into()),
+            LazyValue::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(LazyValue::to_value_of_kind)
+                    .collect::<Result<_, _>>()?,
+            ),
+            LazyValue::Object(entries) => {
+                let mut map = K::Map::default();
+                for (key, value) in entries {
+                    map.insert(key.as_str()?.into(), value.to_value_of_kind()?);
+                }
+                Value::Object(map)
+            }
+        })
+    }
+}
+
+/// Finds the structure of a document without decoding its strings and
+/// numbers. Structural characters are all ASCII, so the input is scanned as
+/// bytes.
+struct Scanner<'a> {
+    input: &'a str,
+    index: usize,
+    options: ParseOptions,
+    decoding: Decoding,
+    /// Number of arrays and objects open around the current position
+    depth: usize,
+    /// The error in the first number that doesn't follow the grammar, which
+    /// is only reported in place of a later error in the structure
+    invalid_number: Option<Error>,
+}
+
+impl<'a> Scanner<'a> {
+    /// Parses the value at the current position. `container` is `b'['` or
+    /// `b'{'` for the innermost container, to report where the input ended.
+    fn value(&mut self, container: Option<u8>) -> Result<LazyValue<'a>, Error> {
+        let first = self.next_byte(container)?;
+        if let Some(number) = self.non_finite() {
+            return Ok(LazyValue::Number(number));
+        }
+        let value = match first {
+            b'"' => LazyValue::String(self.lazy_string()?),
+            b'-' | b'0'..=b'9' => LazyValue::Number(self.number()),
+            b't' => self.literal("true", LazyValue::Boolean(true))?,
+            b'f' => self.literal("false", LazyValue::Boolean(false))?,
+            b'n' => self.literal("null", LazyValue::Null)?,
+            b'[' => {
+                self.enter()?;
+                self.index += 1;
+                let mut elements = Vec::new();
+                if self.next_byte(Some(b'['))? == b']' {
+                    self.index += 1;
+                } else {
+                    loop {
+                        elements.push(self.value(Some(b'['))?);
+                        match self.next_byte(Some(b'['))? {
+                            b',' if self.trailing_comma(b'[')? => break,
+                            b',' => {}
+                            b']' => {
+                                self.index += 1;
+                                break;
+                            }
+                            _ => return Err(self.unexpected(TokenParseError::ExpectedComma)),
+                        }
+                    }
+                }
+                self.depth -= 1;
+                LazyValue::Array(elements)
+            }
+            b'{' => {
+                self.enter()?;
+                self.index += 1;
+                let mut entries = Vec::new();
+                if self.next_byte(Some(b'{'))? == b'}' {
+                    self.index += 1;
+                } else {
+                    loop {
+                        if self.next_byte(Some(b'{'))? != b'"' {
+                            return Err(self.unexpected(TokenParseError::ExpectedProperty));
+                        }
+                        let key = self.lazy_string()?;
+                        if self.next_byte(Some(b'{'))? != b':' {
+                            return Err(self.unexpected(TokenParseError::ExpectedColon));
+                        }
+                        self.index += 1;
+                        entries.push((key, self.value(Some(b'{'))?));
+                        match self.next_byte(Some(b'{'))? {
+                            b',' if self.trailing_comma(b'{')? => break,
+                            b',' => {}
+                            b'}' => {
+                                self.index += 1;
+                                break;
+                            }
+                            _ => return Err(self.unexpected(TokenParseError::ExpectedComma)),
+                        }
+                    }
+                }
+                self.depth -= 1;
+                LazyValue::Object(entries)
+            }
+            b']' | b'}' | b',' | b':' => return Err(self.error(TokenParseError::ExpectedValue)),
+            _ => {
+                let ch = self.input[self.index..]
+                    .chars()
+                    .next()
+                    .expect("not at the end");
+                return Err(TokenizeError::CharNotRecognized(ch).locate(self.input, self.index));
+            }
+        };
+        Ok(value)
+    }
+
+    /// Counts the array or object that's opening at the current position,
+    /// failing when it's nested deeper than [`ParseOptions::max_depth`]
+    fn enter(&mut self) -> Result<(), Error> {
+        self.depth += 1;
+        if self.options.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(TokenizeError::TooDeep.locate(self.input, self.index));
+        }
+        Ok(())
+    }
+
+    /// Converts `err`, found at the current position, locating it in the
+    /// input
+    fn error(&self, err: TokenParseError) -> Error {
+        err.locate(self.input, || self.index)
+    }
+
+    /// Fails with `err` at the current byte, unless the token that starts
+    /// there is invalid itself, which is reported instead as it is by the
+    /// parsers that tokenize first
+    fn unexpected(&self, err: TokenParseError) -> Error {
+        token_error(self.input, self.index, &self.options).unwrap_or_else(|| self.error(err))
+    }
+
+    /// Consumes a comma, then the end of `container` if it follows, which is
+    /// only allowed when trailing commas are. Returns whether the container
+    /// was ended.
+    fn trailing_comma(&mut self, container: u8) -> Result<bool, Error> {
+        let comma = self.index;
+        self.index += 1;
+        let closing = if container == b'[' { b']' } else { b'}' };
+        if self.next_byte(Some(container))? != closing {
+            return Ok(false);
+        }
+        if !self.options.allow_trailing_commas {
+            return Err(TokenParseError::TrailingComma.locate(self.input, || comma));
+        }
+        self.index += 1;
+        Ok(true)
+    }
+
+    /// Skips whitespace and returns the next byte without consuming it
+    fn next_byte(&mut self, container: Option<u8>) -> Result<u8, Error> {
+        let bytes = self.input.as_bytes();
+        while bytes
+            .get(self.index)
+            .is_some_and(|&byte| is_whitespace(byte))
+        {
+            self.index += 1;
+        }
+        let error = match container {
+            None => TokenParseError::EmptyInput,
+            Some(b'[') => TokenParseError::UnclosedBracket,
+            Some(_) => TokenParseError::UnclosedBrace,
+        };
+        bytes
+            .get(self.index)
+            .copied()
+            .ok_or_else(|| self.error(error))
+    }
+
+    /// Consumes a string, to be unescaped when it's accessed
+    fn lazy_string(&mut self) -> Result<LazyString<'a>, Error> {
+        let start = self.index;
+        let raw = self.string()?;
+        Ok(LazyString::new(raw, self.input, start, self.decoding))
+    }
+
+    /// Consumes a string, returning the text between its quotes
+    fn string(&mut self) -> Result<&'a str, Error> {
+        let bytes = self.input.as_bytes();
+        let start = self.index + 1;
+        let mut is_escaping = false;
+        for (i, &byte) in bytes.iter().enumerate().skip(start) {
+            match byte {
+                b'"' if !is_escaping => {
+                    let raw = &self.input[start..i];
+                    check_control_characters(raw, start, &self.options)
+                        .map_err(|err| err.locate(self.input, self.index))?;
+                    self.index = i + 1;
+                    return Ok(raw);
+                }
+                b'\\' => is_escaping = !is_escaping,
+                _ => is_escaping = false,
+            }
+        }
+        Err(TokenizeError::UnclosedQuotes.locate(self.input, self.index))
+    }
+
+    /// Consumes a number, with the characters that can make up one. When
+    /// they don't follow the number grammar, it's reported when the number
+    /// is decoded, or in place of an error later in the structure.
+    fn number(&mut self) -> LazyNumber<'a> {
+        let start = self.index;
+        let length = self.input.as_bytes()[start..]
+            .iter()
+            .take_while(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+            .count();
+        if self.invalid_number.is_none() {
+            if let Err(err) = number_end(self.input.as_bytes(), start) {
+                self.invalid_number = Some(err.locate(self.input, start));
+            }
+        }
+        self.index += length;
+        LazyNumber::new(
+            &self.input[start..self.index],
+            self.input,
+            start,
+            self.decoding,
+        )
+    }
+
+    /// Consumes `NaN`, `Infinity` or `-Infinity` if one comes next and they
+    /// are allowed
+    fn non_finite(&mut self) -> Option<LazyNumber<'a>> {
+        if !self.options.allow_non_finite_numbers {
+            return None;
+        }
+        let start = self.index;
+        let rest = &self.input[start..];
+        let raw = ["NaN", "Infinity", "-Infinity"]
+            .into_iter()
+            .find(|word| rest.starts_with(word))?;
+        self.index += raw.len();
+        let n = non_finite(raw).expect("one of the non-finite numbers");
+        Some(LazyNumber {
+            raw,
+            input: self.input,
+            start,
+            decoding: self.decoding,
+            parsed: OnceCell::from(Ok(Number::F64(n))),
+        })
+    }
+
+    fn literal(&mut self, text: &str, value: LazyValue<'a>) -> Result<LazyValue<'a>, Error> {
+        if !self.input[self.index..].starts_with(text) {
+            return Err(TokenizeError::UnfinishedLiteralValue.locate(self.input, self.index));
+        }
+        self.index += text.len();
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LazyValue;
+    use crate::{parse, Error, ErrorKind, Number, TokenParseError, TokenizeError};
+
+    #[test]
+    fn matches_parse() {
+        let input = r#"{"id": 12, "name": "café", "tags": ["a", "b\nc"], "ok": true, "n": null, "x": -1.5}"#;
+        let lazy = LazyValue::parse(input).unwrap();
+
+        assert_eq!(lazy.to_value().unwrap(), parse(input).unwrap());
+    }
+
+    #[test]
+    fn decodes_on_access() {
+        let lazy = LazyValue::parse(r#"{"a\"b": 1, "list": [10, "x\ty"], "a\"b": 2}"#).unwrap();
+
+        let value = lazy.get("a\"b").unwrap();
+        let LazyValue::Number(number) = value else {
+            panic!("{value:?}");
+        };
+        assert_eq!(number.raw(), "2");
+        assert_eq!(number.get(), Ok(&Number::I64(2)));
+
+        let list = lazy.get("list").unwrap();
+        assert_eq!(list.get_index(1).unwrap().as_str(), Some(Ok("x\ty")));
+        assert_eq!(list.get_index(0).unwrap().as_str(), None);
+        assert!(list.get_index(2).is_none());
+        assert!(lazy.get("missing").is_none());
+    }
+
+    #[test]
+    fn leaf_errors_are_deferred() {
+        let lazy = LazyValue::parse(r#"["ok", "\uZZZZ", 1-2]"#).unwrap();
+
+        assert_eq!(lazy.get_index(0).unwrap().as_str(), Some(Ok("ok")));
+        assert_eq!(
+            lazy.get_index(1).unwrap().as_str(),
+            Some(Err(TokenizeError::InvalidUnicodeEscape { offset: 8 }
+                .locate(r#"["ok", "\uZZZZ", 1-2]"#, 7)))
+        );
+        assert!(matches!(
+            lazy.get_index(2).unwrap().as_number(),
+            Some(Err(Error {
+                kind: ErrorKind::UnexpectedCharInNumber('-'),
+                ..
+            }))
+        ));
+        assert!(lazy.to_value().is_err());
+    }
+
+    #[test]
+    fn structure_errors() {
+        let cases = [
+            ("", TokenParseError::EmptyInput.locate("", || 0)),
+            (
+                "[1, 2",
+                TokenParseError::UnclosedBracket.locate("[1, 2", || 5),
+            ),
+            (
+                r#"{"a": 1"#,
+                TokenParseError::UnclosedBrace.locate(r#"{"a": 1"#, || 7),
+            ),
+            (
+                "[1 2]",
+                TokenParseError::ExpectedComma.locate("[1 2]", || 3),
+            ),
+            (
+                "[1] 2",
+                TokenParseError::UnexpectedTrailingTokens { offset: 4 }.locate("[1] 2", || 4),
+            ),
+            (
+                r#"{"a" 1}"#,
+                TokenParseError::ExpectedColon.locate(r#"{"a" 1}"#, || 5),
+            ),
+            (
+                "{1: 2}",
+                TokenParseError::ExpectedProperty.locate("{1: 2}", || 1),
+            ),
+            ("[1,]", TokenParseError::TrailingComma.locate("[1,]", || 2)),
+            (
+                "[tru]",
+                TokenizeError::UnfinishedLiteralValue.locate("[tru]", 1),
+            ),
+            (r#"["a"#, TokenizeError::UnclosedQuotes.locate(r#"["a"#, 1)),
+            (
+                "[@]",
+                TokenizeError::CharNotRecognized('@').locate("[@]", 1),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(LazyValue::parse(input).unwrap_err(), expected, "{input}");
+        }
+    }
+}
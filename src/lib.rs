@@ -1,66 +1,335 @@
+// `Str` is a `String` unless the `arc_str` feature is enabled, so converting
+// into it is only a no-op in the default configuration
+#![cfg_attr(not(feature = "arc_str"), allow(clippy::useless_conversion))]
+
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod assert;
+mod borrowed;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod codegen;
+mod diagnostics;
+pub mod diff;
+#[cfg(test)]
+mod differential;
+mod documents;
+pub mod encoding;
+mod error;
+#[cfg(feature = "arc_str")]
+mod intern;
+pub mod lazy;
+mod lines;
+pub mod lint;
+mod map;
+mod number;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod parse;
+pub mod patch;
+pub mod projection;
+pub mod pull;
+mod push;
+mod raw;
+mod recover;
+mod repair;
+pub mod sax;
+pub mod schema;
+mod serialize;
+mod simd;
+pub mod tape;
 mod tokenize;
+mod validate;
+mod value;
 
-use parse::{parse_tokens, TokenParseError};
-use std::collections::HashMap;
-use tokenize::{tokenize, TokenizeError};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-pub fn parse(input: String) -> Result<Value, ParseError> {
-    let tokens = tokenize(input)?;
-    let value = parse_tokens(&tokens, &mut 0)?;
-    Ok(value)
+use encoding::{Encoding, EncodingError};
+use parse::{parse_document, TokenParseError};
+use tokenize::{ChunkTokenizer, ReaderTokens, TokenStream, TokenizeError, READ_CHUNK};
+
+pub use borrowed::BorrowedValue;
+pub use diagnostics::{
+    diagnose, parse_with_diagnostics, Diagnostic, Diagnostics, Severity, Warning, WarningKind,
+};
+pub use documents::{parse_documents, Documents, ValueStream};
+pub use error::{render_error, Error, ErrorKind, Location};
+#[cfg(feature = "arc_str")]
+pub use intern::{InternStats, KeyInterner};
+pub use lines::{parse_lines, parse_lines_reader, LineError};
+pub use map::{
+    BTreeMapKind, DefaultMapKind, HashMapKind, Map, MapKind, ObjectMap, OrderedMap, OrderedMapKind,
+};
+pub use number::{Number, NumberOutOfRange};
+pub use options::{
+    CancelToken, DuplicateKey, DuplicateKeyCallback, DuplicateKeyPolicy, LoneSurrogatePolicy,
+    ParseOptions, ParseProfile, Progress, ProgressCallback,
+};
+#[cfg(feature = "rayon")]
+pub use parallel::parse_parallel;
+pub use push::PushParser;
+pub use raw::RawValue;
+pub use recover::{parse_collecting_errors, parse_with_recovery};
+pub use repair::{parse_lenient, parse_lenient_with, Repair, RepairKind};
+pub use tokenize::{tokenize_spanned, SpannedToken, Token, Tokens};
+pub use validate::{validate, validate_with};
+#[cfg(feature = "proptest")]
+pub use value::ArbitraryOptions;
+pub use value::{
+    EqOptions, InvalidPath, PathProfile, Profile, SharedValue, Str, UnflattenError, Value, ELIDED,
+    REDACTED,
+};
+
+/// Parses JSON text with the default options. The input is borrowed, so any
+/// of `&str`, `String` or `&String` can be passed.
+pub fn parse(input: impl AsRef<str>) -> Result<Value, Error> {
+    parse_with(input, &ParseOptions::default())
+}
+
+/// Parses JSON text with the given options
+pub fn parse_with(input: impl AsRef<str>, options: &ParseOptions) -> Result<Value, Error> {
+    parse_with_kind(input.as_ref(), options)
 }
 
-/// Representation of a JSON value
-#[derive(Debug, Clone, PartialEq)]
-pub enum Value {
-    /// literal characters `null`
-    Null,
+/// Parses the value at the start of `input`, ignoring any text after it, and
+/// returns it with the byte offset just after the value, where the rest of
+/// the input starts.
+///
+/// [`parse`] instead fails with [`ErrorKind::TrailingContent`] when there is
+/// more than whitespace after the value.
+pub fn parse_partial(input: &str) -> Result<(Value, usize), Error> {
+    parse_partial_with(input, &ParseOptions::default())
+}
 
-    /// literal characters `true` or `false`
-    Boolean(bool),
+/// Parses the value at the start of `input` like [`parse_partial`], with the
+/// given options
+pub fn parse_partial_with(input: &str, options: &ParseOptions) -> Result<(Value, usize), Error> {
+    let mut end = 0;
+    let Some((tokens, offsets)) = tokenize::tokenize_value(input, &mut end, options)? else {
+        return Err(TokenParseError::EmptyInput.locate(input, || input.len()));
+    };
+    let mut index = 0;
+    let value = parse::parse_tokens(&tokens, &mut index, options)
+        .map_err(|err| err.locate(input, || offsets.get(index).copied().unwrap_or(end)))?;
+    Ok((value, end))
+}
 
-    /// characters within double quotes "..."
-    String(String),
+/// Parses JSON text from bytes in UTF-8, UTF-16 or UTF-32, detecting the
+/// encoding and skipping a byte order mark as described in
+/// [`encoding::detect`]
+pub fn parse_bytes(input: &[u8]) -> Result<Value, Error> {
+    parse_bytes_with(input, &ParseOptions::default())
+}
 
-    /// numbers stored as a 64-bit floating point
-    Number(f64),
+/// Parses JSON text from bytes like [`parse_bytes`], with the given options
+pub fn parse_bytes_with(input: &[u8], options: &ParseOptions) -> Result<Value, Error> {
+    let (detected, bom) = encoding::detect(input);
+    if options.reject_byte_order_mark && bom > 0 {
+        let mut error = Error::from(TokenizeError::ByteOrderMark);
+        error.location = Some(Location::default());
+        return Err(error);
+    }
+    if detected == Encoding::Utf8 {
+        let text = encoding::decode(input)?;
+        return parse_with(text, options);
+    }
 
-    /// Zero to many JSON values
-    Array(Vec<Value>),
+    // other encodings are decoded and tokenized a chunk at a time, rather
+    // than transcoding the whole input first
+    let mut decoder = encoding::Decoder::new();
+    let mut tokenizer = ChunkTokenizer::new(options.clone());
+    let mut text = String::new();
+    for chunk in input.chunks(READ_CHUNK).chain([&[][..]]) {
+        text.clear();
+        decoder.decode(chunk, &mut text)?;
+        if !text.is_empty() {
+            tokenizer.push(text.as_bytes())?;
+        }
+    }
+    tokenizer.push(&[])?;
+    tokenizer.parse_document()
+}
 
-    /// String keys with JSON values
-    Object(HashMap<String, Value>),
+/// Parses JSON text from UTF-16 code units, such as a wide string from a
+/// Windows API. A leading byte order mark is skipped unless
+/// [`ParseOptions::reject_byte_order_mark`] is set.
+pub fn parse_utf16(input: &[u16]) -> Result<Value, Error> {
+    parse_utf16_with(input, &ParseOptions::default())
 }
 
-#[cfg(test)]
-impl Value {
-    pub(crate) fn object<const N: usize>(pairs: [(&'static str, Self); N]) -> Self {
-        let owned_pairs = pairs.map(|(key, value)| (String::from(key), value));
-        let map = HashMap::from(owned_pairs);
-        Self::Object(map)
+/// Parses JSON text from UTF-16 code units like [`parse_utf16`], with the
+/// given options.
+///
+/// An unpaired surrogate is reported as an [`EncodingError`] whose offset
+/// counts two bytes per code unit, in [`Encoding::Utf16Le`] or
+/// [`Encoding::Utf16Be`] to match the platform.
+pub fn parse_utf16_with(input: &[u16], options: &ParseOptions) -> Result<Value, Error> {
+    let encoding = if cfg!(target_endian = "big") {
+        Encoding::Utf16Be
+    } else {
+        Encoding::Utf16Le
+    };
+    let mut tokenizer = ChunkTokenizer::new(options.clone());
+    let mut text = String::new();
+    let mut offset = 0;
+    for ch in char::decode_utf16(input.iter().copied()) {
+        let ch = ch.map_err(|_| EncodingError { encoding, offset })?;
+        offset += ch.len_utf16() * 2;
+        text.push(ch);
+        if text.len() >= READ_CHUNK {
+            tokenizer.push(text.as_bytes())?;
+            text.clear();
+        }
+    }
+    if !text.is_empty() {
+        tokenizer.push(text.as_bytes())?;
+    }
+    tokenizer.push(&[])?;
+    tokenizer.parse_document()
+}
+
+/// Parses UTF-8 JSON text from a reader, such as a file or socket.
+///
+/// The input is read and tokenized a chunk at a time as the value is built,
+/// so neither the full text nor all of its tokens are held in memory, only
+/// the value. There is no need to wrap the reader in a `BufReader`.
+pub fn parse_reader(reader: impl Read) -> Result<Value, ReadError> {
+    parse_reader_with(reader, &ParseOptions::default())
+}
+
+/// Parses UTF-8 JSON text from a reader like [`parse_reader`], with the
+/// given options
+pub fn parse_reader_with(reader: impl Read, options: &ParseOptions) -> Result<Value, ReadError> {
+    ReaderTokens::new(reader, options).parse_document()
+}
+
+/// Parses UTF-8 JSON text from a tokio `AsyncRead`, such as a request body or
+/// socket, tokenizing it a chunk at a time.
+///
+/// The executor isn't blocked while waiting for input, though tokenizing
+/// happens inline on the calling task. Unlike [`parse_reader`], the tokens of
+/// the whole input are kept until it ends and only then parsed, so memory
+/// use grows with the input rather than only with the value.
+#[cfg(feature = "tokio")]
+pub async fn parse_async(reader: impl tokio::io::AsyncRead) -> Result<Value, ReadError> {
+    parse_async_with(reader, &ParseOptions::default()).await
+}
+
+/// Parses UTF-8 JSON text from a tokio `AsyncRead` like [`parse_async`],
+/// with the given options
+#[cfg(feature = "tokio")]
+pub async fn parse_async_with(
+    reader: impl tokio::io::AsyncRead,
+    options: &ParseOptions,
+) -> Result<Value, ReadError> {
+    let tokenizer = tokenize::tokenize_async(reader, options).await?;
+    let value = tokenizer.parse_document()?;
+    Ok(value)
+}
+
+/// Reads and parses a JSON file, which may be in any of the encodings that
+/// [`parse_bytes`] accepts and may start with a byte order mark.
+///
+/// Errors opening or reading the file include its path.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Value, ReadError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| ReadError::File {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    Ok(parse_bytes(&bytes)?)
+}
+
+/// Parses a JSON file by memory-mapping it rather than reading it into a
+/// buffer, so that the operating system pages the input in as it's parsed.
+/// Like [`parse_file`], any of the encodings accepted by [`parse_bytes`] may
+/// be used.
+///
+/// The file must not be modified while it is being parsed: changes through
+/// the mapping are undefined behavior, which is why mapping is only offered
+/// for files.
+#[cfg(feature = "mmap")]
+pub fn parse_mmap(path: impl AsRef<Path>) -> Result<Value, ReadError> {
+    let path = path.as_ref();
+    let file_error = |error| ReadError::File {
+        path: path.to_path_buf(),
+        error,
+    };
+
+    let file = fs::File::open(path).map_err(file_error)?;
+    // SAFETY: the mapping is only read while parsing, and the documented
+    // requirement is that the file isn't modified in the meantime
+    let map = unsafe { memmap2::Mmap::map(&file) }.map_err(file_error)?;
+    Ok(parse_bytes(&map)?)
+}
+
+fn parse_with_kind<K: MapKind>(input: &str, options: &ParseOptions) -> Result<Value<K>, Error> {
+    // the tokens are read as they're parsed, so that an error stops both
+    let mut tokens = TokenStream::new(input, options);
+    let result = parse_document(&mut tokens, options, |tokens, index| tokens.offset(index));
+    if let Some(error) = tokens.error.take() {
+        return Err(error);
+    }
+    result.map_err(|(err, index)| err.locate(input, || tokens.offset(index)))
+}
+
+/// Parses with the default options into a `Value` of any [`MapKind`], ex.
+/// `let value: Value<BTreeMapKind> = input.parse()?;`
+impl<K: MapKind> FromStr for Value<K> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_with_kind(s, &ParseOptions::default())
     }
+}
+
+/// Reading the input failed, or the text that was read could not be parsed
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+
+    /// A file could not be opened or read, see [`parse_file`]
+    File {
+        path: PathBuf,
+        error: io::Error,
+    },
 
-    pub(crate) fn string(s: &str) -> Self {
-        Self::String(String::from(s))
+    Parse(Error),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum ParseError {
-    TokenizeError(TokenizeError),
-    ParseError(TokenParseError),
+impl From<Error> for ReadError {
+    fn from(err: Error) -> Self {
+        Self::Parse(err)
+    }
 }
 
-impl From<TokenParseError> for ParseError {
-    fn from(err: TokenParseError) -> Self {
-        Self::ParseError(err)
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(_) => f.write_str("failed to read input"),
+            ReadError::File { path, .. } => write!(f, "failed to read {}", path.display()),
+            ReadError::Parse(_) => f.write_str("failed to parse input"),
+        }
     }
 }
 
-impl From<TokenizeError> for ParseError {
-    fn from(err: TokenizeError) -> Self {
-        Self::TokenizeError(err)
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) | ReadError::File { error: err, .. } => Some(err),
+            ReadError::Parse(err) => Some(err),
+        }
     }
 }
 
@@ -69,14 +338,236 @@ mod tests {
     use super::*;
 
     fn check(input: &str, expected: Value) {
-        let actual = parse(String::from(input)).unwrap();
+        let actual = parse(input).unwrap();
         assert_eq!(actual, expected);
     }
 
-    fn check_error<E: Into<ParseError>>(input: &str, expected: E) {
+    /// Checks the kind of error that parsing `input` fails with, wherever
+    /// it's located
+    fn check_error<E: Into<Error>>(input: &str, expected: E) {
         let expected = expected.into();
-        let actual = parse(String::from(input)).unwrap_err();
-        assert_eq!(actual, expected);
+        let actual = parse(input).unwrap_err();
+        assert_eq!(actual.kind, expected.kind);
+    }
+
+    #[test]
+    fn parses_borrowed_and_owned_input() {
+        let owned = String::from("[true]");
+        let expected = Value::Array(vec![Value::Boolean(true)]);
+
+        assert_eq!(parse("[true]").unwrap(), expected);
+        assert_eq!(parse(&owned).unwrap(), expected);
+        assert_eq!(parse(owned).unwrap(), expected);
+    }
+
+    #[test]
+    fn parses_reader() {
+        let input = r#"{"key": ["value", 1.5]}"#;
+        let expected = parse(input).unwrap();
+
+        assert_eq!(parse_reader(input.as_bytes()).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_reader_io_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disconnected"))
+            }
+        }
+
+        assert!(matches!(parse_reader(Failing), Err(ReadError::Io(_))));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "tokio")]
+    async fn parses_async() {
+        use tokio::io::AsyncWriteExt;
+
+        let input = r#"{"key": [1, "é 😀", true]}"#;
+        // a buffer of one byte splits every token and character between reads
+        let (mut writer, reader) = tokio::io::duplex(1);
+        let write = tokio::spawn(async move { writer.write_all(input.as_bytes()).await });
+
+        let expected = Value::object([(
+            "key",
+            Value::Array(vec![1.into(), Value::string("é 😀"), Value::Boolean(true)]),
+        )]);
+        assert_eq!(parse_async(reader).await.unwrap(), expected);
+        write.await.unwrap().unwrap();
+
+        let options = ParseOptions::new()
+            .max_depth(1)
+            .profile(ParseProfile::Relaxed);
+        let parse = |input: &'static str| parse_async_with(input.as_bytes(), &options);
+        assert_eq!(
+            parse("[1, 2,]").await.unwrap(),
+            Value::Array(vec![1.into(), 2.into()])
+        );
+        let Err(ReadError::Parse(error)) = parse("[[1]]").await else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(error, TokenizeError::TooDeep.locate("[[1]]", 1));
+    }
+
+    #[test]
+    fn parses_file() {
+        let path = std::env::temp_dir().join(format!("json_parser_{}.json", std::process::id()));
+        fs::write(&path, b"\xEF\xBB\xBF{\"key\": [1, 2]}").unwrap();
+
+        let actual = parse_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        let expected = Value::object([("key", Value::Array(vec![1.into(), 2.into()]))]);
+        assert_eq!(actual.unwrap(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn parses_mmap() {
+        let path =
+            std::env::temp_dir().join(format!("json_parser_mmap_{}.json", std::process::id()));
+        fs::write(&path, r#"{"key": [1, 2]}"#).unwrap();
+
+        let actual = parse_mmap(&path);
+        fs::remove_file(&path).unwrap();
+
+        let expected = Value::object([("key", Value::Array(vec![1.into(), 2.into()]))]);
+        assert_eq!(actual.unwrap(), expected);
+        assert!(matches!(
+            parse_mmap("does/not/exist.json"),
+            Err(ReadError::File { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_file_missing() {
+        let path = Path::new("does/not/exist.json");
+
+        match parse_file(path) {
+            Err(ReadError::File {
+                path: actual,
+                error,
+            }) => {
+                assert_eq!(actual, path);
+                assert_eq!(error.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("expected a file error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_bytes() {
+        let expected = Value::object([("key", Value::string("value"))]);
+        let utf16: Vec<u8> = "\u{FEFF}{\"key\": \"value\"}"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+
+        assert_eq!(parse_bytes(br#"{"key": "value"}"#).unwrap(), expected);
+        assert_eq!(parse_bytes(&utf16).unwrap(), expected);
+    }
+
+    #[test]
+    fn parses_utf16() {
+        let text = r#"{"emoji": ["😀", "é"], "n": 1}"#;
+        let units: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(parse_utf16(&units), parse(text));
+
+        let with_bom: Vec<u16> = format!("\u{FEFF}{text}").encode_utf16().collect();
+        assert_eq!(parse_utf16(&with_bom), parse(text));
+        let options = ParseOptions::new().reject_byte_order_mark(true);
+        assert_eq!(
+            parse_utf16_with(&with_bom, &options),
+            Err(TokenizeError::ByteOrderMark.locate("", 0))
+        );
+
+        let lone = [u16::from(b'"'), 0xDC00, u16::from(b'"')];
+        match parse_utf16(&lone) {
+            Err(Error {
+                kind: ErrorKind::Encoding(error),
+                ..
+            }) => assert_eq!(error.offset, 2),
+            other => panic!("expected an encoding error, got {other:?}"),
+        }
+        assert_eq!(
+            parse_utf16(&[]),
+            Err(TokenParseError::EmptyInput.locate("", || 0))
+        );
+    }
+
+    #[test]
+    fn parses_long_utf16_bytes() {
+        // longer than a chunk, so that characters and tokens straddle chunks
+        let items: Vec<String> = (0..4000).map(|i| format!("\"é{i}😀\"")).collect();
+        let text = format!("[{}]", items.join(", "));
+        for big_endian in [true, false] {
+            let bytes: Vec<u8> = text
+                .encode_utf16()
+                .flat_map(|unit| match big_endian {
+                    true => unit.to_be_bytes(),
+                    false => unit.to_le_bytes(),
+                })
+                .collect();
+            assert_eq!(parse_bytes(&bytes), parse(&text));
+        }
+    }
+
+    #[test]
+    fn errors_chain_sources() {
+        use std::error::Error;
+
+        fn read(input: &str) -> Result<Value, Box<dyn Error>> {
+            Ok(parse_reader(input.as_bytes())?)
+        }
+
+        let error = read("[1, tru]").unwrap_err();
+        let mut messages = vec![error.to_string()];
+        let mut source = error.source();
+        while let Some(error) = source {
+            messages.push(error.to_string());
+            source = error.source();
+        }
+        assert_eq!(
+            messages,
+            [
+                "failed to parse input",
+                "expected `true`, `false` or `null` at line 1, column 5"
+            ]
+        );
+
+        let error = parse("[1, 2").unwrap_err();
+        assert_eq!(error.to_string(), "unclosed array at line 1, column 6");
+        assert!(error.source().is_none());
+        let error = parse("\"\\q\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid escape `\\q` in string at line 1, column 2"
+        );
+        let error = parse("[\n  \"\t\"]").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unescaped control character '\\t' in string at line 2, column 4"
+        );
+        let error = parse_bytes(b"[\"\xC0\"]").unwrap_err();
+        assert_eq!(error.to_string(), "invalid UTF-8 at byte 2");
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "invalid UTF-8 at byte 2"
+        );
+    }
+
+    #[test]
+    fn parse_bytes_invalid_utf8() {
+        let expected = EncodingError {
+            encoding: encoding::Encoding::Utf8,
+            offset: 2,
+        };
+
+        let actual = parse_bytes(b"[\"\xC0\"]").unwrap_err();
+        assert_eq!(actual, expected.into());
     }
 
     #[test]
@@ -112,9 +603,9 @@ mod tests {
         check(
             "[1, 2, 3]",
             Value::Array(vec![
-                Value::Number(1.0),
-                Value::Number(2.0),
-                Value::Number(3.0),
+                Value::Number(Number::I64(1)),
+                Value::Number(Number::I64(2)),
+                Value::Number(Number::I64(3)),
             ]),
         )
     }
@@ -136,7 +627,7 @@ mod tests {
     fn object_with_number() {
         check(
             r#"{"key": 1}"#,
-            Value::object([("key", Value::Number(1.0))]),
+            Value::object([("key", Value::Number(Number::I64(1)))]),
         );
     }
 
@@ -144,7 +635,7 @@ mod tests {
     fn object_with_string() {
         check(
             r#"{"key": "value"}"#,
-            Value::object([("key", Value::String("value".to_string()))]),
+            Value::object([("key", Value::string("value"))]),
         );
     }
 
@@ -182,7 +673,7 @@ mod tests {
         check(
             r#"{ "a": 1, "b": "ya like jazz?", "c": false }"#,
             Value::object([
-                ("a", Value::Number(1.0)),
+                ("a", Value::Number(Number::I64(1))),
                 ("b", Value::string("ya like jazz?")),
                 ("c", Value::Boolean(false)),
             ]),
@@ -190,25 +681,743 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "this fails - for the sake of brevity, leaving this unfixed"]
+    fn parse_into_btree_map() {
+        let actual: Value<BTreeMapKind> =
+            r#"{ "z": 1, "a": [{ "m": 2, "b": 3 }] }"#.parse().unwrap();
+        let Value::Object(map) = &actual else {
+            panic!("expected an object");
+        };
+        let keys: Vec<&str> = map.keys().map(|key| &**key).collect();
+        assert_eq!(keys, ["a", "z"]);
+
+        let Some(Value::Array(array)) = map.get("a") else {
+            panic!("expected an array");
+        };
+        let Value::Object(inner) = &array[0] else {
+            panic!("expected an object");
+        };
+        let inner_keys: Vec<&str> = inner.keys().map(|key| &**key).collect();
+        assert_eq!(inner_keys, ["b", "m"]);
+    }
+
+    #[test]
+    fn parse_with_duplicate_key_policy() {
+        let options = ParseOptions::new().duplicate_keys(DuplicateKeyPolicy::FirstWins);
+        let actual = parse_with(String::from(r#"{"key": 1, "key": 2}"#), &options).unwrap();
+
+        assert_eq!(
+            actual,
+            Value::object([("key", Value::Number(Number::I64(1)))])
+        );
+    }
+
+    #[test]
+    fn parse_reports_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let options = ParseOptions {
+            progress: Some(ProgressCallback::new(10, move |progress| {
+                sink.lock().unwrap().push(progress)
+            })),
+            ..ParseOptions::default()
+        };
+        let input = r#"{"key": [1, 2, 3], "other": "a string"}"#;
+
+        parse_with(input, &options).unwrap();
+        let reports = reports.lock().unwrap();
+        let bytes: Vec<usize> = reports.iter().map(|progress| progress.bytes).collect();
+        assert_eq!(bytes, [1, 11, 26, 38, input.len()]);
+        assert_eq!(
+            reports.last(),
+            Some(&Progress {
+                bytes: input.len(),
+                tokens: 15,
+                values: 6,
+            })
+        );
+    }
+
+    #[test]
+    fn options_apply_to_every_entry_point() {
+        let options = ParseOptions::new()
+            .duplicate_keys(DuplicateKeyPolicy::Error)
+            .max_memory(1 << 20);
+        let input = r#"{"key": 1, "key": 2}"#;
+        let expected = TokenParseError::DuplicateKey(String::from("key")).locate(input, || 11);
+
+        assert_eq!(parse_with(input, &options), Err(expected.clone()));
+        assert_eq!(
+            parse_bytes_with(input.as_bytes(), &options),
+            Err(expected.clone())
+        );
+        match parse_reader_with(input.as_bytes(), &options) {
+            Err(ReadError::Parse(err)) => assert_eq!(err, expected),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn profiles() {
+        let strict = ParseOptions::new().profile(ParseProfile::Rfc8259Strict);
+        let relaxed = ParseOptions::new().profile(ParseProfile::Relaxed);
+        assert_eq!(strict, ParseOptions::default());
+
+        let cases: [(&str, Error, Value); 5] = [
+            (
+                "[1, 2,]",
+                TokenParseError::TrailingComma.locate("[1, 2,]", || 5),
+                Value::Array(vec![1.into(), 2.into()]),
+            ),
+            (
+                r#"{"a": 1,}"#,
+                TokenParseError::TrailingComma.locate(r#"{"a": 1,}"#, || 7),
+                Value::object([("a", 1.into())]),
+            ),
+            (
+                "true false",
+                TokenParseError::UnexpectedTrailingTokens { offset: 5 }.locate("true false", || 5),
+                Value::Boolean(true),
+            ),
+            (
+                r#""\q""#,
+                TokenParseError::InvalidEscape {
+                    escape: 'q',
+                    offset: 0,
+                }
+                .locate(r#""\q""#, || 0),
+                Value::string("q"),
+            ),
+            (
+                "\"a\tb\"",
+                TokenizeError::ControlCharacter {
+                    character: '\t',
+                    offset: 2,
+                }
+                .locate("\"a\tb\"", 0),
+                Value::string("a\tb"),
+            ),
+        ];
+        for (input, error, value) in cases {
+            assert_eq!(parse_with(input, &strict), Err(error), "{input}");
+            assert_eq!(parse_with(input, &relaxed), Ok(value), "{input}");
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let input = "// settings\n{\n  \"a\": [1, /* two */ 2], // trailing\n  \"b\": \"/* not a comment */\"\n}\n";
+        let options = ParseOptions::new().allow_comments(true);
+        let expected = Value::object([
+            ("a", Value::Array(vec![1.into(), 2.into()])),
+            ("b", Value::string("/* not a comment */")),
+        ]);
+
+        assert_eq!(parse_with(input, &options), Ok(expected));
+        check_error(input, TokenizeError::CharNotRecognized('/'));
+        assert_eq!(
+            parse_with("[1 /* open", &options),
+            Err(TokenizeError::UnclosedComment { offset: 3 }.locate("[1 /* open", 3))
+        );
+        assert_eq!(
+            parse_with("// only a comment", &options),
+            Err(TokenParseError::EmptyInput.locate("// only a comment", || 17))
+        );
+    }
+
+    #[test]
+    fn single_quotes() {
+        let input = r#"{'name': 'it\'s "quoted"', "b": ['[', "'"]}"#;
+        let options = ParseOptions::new().allow_single_quotes(true);
+        let expected = Value::object([
+            ("name", Value::string(r#"it's "quoted""#)),
+            (
+                "b",
+                Value::Array(vec![Value::string("["), Value::string("'")]),
+            ),
+        ]);
+
+        assert_eq!(parse_with(input, &options), Ok(expected));
+        assert_eq!(validate_with(input, &options), Ok(()));
+        check_error(input, TokenizeError::CharNotRecognized('\''));
+        assert_eq!(
+            parse_with("['open]", &options),
+            Err(TokenizeError::UnclosedQuotes.locate("['open]", 1))
+        );
+    }
+
+    #[test]
+    fn unquoted_keys() {
+        let input = "{name: \"a\", $id_2 : 1, null: null, nested: {true: [true]}}";
+        let options = ParseOptions::new().allow_unquoted_keys(true);
+        let expected = Value::object([
+            ("name", Value::string("a")),
+            ("$id_2", 1.into()),
+            ("null", Value::Null),
+            (
+                "nested",
+                Value::object([("true", Value::Array(vec![Value::Boolean(true)]))]),
+            ),
+        ]);
+
+        assert_eq!(parse_with(input, &options), Ok(expected));
+        assert_eq!(validate_with(input, &options), Ok(()));
+        check_error(input, TokenizeError::UnfinishedLiteralValue);
+        assert_eq!(
+            parse_with("[value]", &options),
+            Err(TokenizeError::CharNotRecognized('v').locate("[value]", 1))
+        );
+    }
+
+    #[test]
+    fn json5() {
+        let input = "// JSON5\n{\n  unquoted: 'and you can quote me on that',\n  singleQuotes: 'I can use \"double quotes\" here',\n  lineBreaks: \"Look, Mom! \\\nNo \\\\n's!\",\n  hexadecimal: 0xdecaf,\n  negativeHex: -0x10,\n  positiveSign: +1,\n  trailingComma: 'in objects', andIn: ['arrays',],\n  \"backwardsCompatible\": \"with JSON\",\n  infinite: [Infinity, -Infinity],\n}\n";
+        let options = ParseOptions::new().profile(ParseProfile::Json5);
+        let expected = Value::object([
+            ("unquoted", Value::string("and you can quote me on that")),
+            (
+                "singleQuotes",
+                Value::string(r#"I can use "double quotes" here"#),
+            ),
+            ("lineBreaks", Value::string("Look, Mom! No \\n's!")),
+            ("hexadecimal", 0xdecaf.into()),
+            ("negativeHex", (-16).into()),
+            ("positiveSign", 1.into()),
+            ("trailingComma", Value::string("in objects")),
+            ("andIn", Value::Array(vec![Value::string("arrays")])),
+            ("backwardsCompatible", Value::string("with JSON")),
+            (
+                "infinite",
+                Value::Array(vec![f64::INFINITY.into(), f64::NEG_INFINITY.into()]),
+            ),
+        ]);
+
+        assert_eq!(parse_with(input, &options), Ok(expected));
+        assert_eq!(validate_with(input, &options), Ok(()));
+        assert!(parse(input).is_err());
+        let Ok(Value::Number(nan)) = parse_with("NaN", &options) else {
+            panic!("expected a number");
+        };
+        assert!(nan.as_f64().is_nan());
+        assert_eq!(
+            parse_with("0xG", &options),
+            Err(TokenizeError::CharNotRecognized('G').locate("0xG", 0))
+        );
+    }
+
+    #[test]
+    fn unicode_whitespace() {
+        let input = "[1\u{c}]";
+        let error = TokenizeError::CharNotRecognized('\u{c}').locate(input, 2);
+        for (backend, result) in differential::parse_everywhere(input, &ParseOptions::default()) {
+            assert_eq!(result, Err((error.kind.clone(), Some(2))), "{backend:?}");
+        }
+        assert_eq!(parse_with(input, &ParseOptions::default()), Err(error));
+
+        let input = "\u{b}[1,\u{a0}2\u{2028}\u{c}]\u{3000}";
+        let options = ParseOptions::new().allow_unicode_whitespace(true);
+        let expected = Value::Array(vec![1.into(), 2.into()]);
+        assert_eq!(differential::assert_agree(input, &options), Ok(expected));
+        assert!(parse(input).is_err());
+    }
+
+    #[test]
+    fn non_finite_numbers() {
+        // as written by Python's `json.dumps`
+        let input = r#"{"nan": NaN, "values": [Infinity, -Infinity, 1.5]}"#;
+        let options = ParseOptions::new().allow_non_finite_numbers(true);
+        let check = |value: Value| {
+            let map = value.as_object().unwrap();
+            let nan = map.get("nan").and_then(Value::as_f64);
+            assert!(nan.is_some_and(f64::is_nan), "{value:?}");
+            let values = map.get("values").cloned();
+            let expected = vec![f64::INFINITY.into(), f64::NEG_INFINITY.into(), 1.5.into()];
+            assert_eq!(values, Some(Value::Array(expected)));
+        };
+
+        check(parse_with(input, &options).unwrap());
+        check(
+            BorrowedValue::parse_with(input, &options)
+                .unwrap()
+                .to_value(),
+        );
+        check(
+            lazy::LazyValue::parse_with(input, &options)
+                .unwrap()
+                .to_value()
+                .unwrap(),
+        );
+        check(tape::Tape::parse_with(input, &options).unwrap().to_value());
+        assert_eq!(validate_with(input, &options), Ok(()));
+
+        check_error(input, TokenizeError::CharNotRecognized('N'));
+        assert!(tape::Tape::parse(input).is_err());
+        assert!(lazy::LazyValue::parse(input).is_err());
+    }
+
+    #[test]
+    fn trailing_commas_in_every_backend() {
+        let input = r#"{"a": [1, 2,], "b": {"c": null,},}"#;
+        let options = ParseOptions::new().allow_trailing_commas(true);
+        let expected = parse(r#"{"a": [1, 2], "b": {"c": null}}"#).unwrap();
+
+        assert_eq!(parse_with(input, &options).as_ref(), Ok(&expected));
+        assert_eq!(validate_with(input, &options), Ok(()));
+        let borrowed = BorrowedValue::parse_with(input, &options).unwrap();
+        assert_eq!(borrowed.to_value(), expected);
+        let lazy = lazy::LazyValue::parse_with(input, &options).unwrap();
+        assert_eq!(lazy.to_value().as_ref(), Ok(&expected));
+        let tape = tape::Tape::parse_with(input, &options).unwrap();
+        assert_eq!(tape.to_value(), expected);
+        #[cfg(feature = "arena")]
+        {
+            let arena = arena::ParseArena::new();
+            let value = arena.parse_with(input, &options).unwrap();
+            assert_eq!(value.to_value(), expected);
+        }
+
+        let mut reader = pull::JsonReader::new("[1, 2,]", options);
+        reader.next_event().unwrap();
+        let mut elements = 0;
+        while reader.next_element().unwrap() {
+            reader.skip_value().unwrap();
+            elements += 1;
+        }
+        assert_eq!(elements, 2);
+        assert_eq!(reader.next_event(), Ok(None));
+
+        let error = Err(TokenParseError::TrailingComma.locate(input, || 11));
+        assert_eq!(validate(input), error);
+        assert_eq!(BorrowedValue::parse(input).map(|_| ()), error);
+        assert_eq!(lazy::LazyValue::parse(input).map(|_| ()), error);
+        assert_eq!(tape::Tape::parse(input).map(|_| ()), error);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let input = "\u{FEFF}[1]";
+        let expected = parse("[1]").unwrap();
+        let push = |input: &str, options: ParseOptions| {
+            let mut parser = PushParser::with_options(options);
+            for chunk in input.as_bytes().chunks(1) {
+                parser.feed(chunk)?;
+            }
+            parser.finish()
+        };
+
+        assert_eq!(parse(input).as_ref(), Ok(&expected));
+        assert_eq!(parse_bytes(input.as_bytes()).as_ref(), Ok(&expected));
+        assert_eq!(validate(input), Ok(()));
+        assert_eq!(BorrowedValue::parse(input).unwrap().to_value(), expected);
+        let lazy = lazy::LazyValue::parse(input).unwrap();
+        assert_eq!(lazy.to_value().as_ref(), Ok(&expected));
+        assert_eq!(tape::Tape::parse(input).unwrap().to_value(), expected);
+        assert_eq!(push(input, ParseOptions::new()), Ok(expected));
+
+        let options = ParseOptions::new().reject_byte_order_mark(true);
+        let error = Err(TokenizeError::ByteOrderMark.locate(input, 0));
+        assert_eq!(parse_with(input, &options).map(|_| ()), error);
+        assert_eq!(
+            parse_bytes_with(input.as_bytes(), &options).map(|_| ()),
+            error
+        );
+        assert_eq!(validate_with(input, &options), error);
+        assert_eq!(
+            BorrowedValue::parse_with(input, &options).map(|_| ()),
+            error
+        );
+        assert_eq!(push(input, options.clone()).map(|_| ()), error);
+        // the backends that scan the input themselves don't locate errors
+        let error = Err(ErrorKind::ByteOrderMark);
+        let kind = |result: Result<(), Error>| result.map_err(|err| err.kind);
+        assert_eq!(
+            kind(lazy::LazyValue::parse_with(input, &options).map(|_| ())),
+            error
+        );
+        assert_eq!(
+            kind(tape::Tape::parse_with(input, &options).map(|_| ())),
+            error
+        );
+
+        let error = Err(TokenizeError::CharNotRecognized('\u{FEFF}').locate("[\u{FEFF}1]", 1));
+        assert_eq!(parse("[\u{FEFF}1]").map(|_| ()), error);
+        assert_eq!(push("[\u{FEFF}1]", ParseOptions::new()).map(|_| ()), error);
+    }
+
+    #[test]
+    fn size_limits() {
+        let options = ParseOptions::new()
+            .max_string_length(5)
+            .max_array_length(3)
+            .max_object_entries(2);
+        // an error is expected at the byte offset with it
+        let check = |input: &str, expected: Result<(), (TokenParseError, usize)>| {
+            let actual = parse_with(input, &options).map(|_| ());
+            let expected = expected.map_err(|(err, offset)| err.locate(input, || offset));
+            assert_eq!(actual, expected, "{input}");
+        };
+
+        check(r#"["abcde", "\u0061bcde"]"#, Ok(()));
+        check(r#"["abcdef"]"#, Err((TokenParseError::StringTooLong, 1)));
+        check(r#"{"abcdef": 1}"#, Err((TokenParseError::StringTooLong, 1)));
+        check("[1, [2, 3, 4], 5]", Ok(()));
+        check(
+            "[[1, 2, 3, 4]]",
+            Err((TokenParseError::TooManyElements, 11)),
+        );
+        check(r#"{"a": 1, "b": 2, "a": 3}"#, Ok(()));
+        check(
+            r#"{"a": {"b": 1, "c": 2, "d": 3}}"#,
+            Err((TokenParseError::TooManyEntries, 23)),
+        );
+    }
+
+    #[test]
+    fn duplicate_key_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let found = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&found);
+        let options = ParseOptions::new().duplicate_key_callback(DuplicateKeyCallback::new(
+            move |duplicate| sink.lock().unwrap().push(duplicate),
+        ));
+        let input = r#"{"a":1,"b":{"a":2},"a":3,"\u0061":4,"c":[{"d":1},{"d":1}]}"#;
+        let duplicate = |second| DuplicateKey {
+            key: "a".to_string(),
+            first: 1,
+            second,
+        };
+        let expected = vec![duplicate(19), duplicate(25)];
+
+        let value = parse_with(input, &options).unwrap();
+        assert_eq!(value.as_object().unwrap().get("a"), Some(&4.into()));
+        assert_eq!(std::mem::take(&mut *found.lock().unwrap()), expected);
+
+        let mut parser = PushParser::with_options(options.clone());
+        for chunk in input.as_bytes().chunks(3) {
+            parser.feed(chunk).unwrap();
+        }
+        parser.finish().unwrap();
+        assert_eq!(std::mem::take(&mut *found.lock().unwrap()), expected);
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        // the tokens are parsed as they're read, so text after an error in
+        // the structure isn't tokenized
+        let input = "[1 2] \"unclosed";
+        let expected = TokenParseError::ExpectedComma.locate(input, || 3);
+        assert_eq!(parse(input), Err(expected));
+
+        let input = "[1, @] 2";
+        let expected = TokenizeError::CharNotRecognized('@').locate(input, 4);
+        assert_eq!(parse(input), Err(expected));
+    }
+
+    #[test]
+    fn trailing_content() {
+        let error = |input, offset| {
+            Err(TokenParseError::UnexpectedTrailingTokens { offset }.locate(input, || offset))
+        };
+        assert_eq!(parse("true false"), error("true false", 5));
+        assert_eq!(parse(" [1, 2]\n  {} "), error(" [1, 2]\n  {} ", 10));
+        assert_eq!(parse("[1]]"), error("[1]]", 3));
+        assert_eq!(
+            parse_reader("{}\t1".as_bytes()).map_err(|err| match err {
+                ReadError::Parse(err) => err,
+                err => panic!("{err:?}"),
+            }),
+            error("{}\t1", 3)
+        );
+        let mut parser = PushParser::new();
+        parser.feed(b"\"a\"\n").unwrap();
+        parser.feed(b" \"b\"").unwrap();
+        assert_eq!(parser.finish(), error("\"a\"\n \"b\"", 5));
+
+        let options = ParseOptions::new().allow_trailing_content(true);
+        assert_eq!(parse_with("true false", &options), Ok(Value::Boolean(true)));
+        // the text after the value isn't tokenized
+        assert_eq!(parse_with("true @", &options), Ok(Value::Boolean(true)));
+
+        assert_eq!(parse_partial("true false"), Ok((Value::Boolean(true), 4)));
+        let input = " {\"a\": [1]} rest";
+        let (value, end) = parse_partial(input).unwrap();
+        assert_eq!(value, Value::object([("a", Value::Array(vec![1.into()]))]));
+        assert_eq!(&input[end..], " rest");
+        for input in ["[1, 2", "{\"a\": [1", "{\"a\""] {
+            let partial = parse_partial(input).map(|(value, _)| value);
+            assert_eq!(partial, parse(input), "{input:?}");
+        }
+        assert_eq!(
+            parse_partial("[1, 2"),
+            Err(TokenParseError::UnclosedBracket.locate("[1, 2", || 5))
+        );
+        assert_eq!(
+            parse_partial("  "),
+            Err(TokenParseError::EmptyInput.locate("  ", || 2))
+        );
+    }
+
+    #[test]
+    fn locates_invalid_escapes() {
+        let escape = |offset| ErrorKind::InvalidEscape {
+            escape: 'q',
+            offset,
+        };
+        for (input, offset) in [(r#"[1, "ab\q"]"#, 7), (r#"{"a": 1, "b\q": 2}"#, 11)] {
+            for backend in differential::Backend::ALL {
+                let error = backend.parse(input, &ParseOptions::default()).unwrap_err();
+                assert_eq!(error.kind, escape(offset), "{input} with {backend:?}");
+                let location = Some(Location::new(input, offset));
+                assert_eq!(error.location, location, "{input} with {backend:?}");
+            }
+        }
+
+        let input = "[\n  \"ab\\q\"]";
+        let error = parse(input).unwrap_err();
+        let location = error.location.unwrap();
+        assert_eq!((location.line_number(), location.column_number()), (2, 6));
+        let rendered = render_error(input, &error);
+        assert!(
+            rendered.ends_with("2 |   \"ab\\q\"]\n  |      ^"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn locates_lone_surrogates() {
+        let input = "{\n  \"k\": \"é\\ud800x\"\n}";
+        let kind = ErrorKind::LoneSurrogate {
+            surrogate: 0xD800,
+            offset: 12,
+        };
+        for backend in differential::Backend::ALL {
+            let error = backend.parse(input, &ParseOptions::default()).unwrap_err();
+            assert_eq!(error.kind, kind, "{backend:?}");
+            let location = error.location.unwrap();
+            assert_eq!(location.offset, 12, "{backend:?}");
+            assert_eq!(
+                (location.line_number(), location.column_number()),
+                (2, 10),
+                "{backend:?}"
+            );
+        }
+        assert_eq!(
+            parse(input).unwrap_err().to_string(),
+            "unpaired surrogate `\\uD800` in string at line 2, column 10"
+        );
+    }
+
+    #[test]
+    fn exponents_in_every_backend() {
+        let input = "[1e10, 2.5E-3, -4e+2, 0E0]";
+        let expected = Value::Array(vec![1e10.into(), 2.5e-3.into(), (-4e2).into(), 0.0.into()]);
+
+        assert_eq!(parse(input).as_ref(), Ok(&expected));
+        assert_eq!(validate(input), Ok(()));
+        let borrowed = BorrowedValue::parse(input).unwrap();
+        assert_eq!(borrowed.to_value(), expected);
+        let lazy = lazy::LazyValue::parse(input).unwrap();
+        assert_eq!(lazy.to_value().as_ref(), Ok(&expected));
+        let tape = tape::Tape::parse(input).unwrap();
+        assert_eq!(tape.to_value(), expected);
+        let mut parser = push::PushParser::new();
+        for chunk in input.as_bytes().chunks(2) {
+            parser.feed(chunk).unwrap();
+        }
+        assert_eq!(parser.finish().as_ref(), Ok(&expected));
+
+        let error = Err(TokenizeError::MissingExponentDigits.locate("[1e+]", 1));
+        assert_eq!(parse("[1e+]").map(|_| ()), error);
+        assert_eq!(validate("[1e+]"), error);
+        let error = tape::Tape::parse("[1e+]").map_err(|err| err.kind);
+        assert_eq!(error.map(|_| ()), Err(ErrorKind::MissingExponentDigits));
+    }
+
+    #[test]
+    fn control_characters_in_every_backend() {
+        let input = "{\"a\": [\"tab\there\"]}";
+        let located = Err(TokenizeError::ControlCharacter {
+            character: '\t',
+            offset: 11,
+        }
+        .locate(input, 7));
+        let error = Err(ErrorKind::ControlCharacter('\t'));
+        let kind = |result: Result<(), Error>| result.map_err(|err| err.kind);
+
+        assert_eq!(parse(input).map(|_| ()), located);
+        assert_eq!(validate(input), located);
+        assert_eq!(kind(BorrowedValue::parse(input).map(|_| ())), error);
+        assert_eq!(kind(lazy::LazyValue::parse(input).map(|_| ())), error);
+        assert_eq!(kind(tape::Tape::parse(input).map(|_| ())), error);
+        let mut parser = push::PushParser::new();
+        let mut chunks = input.as_bytes().chunks(3);
+        assert_eq!(chunks.try_for_each(|chunk| parser.feed(chunk)), located);
+
+        let options = ParseOptions::new().allow_control_characters(true);
+        let expected = Value::object([("a", Value::Array(vec![Value::string("tab\there")]))]);
+        assert_eq!(parse_with(input, &options).as_ref(), Ok(&expected));
+        let tape = tape::Tape::parse_with(input, &options).unwrap();
+        assert_eq!(tape.to_value(), expected);
+        let lazy = lazy::LazyValue::parse_with(input, &options).unwrap();
+        assert_eq!(lazy.to_value().as_ref(), Ok(&expected));
+    }
+
+    #[test]
+    fn empty_input_in_every_backend() {
+        for input in ["", "   ", "\n\t\r\n "] {
+            let error = Err(TokenParseError::EmptyInput.locate(input, || input.len()));
+            assert_eq!(parse(input).map(|_| ()), error);
+            assert_eq!(validate(input), error);
+            assert_eq!(BorrowedValue::parse(input).map(|_| ()), error);
+            assert_eq!(lazy::LazyValue::parse(input).map(|_| ()), error);
+            assert_eq!(tape::Tape::parse(input).map(|_| ()), error);
+            assert_eq!(parse_partial(input).map(|_| ()), error);
+            let mut parser = push::PushParser::new();
+            parser.feed(input.as_bytes()).unwrap();
+            assert_eq!(parser.finish().map(|_| ()), error);
+        }
+
+        // an unfinished document is still reported as one
+        assert_eq!(parse("[").unwrap_err().kind, ErrorKind::UnclosedArray);
+        assert_eq!(
+            validate("{"),
+            Err(TokenParseError::UnclosedBrace.locate("{", || 1))
+        );
+    }
+
+    #[test]
+    fn parses_deeply_nested_arrays() {
+        let depth = 100_000;
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        let options = ParseOptions {
+            max_depth: None,
+            ..ParseOptions::default()
+        };
+        let mut value = parse_with(input, &options).unwrap();
+        // taken apart one level at a time, since dropping it whole would
+        // recurse as deep as it's nested
+        let mut levels = 0;
+        while let Value::Array(mut array) = value {
+            levels += 1;
+            value = array.pop().unwrap_or(Value::Null);
+        }
+        assert_eq!(levels, depth);
+    }
+
+    #[test]
+    fn default_depth_limit_in_every_backend() {
+        let depth = 1_000_000;
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        for (backend, result) in differential::parse_everywhere(&input, &ParseOptions::default()) {
+            let expected = Err((ErrorKind::TooDeep, Some(128)));
+            assert_eq!(result, expected, "{backend:?}");
+        }
+    }
+
+    #[test]
+    fn depth_limit_in_every_backend() {
+        let options = ParseOptions::new().max_depth(2);
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        for input in [r#"[{"a": [1]}]"#, "[[[]]]", &deep] {
+            let kind = |result: Result<(), Error>| result.unwrap_err().kind;
+            assert_eq!(
+                kind(parse_with(input, &options).map(|_| ())),
+                ErrorKind::TooDeep
+            );
+            assert_eq!(kind(validate_with(input, &options)), ErrorKind::TooDeep);
+            let borrowed = BorrowedValue::parse_with(input, &options).map(|_| ());
+            assert_eq!(kind(borrowed), ErrorKind::TooDeep);
+            let lazy = lazy::LazyValue::parse_with(input, &options).map(|_| ());
+            assert_eq!(kind(lazy), ErrorKind::TooDeep);
+            let tape = tape::Tape::parse_with(input, &options).map(|_| ());
+            assert_eq!(kind(tape), ErrorKind::TooDeep);
+            let mut parser = push::PushParser::with_options(options.clone());
+            let pushed = parser.feed(input.as_bytes()).and_then(|_| parser.finish());
+            assert_eq!(kind(pushed.map(|_| ())), ErrorKind::TooDeep);
+            let (_, errors) = parse_with_recovery(input, &options);
+            let kinds: Vec<_> = errors.into_iter().map(|error| error.kind).collect();
+            assert_eq!(kinds, [ErrorKind::TooDeep]);
+        }
+
+        let input = r#"[{"a": [1]}, [[]]]"#;
+        assert!(parse_with(input, &ParseOptions::new().max_depth(3)).is_ok());
+        assert!(tape::Tape::parse_with(input, &ParseOptions::new().max_depth(3)).is_ok());
+        assert_eq!(
+            parse_with(input, &options),
+            Err(TokenizeError::TooDeep.locate(input, 7))
+        );
+    }
+
+    #[test]
+    fn parse_stops_when_cancelled() {
+        let cancel = CancelToken::new();
+        let options = ParseOptions {
+            cancel: Some(cancel.clone()),
+            ..ParseOptions::default()
+        };
+        assert_eq!(parse_with("[1, 2]", &options), parse("[1, 2]"));
+
+        // cancelled partway through, by the progress callback
+        let stop = cancel.clone();
+        let options = ParseOptions {
+            progress: Some(ProgressCallback::new(4, move |progress| {
+                if progress.bytes >= 4 {
+                    stop.cancel()
+                }
+            })),
+            ..options
+        };
+        let input = "[1, 2, 3, 4, 5]";
+        assert_eq!(
+            parse_with(input, &options),
+            Err(TokenizeError::Cancelled.locate(input, 5))
+        );
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    #[cfg(feature = "preserve_order")]
+    fn object_keeps_key_order() {
+        let actual = parse(String::from(r#"{ "z": 1, "a": 2, "m": 3 }"#)).unwrap();
+        let Value::Object(map) = actual else {
+            panic!("expected an object");
+        };
+        let keys: Vec<&str> = map.keys().map(|key| &**key).collect();
+        assert_eq!(keys, ["z", "a", "m"]);
+    }
+
+    #[test]
     fn err_unclosed_array() {
-        check_error(
-            "[null",
-            ParseError::ParseError(TokenParseError::UnclosedBracket),
-        )
+        check_error("[null", TokenParseError::UnclosedBracket)
     }
 
     #[test]
-    #[ignore = "this fails - for the sake of brevity, leaving this unfixed"]
     fn err_unclosed_object() {
-        check_error(
-            r#"{"key":"value""#,
-            ParseError::ParseError(TokenParseError::UnclosedBrace),
-        )
+        check_error(r#"{"key":"value""#, TokenParseError::UnclosedBrace)
+    }
+
+    #[test]
+    fn err_truncated_documents() {
+        let cases = [
+            ("[", TokenParseError::UnclosedBracket),
+            ("[1,", TokenParseError::UnclosedBracket),
+            ("{", TokenParseError::UnclosedBrace),
+            (r#"{"a""#, TokenParseError::UnclosedBrace),
+            (r#"{"a":"#, TokenParseError::UnclosedBrace),
+            (r#"{"a": 1,"#, TokenParseError::UnclosedBrace),
+            (r#"{"a": [1, {}"#, TokenParseError::UnclosedBracket),
+            (r#"[{"a": 1}, {"b": 2"#, TokenParseError::UnclosedBrace),
+        ];
+        for (input, expected) in cases {
+            check_error(input, expected);
+        }
     }
 
     #[test]
     fn err_expected_value() {
-        check_error("]", ParseError::ParseError(TokenParseError::ExpectedValue))
+        check_error("]", TokenParseError::ExpectedValue)
     }
 }
@@ -0,0 +1,167 @@
+//! Newline-delimited JSON (JSON Lines, NDJSON), with one document per line
+
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::encoding::{Encoding, EncodingError};
+use crate::{parse, Error, ReadError, Value};
+
+/// An error in one line of newline-delimited JSON
+#[derive(Debug, PartialEq)]
+pub struct LineError<E = Error> {
+    /// Line number of the document, starting from 1
+    pub line: usize,
+
+    pub error: E,
+}
+
+impl<E> fmt::Display for LineError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid document on line {}", self.line)
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for LineError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Parses each line of `input` as a separate JSON document.
+///
+/// Blank lines are skipped, and lines may end with `\r\n`. A line that
+/// doesn't parse produces an error with its line number, and iteration
+/// continues with the next line.
+pub fn parse_lines(input: &str) -> impl Iterator<Item = Result<Value, LineError>> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse_line(i + 1, line))
+}
+
+/// Parses each line read from `reader` as a separate JSON document, like
+/// [`parse_lines`], one line at a time.
+///
+/// An error reading the input ends the iteration after it's returned, since
+/// it's unknown where the next line would start.
+pub fn parse_lines_reader(
+    mut reader: impl BufRead,
+) -> impl Iterator<Item = Result<Value, LineError<ReadError>>> {
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    let mut failed = false;
+    std::iter::from_fn(move || loop {
+        if failed {
+            return None;
+        }
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                failed = true;
+                return Some(Err(LineError {
+                    line: line_number + 1,
+                    error: ReadError::Io(err),
+                }));
+            }
+        }
+        line_number += 1;
+
+        let result = match std::str::from_utf8(&buf) {
+            Ok(line) => parse_line(line_number, line),
+            // the offset is from the start of the line
+            Err(err) => Some(Err(LineError {
+                line: line_number,
+                error: Error::from(EncodingError {
+                    encoding: Encoding::Utf8,
+                    offset: err.valid_up_to(),
+                }),
+            })),
+        };
+        if let Some(result) = result {
+            return Some(result.map_err(|err| LineError {
+                line: err.line,
+                error: err.error.into(),
+            }));
+        }
+    })
+}
+
+/// Parses one line, or returns `None` when it's blank
+fn parse_line(line_number: usize, line: &str) -> Option<Result<Value, LineError>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(parse(line).map_err(|error| LineError {
+        line: line_number,
+        error,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_lines, parse_lines_reader, LineError};
+    use crate::encoding::EncodingError;
+    use crate::{Error, ErrorKind, ReadError, TokenParseError, Value};
+
+    #[test]
+    fn parses_each_line() {
+        let input = "{\"level\": \"info\"}\r\n\n[1, 2]\n  \n\"last\"";
+        let values: Vec<_> = parse_lines(input).collect();
+
+        let expected = [
+            Ok(Value::object([("level", Value::string("info"))])),
+            Ok(Value::Array(vec![1.into(), 2.into()])),
+            Ok(Value::string("last")),
+        ];
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let input = "1\n\n[1, @]\n{\"a\": }\n4";
+        let results: Vec<_> = parse_lines(input).collect();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Ok(1.into()));
+        assert!(matches!(results[1], Err(LineError { line: 3, .. })));
+        assert_eq!(
+            results[2],
+            Err(LineError {
+                line: 4,
+                error: TokenParseError::ExpectedValue.locate("{\"a\": }", || 6),
+            })
+        );
+        assert_eq!(results[3], Ok(4.into()));
+    }
+
+    #[test]
+    fn reader_matches_str() {
+        let input = b"true\n\n[1, @]\n\"\xFF\"\nnull\n";
+        let results: Vec<_> = parse_lines_reader(&input[..]).collect();
+
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[0], Ok(Value::Boolean(true))));
+        assert!(matches!(
+            results[1],
+            Err(LineError {
+                line: 3,
+                error: ReadError::Parse(_)
+            })
+        ));
+        assert!(matches!(
+            results[2],
+            Err(LineError {
+                line: 4,
+                error: ReadError::Parse(Error {
+                    kind: ErrorKind::Encoding(EncodingError { offset: 1, .. }),
+                    ..
+                })
+            })
+        ));
+        assert!(matches!(results[3], Ok(Value::Null)));
+    }
+}
@@ -0,0 +1,356 @@
+//! Data-quality checks for JSON documents, for issues that are valid JSON but
+//! likely to cause problems for consumers
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::parse::unescape_string;
+use crate::tokenize::{tokenize, Token};
+use crate::{validate_with, Error, ParseOptions};
+
+/// Which checks [`lint`] runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Report keys that appear more than once in an object
+    pub duplicate_keys: bool,
+
+    /// Report objects and arrays nested more deeply than this, with the root
+    /// at depth 1
+    pub max_depth: Option<usize>,
+
+    /// Report numbers that can't be stored exactly in an `f64`, which is all
+    /// that many JSON consumers (such as JavaScript) have
+    pub precision_loss: bool,
+
+    /// Report keys that contain characters outside of ASCII
+    pub non_ascii_keys: bool,
+
+    /// Report numbers with zeros at the end of their fraction, ex. `1.50`
+    pub trailing_zeros: bool,
+}
+
+/// Every check is enabled, with a maximum depth of 32
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_keys: true,
+            max_depth: Some(32),
+            precision_loss: true,
+            non_ascii_keys: true,
+            trailing_zeros: true,
+        }
+    }
+}
+
+/// An issue found by [`lint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Location of the issue, in the notation of
+    /// [`Value::flatten`](crate::Value::flatten). For key issues this is the
+    /// path of the key itself.
+    pub path: String,
+
+    /// What the issue is
+    pub kind: LintKind,
+}
+
+/// One of the issues that [`lint`] can report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// A key appeared more than once in the same object
+    DuplicateKey(String),
+
+    /// A container was nested beyond `max_depth`, at the given depth
+    ExcessiveDepth(usize),
+
+    /// The number (as written) would be rounded when stored as an `f64`
+    PrecisionLoss(String),
+
+    /// The key contains characters outside of ASCII
+    NonAsciiKey(String),
+
+    /// The number (as written) has zeros at the end of its fraction
+    TrailingZeros(String),
+}
+
+/// Checks a document for the issues enabled in `options`.
+///
+/// The document must be valid JSON, otherwise the parse error is returned.
+/// Issues are listed in the order they appear in the document.
+pub fn lint(input: &str, options: &LintOptions) -> Result<Vec<Lint>, Error> {
+    // the text of numbers is needed for the number checks
+    let parse_options = ParseOptions {
+        preserve_number_literals: true,
+        ..ParseOptions::default()
+    };
+    validate_with(input, &parse_options)?;
+    let tokens = tokenize(input, &parse_options)?;
+
+    let mut linter = Linter {
+        tokens: &tokens,
+        index: 0,
+        options,
+        lints: Vec::new(),
+    };
+    linter.value(String::new(), 1)?;
+    Ok(linter.lints)
+}
+
+/// Walks the tokens of a document that is known to parse
+struct Linter<'a> {
+    tokens: &'a [Token<'a>],
+    index: usize,
+    options: &'a LintOptions,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn value(&mut self, path: String, depth: usize) -> Result<(), Error> {
+        let token = &self.tokens[self.index];
+        self.index += 1;
+        match token {
+            Token::LeftBracket | Token::LeftBrace => {
+                // only the outermost container that is too deep is reported
+                if self.options.max_depth.is_some_and(|max| depth == max + 1) {
+                    self.push(&path, LintKind::ExcessiveDepth(depth));
+                }
+                if *token == Token::LeftBracket {
+                    self.array(path, depth)
+                } else {
+                    self.object(path, depth)
+                }
+            }
+            Token::Number(number) => {
+                let text = number.to_string();
+                if self.options.precision_loss && loses_precision(&text) {
+                    self.push(&path, LintKind::PrecisionLoss(text.clone()));
+                }
+                if self.options.trailing_zeros && has_trailing_zeros(&text) {
+                    self.push(&path, LintKind::TrailingZeros(text));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn array(&mut self, path: String, depth: usize) -> Result<(), Error> {
+        let mut i = 0;
+        while !self.close(&Token::RightBracket) {
+            self.value(format!("{path}[{i}]"), depth + 1)?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, path: String, depth: usize) -> Result<(), Error> {
+        let mut keys = HashSet::new();
+        while !self.close(&Token::RightBrace) {
+            let Token::String(key) = &self.tokens[self.index] else {
+                unreachable!("the document was parsed")
+            };
+            let key = unescape_string(key, &ParseOptions::default())?.into_owned();
+            // the key and colon
+            self.index += 2;
+
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            if self.options.non_ascii_keys && !key.is_ascii() {
+                self.push(&path, LintKind::NonAsciiKey(key.clone()));
+            }
+            if self.options.duplicate_keys && !keys.insert(key.clone()) {
+                self.push(&path, LintKind::DuplicateKey(key));
+            }
+            self.value(path, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Skips a separating comma, and consumes the closing token if it's next
+    fn close(&mut self, closing: &Token) -> bool {
+        if self.tokens[self.index] == Token::Comma {
+            self.index += 1;
+        }
+        let is_closed = self.tokens[self.index] == *closing;
+        if is_closed {
+            self.index += 1;
+        }
+        is_closed
+    }
+
+    fn push(&mut self, path: &str, kind: LintKind) {
+        self.lints.push(Lint {
+            path: path.to_string(),
+            kind,
+        });
+    }
+}
+
+/// Whether the nearest `f64` to the number has a different decimal value
+pub(crate) fn loses_precision(text: &str) -> bool {
+    let Ok(float) = text.parse::<f64>() else {
+        return false;
+    };
+    // `{:e}` writes the fewest digits that parse back to the same float
+    !float.is_finite() || decimal(text) != decimal(&format!("{float:e}"))
+}
+
+/// Splits a number into its significant digits and the exponent of the
+/// first digit, so that numbers with the same value compare equal. The
+/// sign is left out.
+pub(crate) fn decimal(text: &str) -> (String, i64) {
+    let text = text.trim_start_matches('-');
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], text[i + 1..].parse().unwrap_or(0)),
+        None => (text, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{integer}{fraction}");
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let significant = digits.trim_matches('0');
+    if significant.is_empty() {
+        return (String::new(), 0);
+    }
+    let position = exponent + integer.len() as i64 - leading_zeros as i64;
+    (significant.to_string(), position)
+}
+
+fn has_trailing_zeros(text: &str) -> bool {
+    let mantissa = text.split(['e', 'E']).next().unwrap_or(text);
+    mantissa.contains('.') && mantissa.ends_with('0')
+}
+
+/// Writes a one-line description, ex. `users[0].id: duplicate key "id"`
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str("(root): ")?;
+        } else {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            LintKind::DuplicateKey(key) => write!(f, "duplicate key {key:?}"),
+            LintKind::ExcessiveDepth(depth) => write!(f, "nested {depth} levels deep"),
+            LintKind::PrecisionLoss(n) => write!(f, "number {n} is rounded as a 64-bit float"),
+            LintKind::NonAsciiKey(key) => write!(f, "key {key:?} contains non-ASCII characters"),
+            LintKind::TrailingZeros(n) => write!(f, "number {n} has trailing zeros"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decimal, lint, loses_precision, LintKind, LintOptions};
+
+    fn check(input: &str, options: &LintOptions, expected: &[&str]) {
+        let lints: Vec<String> = lint(input, options)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(lints, expected);
+    }
+
+    #[test]
+    fn clean_document() {
+        check(
+            r#"{"a": [1, 2.5, "x"], "b": {"c": null}}"#,
+            &LintOptions::default(),
+            &[],
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let lints = lint(
+            r#"{"a": 1, "b": {"c": 1, "c": 2}, "a": 3}"#,
+            &LintOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(lints.len(), 2);
+        assert_eq!(lints[0].path, "b.c");
+        assert_eq!(lints[0].kind, LintKind::DuplicateKey(String::from("c")));
+        assert_eq!(lints[1].path, "a");
+    }
+
+    #[test]
+    fn excessive_depth() {
+        let options = LintOptions {
+            max_depth: Some(2),
+            ..LintOptions::default()
+        };
+
+        check(
+            r#"[[1], [[2, [3]]], {"a": {}}]"#,
+            &options,
+            &[
+                "[1][0]: nested 3 levels deep",
+                "[2].a: nested 3 levels deep",
+            ],
+        );
+        check(
+            "[[[1]]]",
+            &LintOptions {
+                max_depth: None,
+                ..options
+            },
+            &[],
+        );
+    }
+
+    #[test]
+    fn number_checks() {
+        check(
+            r#"{"id": 9007199254740993, "price": 1.50, "big": 18446744073709551617, "ok": [0.1, 100, 123456789012]}"#,
+            &LintOptions::default(),
+            &[
+                "id: number 9007199254740993 is rounded as a 64-bit float",
+                "price: number 1.50 has trailing zeros",
+                "big: number 18446744073709551617 is rounded as a 64-bit float",
+            ],
+        );
+    }
+
+    #[test]
+    fn non_ascii_keys() {
+        check(
+            r#"{"naïve": 1, "plain": 2}"#,
+            &LintOptions::default(),
+            &[r#"naïve: key "naïve" contains non-ASCII characters"#],
+        );
+    }
+
+    #[test]
+    fn disabled_checks() {
+        let options = LintOptions {
+            duplicate_keys: false,
+            max_depth: None,
+            precision_loss: false,
+            non_ascii_keys: false,
+            trailing_zeros: false,
+        };
+
+        check(r#"{"ü": [1.0, 9007199254740993], "ü": 2}"#, &options, &[]);
+    }
+
+    #[test]
+    fn invalid_document() {
+        assert!(lint("[1, @]", &LintOptions::default()).is_err());
+    }
+
+    #[test]
+    fn compares_decimals() {
+        assert_eq!(decimal("120.50"), decimal("1.205e2"));
+        assert_eq!(decimal("-0.00"), decimal("0"));
+        assert!(!loses_precision("0.1"));
+        assert!(!loses_precision("-2.50e-3"));
+        assert!(loses_precision("0.1000000000000000000001"));
+        assert!(loses_precision("18446744073709551617"));
+    }
+}
@@ -0,0 +1,455 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+use crate::{Str, Value};
+
+/// Map used for the entries of [`Value::Object`] with the default [`MapKind`]
+///
+/// Keys are unordered by default. With the `preserve_order` feature enabled,
+/// this is an [`OrderedMap`] which keeps keys in the order they were inserted,
+/// so parsed objects keep the key order of the source text.
+pub type Map = <DefaultMapKind as MapKind>::Map<Value>;
+
+/// The [`MapKind`] used when `Value` is written without a type parameter
+#[cfg(not(feature = "preserve_order"))]
+pub type DefaultMapKind = HashMapKind;
+
+/// The [`MapKind`] used when `Value` is written without a type parameter
+#[cfg(feature = "preserve_order")]
+pub type DefaultMapKind = OrderedMapKind;
+
+/// Chooses the map type that stores the entries of [`Value::Object`]
+///
+/// A map kind is a marker type rather than the map itself, because the map's
+/// values are `Value`s of that same kind. For example, `Value<BTreeMapKind>`
+/// stores objects as `BTreeMap<String, Value<BTreeMapKind>>`.
+pub trait MapKind {
+    type Map<V>: ObjectMap<V>;
+}
+
+/// `HashMap` objects, optionally with a custom hasher
+pub struct HashMapKind<S = RandomState>(PhantomData<S>);
+
+impl<S: BuildHasher + Default> MapKind for HashMapKind<S> {
+    type Map<V> = HashMap<Str, V, S>;
+}
+
+/// `BTreeMap` objects, which iterate in sorted key order
+pub struct BTreeMapKind;
+
+impl MapKind for BTreeMapKind {
+    type Map<V> = BTreeMap<Str, V>;
+}
+
+/// [`OrderedMap`] objects, which iterate in insertion order
+pub struct OrderedMapKind;
+
+impl MapKind for OrderedMapKind {
+    type Map<V> = OrderedMap<Str, V>;
+}
+
+/// Operations needed from a map of [`Str`] keys to `V` to use it for objects
+pub trait ObjectMap<V>: Default + FromIterator<(Str, V)> + IntoIterator<Item = (Str, V)> {
+    type Iter<'a>: Iterator<Item = (&'a Str, &'a V)>
+    where
+        Self: 'a,
+        V: 'a;
+
+    type IterMut<'a>: Iterator<Item = (&'a Str, &'a mut V)>
+    where
+        Self: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, key: &str) -> Option<&V>;
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V>;
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts the value, returning the previous value if the key was present
+    fn insert(&mut self, key: Str, value: V) -> Option<V>;
+
+    fn remove(&mut self, key: &str) -> Option<V>;
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+}
+
+impl<V, S: BuildHasher + Default> ObjectMap<V> for HashMap<Str, V, S> {
+    type Iter<'a>
+        = hash_map::Iter<'a, Str, V>
+    where
+        Self: 'a,
+        V: 'a;
+
+    type IterMut<'a>
+        = hash_map::IterMut<'a, Str, V>
+    where
+        Self: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Str, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        HashMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        HashMap::iter_mut(self)
+    }
+}
+
+impl<V> ObjectMap<V> for BTreeMap<Str, V> {
+    type Iter<'a>
+        = btree_map::Iter<'a, Str, V>
+    where
+        Self: 'a,
+        V: 'a;
+
+    type IterMut<'a>
+        = btree_map::IterMut<'a, Str, V>
+    where
+        Self: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Str, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        BTreeMap::iter_mut(self)
+    }
+}
+
+impl<V> ObjectMap<V> for OrderedMap<Str, V> {
+    type Iter<'a>
+        = <&'a Self as IntoIterator>::IntoIter
+    where
+        Self: 'a,
+        V: 'a;
+
+    type IterMut<'a>
+        = <&'a mut Self as IntoIterator>::IntoIter
+    where
+        Self: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize {
+        OrderedMap::len(self)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        OrderedMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        OrderedMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Str, value: V) -> Option<V> {
+        OrderedMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        OrderedMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.into_iter()
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        self.into_iter()
+    }
+}
+
+/// Map that remembers the insertion order of its keys
+///
+/// Entries are stored in a `Vec` of pairs and located through a hash index
+/// of key to position. Lookups are as fast as a `HashMap`, while iteration
+/// follows insertion order. Inserting an existing key replaces the value but
+/// keeps the original position. Removing an entry shifts the later entries
+/// down, which is O(n).
+#[derive(Clone)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    indices: HashMap<K, usize>,
+}
+
+impl<K, V> OrderedMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            indices: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&K, &mut V)> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> OrderedMap<K, V> {
+    /// Inserts the value, returning the previous value if the key was present
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.indices.get(&key) {
+            Some(&index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = *self.indices.get(key)?;
+        Some(&self.entries[index].1)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = *self.indices.get(key)?;
+        Some(&mut self.entries[index].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.indices.contains_key(key)
+    }
+
+    /// Removes the entry for the key, keeping the order of the other entries
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for later in self.indices.values_mut() {
+            if *later > index {
+                *later -= 1;
+            }
+        }
+        Some(value)
+    }
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OrderedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Maps are equal when they have the same entries, regardless of order
+impl<K: Hash + Eq + Clone, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key).is_some_and(|other| value == other))
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for OrderedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, const N: usize> From<[(K, V); N]> for OrderedMap<K, V> {
+    fn from(pairs: [(K, V); N]) -> Self {
+        Self::from_iter(pairs)
+    }
+}
+
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a OrderedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = std::iter::Map<std::slice::Iter<'a, (K, V)>, fn(&'a (K, V)) -> (&'a K, &'a V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut OrderedMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter =
+        std::iter::Map<std::slice::IterMut<'a, (K, V)>, fn(&'a mut (K, V)) -> (&'a K, &'a mut V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderedMap;
+
+    fn keys(map: &OrderedMap<&'static str, i32>) -> Vec<&'static str> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn keeps_insertion_order() {
+        let map = OrderedMap::from([("z", 1), ("a", 2), ("m", 3)]);
+
+        assert_eq!(keys(&map), ["z", "a", "m"]);
+    }
+
+    #[test]
+    fn insert_existing_key_keeps_position() {
+        let mut map = OrderedMap::from([("z", 1), ("a", 2)]);
+        let previous = map.insert("z", 10);
+
+        assert_eq!(previous, Some(1));
+        assert_eq!(keys(&map), ["z", "a"]);
+        assert_eq!(map.get("z"), Some(&10));
+    }
+
+    #[test]
+    fn remove_shifts_later_entries() {
+        let mut map = OrderedMap::from([("z", 1), ("a", 2), ("m", 3)]);
+
+        assert_eq!(map.remove("z"), Some(1));
+        assert_eq!(map.remove("z"), None);
+        assert_eq!(keys(&map), ["a", "m"]);
+        assert_eq!(map.get("m"), Some(&3));
+
+        map.insert("z", 4);
+        assert_eq!(keys(&map), ["a", "m", "z"]);
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let left = OrderedMap::from([("z", 1), ("a", 2)]);
+        let right = OrderedMap::from([("a", 2), ("z", 1)]);
+
+        assert_eq!(left, right);
+        assert_ne!(left, OrderedMap::from([("a", 2)]));
+    }
+}
@@ -0,0 +1,661 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::lint::{decimal, loses_precision};
+use crate::map::MapKind;
+use crate::Value;
+
+/// A JSON number, stored in the narrowest representation that holds it exactly
+///
+/// The tokenizer produces `I64` for integers that fit in an `i64`, `U64` for
+/// larger positive integers, and `F64` for everything else (fractions and
+/// integers beyond the 64-bit range). Keeping integers out of `f64` means IDs
+/// such as `9007199254740993` are not rounded.
+///
+/// A number too large for an `f64`, ex. `1e400`, is kept as its `Literal`
+/// text rather than becoming infinite, so that it's written back as it was
+/// instead of as `null`.
+///
+/// With the `arbitrary_precision` feature enabled, numbers that don't fit in
+/// an `I64` or `U64` are kept as their original `Literal` text instead of an
+/// `F64`, so no digits are ever lost. The
+/// [`preserve_number_literals`](crate::ParseOptions::preserve_number_literals)
+/// option keeps the text of every number, so that it is written back
+/// unchanged.
+#[derive(Debug, Clone)]
+pub enum Number {
+    /// Integer in the range of `i64`
+    I64(i64),
+
+    /// Positive integer above `i64::MAX`
+    U64(u64),
+
+    /// Number with a fractional part, or outside the range of 64-bit integers
+    F64(f64),
+
+    /// Exact source text of the number
+    Literal(String),
+}
+
+impl Number {
+    /// Creates the narrowest number for an integer, preferring `I64`
+    fn from_u64(n: u64) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Number::I64(n),
+            Err(_) => Number::U64(n),
+        }
+    }
+
+    /// Returns the number as an `i64` if it is an integer within range.
+    ///
+    /// An `F64` converts when it has no fractional part, ex. `1e3`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Number::I64(n) => Some(*n),
+            Number::U64(n) => i64::try_from(*n).ok(),
+            // `i64::MAX as f64` rounds up to 2^63, which is out of range
+            Number::F64(n) if is_whole(*n) && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => {
+                Some(*n as i64)
+            }
+            Number::F64(_) => None,
+            Number::Literal(s) => parse_literal(s).as_i64(),
+        }
+    }
+
+    /// Returns the number as a `u64` if it is a non-negative integer within
+    /// range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Number::I64(n) => u64::try_from(*n).ok(),
+            Number::U64(n) => Some(*n),
+            // `u64::MAX as f64` rounds up to 2^64, which is out of range
+            Number::F64(n) if is_whole(*n) && *n >= 0.0 && *n < u64::MAX as f64 => Some(*n as u64),
+            Number::F64(_) => None,
+            Number::Literal(s) => parse_literal(s).as_u64(),
+        }
+    }
+
+    /// Returns the number as an `f64`, which may round large integers
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Number::I64(n) => *n as f64,
+            Number::U64(n) => *n as f64,
+            Number::F64(n) => *n,
+            Number::Literal(s) => parse_literal(s).as_f64(),
+        }
+    }
+
+    /// Whether the number has no fractional part
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::I64(_) | Number::U64(_) => true,
+            Number::F64(n) => is_whole(*n),
+            Number::Literal(s) => !s.contains(['.', 'e', 'E']) || parse_literal(s).is_integer(),
+        }
+    }
+
+    /// Returns the exact source text of the number, if it was kept
+    pub fn as_literal(&self) -> Option<&str> {
+        match self {
+            Number::Literal(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `i128` if it is an integer within range.
+    ///
+    /// A `Literal` integer beyond the 64-bit range converts exactly.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Number::I64(n) => Some(i128::from(*n)),
+            Number::U64(n) => Some(i128::from(*n)),
+            // `i128::MAX as f64` rounds up to 2^127, which is out of range
+            Number::F64(n) if is_whole(*n) && *n >= i128::MIN as f64 && *n < i128::MAX as f64 => {
+                Some(*n as i128)
+            }
+            Number::F64(_) => None,
+            Number::Literal(s) => s.parse().ok().or_else(|| parse_literal(s).as_i128()),
+        }
+    }
+
+    /// Returns the number as a `u128` if it is a non-negative integer within
+    /// range.
+    ///
+    /// A `Literal` integer beyond the 64-bit range converts exactly.
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Number::I64(n) => u128::try_from(*n).ok(),
+            Number::U64(n) => Some(u128::from(*n)),
+            // `u128::MAX as f64` rounds up to 2^128, which is out of range
+            Number::F64(n) if is_whole(*n) && *n >= 0.0 && *n < u128::MAX as f64 => {
+                Some(*n as u128)
+            }
+            Number::F64(_) => None,
+            Number::Literal(s) => s.parse().ok().or_else(|| parse_literal(s).as_u128()),
+        }
+    }
+}
+
+/// A 128-bit integer was outside the range of `i64` and `u64`, so it could only
+/// be stored by rounding to an `f64`. Enable the `arbitrary_precision` feature
+/// to keep such integers exactly.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NumberOutOfRange;
+
+impl fmt::Display for NumberOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("integer is out of the range of 64-bit integers")
+    }
+}
+
+impl std::error::Error for NumberOutOfRange {}
+
+/// Converts literal text to the closest `I64`, `U64` or `F64`, with `NaN` for
+/// text that isn't a number at all
+fn parse_literal(s: &str) -> Number {
+    if let Ok(n) = s.parse::<i64>() {
+        return Number::I64(n);
+    }
+    if let Ok(n) = s.parse::<u64>() {
+        return Number::U64(n);
+    }
+    Number::F64(s.parse().unwrap_or(f64::NAN))
+}
+
+/// The exact value of a `Literal` whose closest `I64`, `U64` or `F64` has a
+/// different decimal value, ex. an integer beyond 64 bits
+#[derive(PartialEq, Eq, Hash)]
+struct Decimal {
+    negative: bool,
+    /// Significant digits and the exponent of the first one, see [`decimal`]
+    digits: String,
+    position: i64,
+}
+
+/// Orders by value
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the digits have no trailing zeros, so they compare as text
+        let magnitude = self
+            .position
+            .cmp(&other.position)
+            .then_with(|| self.digits.cmp(&other.digits));
+        match (self.negative, other.negative) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (negative, _) => negative.cmp(&false).reverse(),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// What a `Literal` is compared and hashed by: its closest `I64`, `U64` or
+/// `F64`, and its exact value when that number doesn't hold it exactly
+fn literal_value(s: &str) -> (Number, Option<Decimal>) {
+    let number = parse_literal(s);
+    // integers are parsed exactly, and only the nearest float can differ
+    let exact = (matches!(number, Number::F64(_)) && loses_precision(s)).then(|| {
+        let (digits, position) = decimal(s);
+        Decimal {
+            negative: s.starts_with('-'),
+            digits,
+            position,
+        }
+    });
+    (number, exact)
+}
+
+fn is_whole(n: f64) -> bool {
+    n.is_finite() && n.fract() == 0.0
+}
+
+/// Maps `-0.0` to `0.0` and every `NaN` to the same positive `NaN`, so that
+/// floats that are equal as JSON hash and order the same
+fn canonical(n: f64) -> f64 {
+    if n == 0.0 {
+        0.0
+    } else if n.is_nan() {
+        f64::NAN
+    } else {
+        n
+    }
+}
+
+/// Compares an integer to a float exactly, without rounding the integer to an
+/// `f64`. When both have the same value the integer is ordered first.
+fn cmp_int_float(int: i128, float: f64) -> Ordering {
+    let float = canonical(float);
+    // `i128::MIN as f64` is exactly -2^127 and `i128::MAX as f64` rounds to 2^127
+    if float.is_nan() || float >= i128::MAX as f64 {
+        return Ordering::Less;
+    }
+    if float < i128::MIN as f64 {
+        return Ordering::Greater;
+    }
+    int.cmp(&(float.trunc() as i128))
+        .then(if float.fract() < 0.0 {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        })
+}
+
+/// Integers are equal when they have the same value, regardless of variant.
+/// An integer is never equal to an `F64`, even one with no fractional part.
+/// Unlike `f64`, `-0.0` is equal to `0.0` and `NaN` is equal to itself, so
+/// that this is an equivalence.
+///
+/// A `Literal` is compared by its value: it's equal to the closest `I64`,
+/// `U64` or `F64` when that has the same decimal value, ex. `2.50` and `2.5`,
+/// and otherwise only to `Literal`s with the same decimal value.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        // the order has the same rules, so that the two agree
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// `Eq` is implemented so that numbers can be used in sets and as map keys
+impl Eq for Number {}
+
+/// Numbers are hashed by value to agree with `PartialEq`: integers hash the
+/// same regardless of variant, `-0.0` hashes the same as `0.0`, all `NaN`s
+/// hash the same, and a `Literal` hashes as its closest `I64`, `U64` or `F64`
+/// along with its exact value when that differs.
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Number::F64(n) => {
+                1u8.hash(state);
+                canonical(*n).to_bits().hash(state);
+            }
+            Number::Literal(s) => {
+                let (number, exact) = literal_value(s);
+                number.hash(state);
+                if let Some(exact) = exact {
+                    exact.hash(state);
+                }
+            }
+            n => {
+                0u8.hash(state);
+                n.as_i128().hash(state);
+            }
+        }
+    }
+}
+
+/// Numbers are ordered by value, with `-0.0` equal to `0.0` and `NaN` after
+/// every other number (as in [`f64::total_cmp`]). An integer and an `F64`
+/// with the same value are ordered integer first. A `Literal` is ordered by
+/// its closest `I64`, `U64` or `F64`, after that number when it doesn't hold
+/// the literal exactly, and then by its exact value.
+///
+/// The order agrees with `PartialEq`: two numbers are ordered equal exactly
+/// when they are equal, including a `NaN` with itself.
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Number::Literal(a), Number::Literal(b)) => {
+                let (a, a_exact) = literal_value(a);
+                let (b, b_exact) = literal_value(b);
+                a.cmp(&b).then_with(|| a_exact.cmp(&b_exact))
+            }
+            (Number::Literal(a), b) => {
+                let (a, exact) = literal_value(a);
+                a.cmp(b).then(if exact.is_some() {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                })
+            }
+            (a, Number::Literal(_)) => other.cmp(a).reverse(),
+            (Number::F64(a), Number::F64(b)) => canonical(*a).total_cmp(&canonical(*b)),
+            (Number::I64(a), Number::F64(b)) => cmp_int_float(i128::from(*a), *b),
+            (Number::U64(a), Number::F64(b)) => cmp_int_float(i128::from(*a), *b),
+            (Number::F64(a), Number::I64(b)) => cmp_int_float(i128::from(*b), *a).reverse(),
+            (Number::F64(a), Number::U64(b)) => cmp_int_float(i128::from(*b), *a).reverse(),
+            (a, b) => a.as_i128().cmp(&b.as_i128()),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::I64(n) => write!(f, "{n}"),
+            Number::U64(n) => write!(f, "{n}"),
+            Number::F64(n) => write!(f, "{n}"),
+            Number::Literal(s) => f.write_str(s),
+        }
+    }
+}
+
+macro_rules! from_signed {
+    ($($ty:ty)*) => {$(
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Self {
+                Number::I64(i64::from(n))
+            }
+        }
+    )*};
+}
+
+macro_rules! from_unsigned {
+    ($($ty:ty)*) => {$(
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Self {
+                Number::from_u64(u64::from(n))
+            }
+        }
+    )*};
+}
+
+from_signed!(i8 i16 i32 i64);
+from_unsigned!(u8 u16 u32 u64);
+
+macro_rules! from_wide {
+    ($($ty:ty)*) => {$(
+        /// Integers beyond the 64-bit range are kept as a `Literal`
+        #[cfg(feature = "arbitrary_precision")]
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Self {
+                if let Ok(n) = i64::try_from(n) {
+                    return Number::I64(n);
+                }
+                match u64::try_from(n) {
+                    Ok(n) => Number::U64(n),
+                    Err(_) => Number::Literal(n.to_string()),
+                }
+            }
+        }
+
+        /// Integers beyond the 64-bit range are an error
+        #[cfg(not(feature = "arbitrary_precision"))]
+        impl TryFrom<$ty> for Number {
+            type Error = NumberOutOfRange;
+
+            fn try_from(n: $ty) -> Result<Self, Self::Error> {
+                if let Ok(n) = i64::try_from(n) {
+                    return Ok(Number::I64(n));
+                }
+                u64::try_from(n).map(Number::U64).map_err(|_| NumberOutOfRange)
+            }
+        }
+
+        #[cfg(feature = "arbitrary_precision")]
+        impl<K: MapKind> From<$ty> for Value<K> {
+            fn from(n: $ty) -> Self {
+                Value::Number(Number::from(n))
+            }
+        }
+
+        #[cfg(not(feature = "arbitrary_precision"))]
+        impl<K: MapKind> TryFrom<$ty> for Value<K> {
+            type Error = NumberOutOfRange;
+
+            fn try_from(n: $ty) -> Result<Self, Self::Error> {
+                Number::try_from(n).map(Value::Number)
+            }
+        }
+    )*};
+}
+
+from_wide!(i128 u128);
+
+impl From<f32> for Number {
+    fn from(n: f32) -> Self {
+        Number::F64(f64::from(n))
+    }
+}
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Self {
+        Number::F64(n)
+    }
+}
+
+impl<K: MapKind> From<Number> for Value<K> {
+    fn from(n: Number) -> Self {
+        Value::Number(n)
+    }
+}
+
+macro_rules! value_from_number {
+    ($($ty:ty)*) => {$(
+        impl<K: MapKind> From<$ty> for Value<K> {
+            fn from(n: $ty) -> Self {
+                Value::Number(Number::from(n))
+            }
+        }
+    )*};
+}
+
+value_from_number!(i8 i16 i32 i64 u8 u16 u32 u64 f32 f64);
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::collections::{BTreeSet, HashSet};
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    use super::Number;
+    use crate::Value;
+
+    #[test]
+    fn as_i64_checks_range_and_fraction() {
+        assert_eq!(Number::I64(-5).as_i64(), Some(-5));
+        assert_eq!(Number::U64(u64::MAX).as_i64(), None);
+        assert_eq!(Number::F64(1e3).as_i64(), Some(1000));
+        assert_eq!(Number::F64(1.5).as_i64(), None);
+        assert_eq!(Number::F64(9.3e18).as_i64(), None);
+        assert_eq!(Number::F64(f64::NAN).as_i64(), None);
+    }
+
+    #[test]
+    fn as_u64_rejects_negatives() {
+        assert_eq!(Number::I64(-1).as_u64(), None);
+        assert_eq!(Number::I64(7).as_u64(), Some(7));
+        assert_eq!(Number::U64(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(Number::F64(-0.0).as_u64(), Some(0));
+        assert_eq!(Number::F64(1.9e19).as_u64(), None);
+    }
+
+    #[test]
+    fn as_i128_and_u128() {
+        assert_eq!(Number::I64(-5).as_i128(), Some(-5));
+        assert_eq!(Number::U64(u64::MAX).as_i128(), Some(i128::from(u64::MAX)));
+        assert_eq!(
+            Number::F64(1e20).as_u128(),
+            Some(100_000_000_000_000_000_000)
+        );
+        assert_eq!(Number::F64(-1e20).as_u128(), None);
+        assert_eq!(Number::F64(1e40).as_i128(), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn wide_integers_out_of_range() {
+        use super::NumberOutOfRange;
+
+        assert_eq!(Number::try_from(-5i128), Ok(Number::I64(-5)));
+        assert_eq!(
+            Number::try_from(u128::from(u64::MAX)),
+            Ok(Number::U64(u64::MAX))
+        );
+        assert_eq!(Number::try_from(u128::MAX), Err(NumberOutOfRange));
+        assert_eq!(Number::try_from(i128::MIN), Err(NumberOutOfRange));
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary_precision")]
+    fn wide_integers_as_literals() {
+        let n = Number::from(u128::MAX);
+
+        assert_eq!(
+            n.as_literal(),
+            Some("340282366920938463463374607431768211455")
+        );
+        assert_eq!(n.as_u128(), Some(u128::MAX));
+        assert_eq!(n.as_i128(), None);
+        assert_eq!(Number::from(i128::MIN).as_i128(), Some(i128::MIN));
+    }
+
+    #[test]
+    fn orders_by_value() {
+        let mut numbers = [
+            Number::F64(f64::NAN),
+            Number::F64(1.5),
+            Number::U64(u64::MAX),
+            Number::F64(1.0),
+            Number::I64(1),
+            Number::F64(f64::NEG_INFINITY),
+            Number::I64(-3),
+            Number::F64(-2.5),
+        ];
+        numbers.sort();
+
+        let expected = [
+            Number::F64(f64::NEG_INFINITY),
+            Number::I64(-3),
+            Number::F64(-2.5),
+            Number::I64(1),
+            Number::F64(1.0),
+            Number::F64(1.5),
+            Number::U64(u64::MAX),
+        ];
+        assert_eq!(numbers[..7], expected);
+        assert!(numbers[7].as_f64().is_nan());
+    }
+
+    #[test]
+    fn orders_integers_exactly_against_floats() {
+        let float = Number::F64(9007199254740992.0);
+
+        assert!(Number::I64(9007199254740993) > float);
+        assert!(Number::I64(9007199254740991) < float);
+        assert_eq!(Number::F64(-0.0).cmp(&Number::F64(0.0)), Ordering::Equal);
+    }
+
+    #[test]
+    fn is_integer() {
+        assert!(Number::I64(1).is_integer());
+        assert!(Number::F64(2.0).is_integer());
+        assert!(!Number::F64(2.5).is_integer());
+        assert!(!Number::F64(f64::INFINITY).is_integer());
+    }
+
+    #[test]
+    fn unsigned_prefers_i64() {
+        assert_eq!(Number::from(5u64), Number::I64(5));
+        assert!(matches!(Number::from(u64::MAX), Number::U64(u64::MAX)));
+    }
+
+    #[test]
+    fn integers_equal_across_variants() {
+        assert_eq!(Number::I64(5), Number::U64(5));
+        assert_ne!(Number::I64(-1), Number::U64(u64::MAX));
+    }
+
+    #[test]
+    fn integer_not_equal_to_float() {
+        assert_ne!(Number::I64(1), Number::F64(1.0));
+    }
+
+    #[test]
+    fn literal_accessors() {
+        let big = Number::Literal(String::from("123456789012345678901234567890"));
+        assert_eq!(big.as_literal(), Some("123456789012345678901234567890"));
+        assert_eq!(big.as_i64(), None);
+        assert!(big.is_integer());
+        assert_eq!(big.to_string(), "123456789012345678901234567890");
+
+        let fraction = Number::Literal(String::from("2.5"));
+        assert_eq!(fraction.as_f64(), 2.5);
+        assert!(!fraction.is_integer());
+        assert_eq!(fraction, Number::F64(2.5));
+        assert_eq!(fraction, Number::Literal(String::from("2.50")));
+    }
+
+    #[test]
+    fn equality_is_an_equivalence() {
+        let nan = Number::F64(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_eq!(Number::F64(-0.0), Number::F64(0.0));
+
+        let set: HashSet<Value> = [Value::from(f64::NAN), Value::from(f64::NAN)].into();
+        assert_eq!(set.len(), 1);
+
+        // literals are equal by value, including beyond what an `f64` holds
+        let literal = |s: &str| Number::Literal(s.to_string());
+        assert_eq!(literal("2.5"), literal("25e-1"));
+        let big = literal("123456789012345678901234567890");
+        let next = literal("123456789012345678901234567891");
+        assert_ne!(big, next);
+        assert_ne!(big, Number::F64(123456789012345678901234567890.0));
+        assert_eq!(big, literal("1.2345678901234567890123456789e29"));
+        assert_eq!(
+            hash(&big),
+            hash(&literal("1.2345678901234567890123456789e29"))
+        );
+        assert_eq!(hash(&literal("2.50")), hash(&Number::F64(2.5)));
+    }
+
+    #[test]
+    fn order_agrees_with_equality() {
+        let literal = |s: &str| Number::Literal(s.to_string());
+        let numbers = [
+            Number::F64(f64::NAN),
+            literal("2.50"),
+            Number::F64(f64::NAN),
+            literal("123456789012345678901234567891"),
+            Number::F64(2.5),
+            literal("123456789012345678901234567890"),
+            Number::F64(123456789012345678901234567890.0),
+            literal("-123456789012345678901234567890"),
+            Number::I64(2),
+            literal("2.5"),
+        ];
+        for a in &numbers {
+            for b in &numbers {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b, "{a} and {b}");
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a} and {b}");
+            }
+        }
+
+        let set: BTreeSet<Number> = numbers.iter().cloned().collect();
+        let mut sorted = numbers.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), set.len());
+        assert_eq!(
+            sorted.iter().map(Number::to_string).collect::<Vec<_>>(),
+            [
+                "-123456789012345678901234567890",
+                "2",
+                "2.50",
+                "123456789012345680000000000000",
+                "123456789012345678901234567890",
+                "123456789012345678901234567891",
+                "NaN",
+            ]
+        );
+    }
+
+    fn hash(number: &Number) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        number.hash(&mut hasher);
+        hasher.finish()
+    }
+}
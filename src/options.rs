@@ -0,0 +1,537 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Configuration for parsing, see [`parse_with`](crate::parse_with)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// What to do when an object contains the same key more than once
+    pub duplicate_keys: DuplicateKeyPolicy,
+
+    /// What to do with a `\u` escape of a UTF-16 surrogate that isn't part
+    /// of a pair, ex. `"\uD800"`
+    pub lone_surrogates: LoneSurrogatePolicy,
+
+    /// Keep the source text of every number as a [`Number::Literal`], so that
+    /// serializing writes each number exactly as it was written, ex. `1.0`
+    /// stays `1.0` rather than becoming `1`
+    ///
+    /// [`Number::Literal`]: crate::Number::Literal
+    pub preserve_number_literals: bool,
+
+    /// Fail with [`ErrorKind::PrecisionLoss`] on an integer that an
+    /// `f64` can't represent exactly, ex. a 64-bit ID above 2^53, since
+    /// consumers that store numbers as floats, such as JavaScript, would
+    /// silently round it
+    ///
+    /// [`ErrorKind::PrecisionLoss`]: crate::ErrorKind::PrecisionLoss
+    pub require_exact_integers: bool,
+
+    /// Share one allocation between every occurrence of the same object key,
+    /// see [`KeyInterner`](crate::KeyInterner)
+    #[cfg(feature = "arc_str")]
+    pub key_interner: Option<crate::KeyInterner>,
+
+    /// Called periodically while the input is tokenized, see
+    /// [`ProgressCallback`]
+    pub progress: Option<ProgressCallback>,
+
+    /// Called for each object key that repeats an earlier key of the same
+    /// object, whichever [`DuplicateKeyPolicy`] decides the value, see
+    /// [`DuplicateKeyCallback`]
+    pub duplicate_key_callback: Option<DuplicateKeyCallback>,
+
+    /// Checked before each token, so that parsing stops with
+    /// [`ErrorKind::Cancelled`](crate::ErrorKind::Cancelled) soon
+    /// after the token is cancelled
+    pub cancel: Option<CancelToken>,
+
+    /// Approximate number of bytes that the parsed value may allocate, for
+    /// its strings, arrays and object entries, before parsing stops with
+    /// [`ErrorKind::MemoryLimitExceeded`], so that untrusted input
+    /// can't exhaust memory. The input and its tokens aren't counted.
+    ///
+    /// [`ErrorKind::MemoryLimitExceeded`]: crate::ErrorKind::MemoryLimitExceeded
+    pub max_memory: Option<usize>,
+
+    /// Longest string or object key, in bytes once unescaped, before parsing
+    /// stops with [`ErrorKind::StringTooLong`]
+    ///
+    /// [`ErrorKind::StringTooLong`]: crate::ErrorKind::StringTooLong
+    pub max_string_length: Option<usize>,
+
+    /// Most elements in any one array before parsing stops with
+    /// [`ErrorKind::TooManyElements`]
+    ///
+    /// [`ErrorKind::TooManyElements`]: crate::ErrorKind::TooManyElements
+    pub max_array_length: Option<usize>,
+
+    /// Most entries in any one object before parsing stops with
+    /// [`ErrorKind::TooManyEntries`]. Repeated keys count once.
+    ///
+    /// [`ErrorKind::TooManyEntries`]: crate::ErrorKind::TooManyEntries
+    pub max_object_entries: Option<usize>,
+
+    /// Deepest that arrays and objects may be nested before parsing stops
+    /// with [`ErrorKind::TooDeep`], ex. `[[1]]` is nested 2 deep, which is
+    /// 128 by default. The lazy and tape parsers recurse, as does dropping,
+    /// cloning, comparing or printing a value, so the limit keeps untrusted
+    /// input from overflowing the stack, and no input makes any of the
+    /// parsers panic.
+    ///
+    /// Setting it to `None` allows any depth, for trusted input only.
+    ///
+    /// [`ErrorKind::TooDeep`]: crate::ErrorKind::TooDeep
+    pub max_depth: Option<usize>,
+
+    /// Fail with [`ErrorKind::ByteOrderMark`] when the input starts with
+    /// a byte order mark, U+FEFF, which is otherwise skipped as RFC 8259
+    /// permits
+    ///
+    /// [`ErrorKind::ByteOrderMark`]: crate::ErrorKind::ByteOrderMark
+    pub reject_byte_order_mark: bool,
+
+    /// Accept a comma after the last element of an array or entry of an
+    /// object, ex. `[1, 2,]`
+    pub allow_trailing_commas: bool,
+
+    /// Accept text after the top-level value, which is ignored, ex.
+    /// `true false`
+    pub allow_trailing_content: bool,
+
+    /// Accept a backslash before any character in a string, which stands for
+    /// that character, ex. `"\q"` is `"q"`. Otherwise only the escapes
+    /// listed in RFC 8259 are valid.
+    pub allow_invalid_escapes: bool,
+
+    /// Accept the control characters U+0000 to U+001F unescaped in a string
+    pub allow_control_characters: bool,
+
+    /// Skip `//` line comments and `/* */` block comments between tokens, as
+    /// in JSONC files such as VS Code's settings
+    pub allow_comments: bool,
+
+    /// Accept strings delimited by `'`, in which `\'` escapes a quote, as in
+    /// JavaScript
+    pub allow_single_quotes: bool,
+
+    /// Accept object keys that aren't quoted, ex. `{key: 1}`, when they are
+    /// made of ASCII letters, digits, `_` and `$` and don't start with a
+    /// digit
+    pub allow_unquoted_keys: bool,
+
+    /// Accept hexadecimal integers, ex. `0xFF` or `-0x10`
+    pub allow_hex_numbers: bool,
+
+    /// Accept a `+` before a number, ex. `+1`
+    pub allow_leading_plus: bool,
+
+    /// Accept `NaN`, `Infinity` and `-Infinity` as numbers, which are stored
+    /// as the corresponding `f64` values
+    pub allow_non_finite_numbers: bool,
+
+    /// Accept a backslash before a line break in a string, which continues
+    /// the string on the next line without including the line break
+    pub allow_line_continuations: bool,
+
+    /// Accept the whitespace that JavaScript does between tokens: vertical
+    /// tab, form feed, no-break space, the line and paragraph separators,
+    /// the byte order mark and the other Unicode space separators. Otherwise
+    /// only RFC 8259's space, tab, line feed and carriage return are.
+    pub allow_unicode_whitespace: bool,
+}
+
+/// Strict RFC 8259 parsing, with no limits other than
+/// [`max_depth`](ParseOptions::max_depth)
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            lone_surrogates: LoneSurrogatePolicy::default(),
+            preserve_number_literals: false,
+            require_exact_integers: false,
+            #[cfg(feature = "arc_str")]
+            key_interner: None,
+            progress: None,
+            duplicate_key_callback: None,
+            cancel: None,
+            max_memory: None,
+            max_string_length: None,
+            max_array_length: None,
+            max_object_entries: None,
+            max_depth: Some(128),
+            reject_byte_order_mark: false,
+            allow_trailing_commas: false,
+            allow_trailing_content: false,
+            allow_invalid_escapes: false,
+            allow_control_characters: false,
+            allow_comments: false,
+            allow_single_quotes: false,
+            allow_unquoted_keys: false,
+            allow_hex_numbers: false,
+            allow_leading_plus: false,
+            allow_non_finite_numbers: false,
+            allow_line_continuations: false,
+            allow_unicode_whitespace: false,
+        }
+    }
+}
+
+/// The fields can be set directly, or by chaining the methods of the same
+/// names, ex. `ParseOptions::new().max_memory(1 << 20).cancel(token)`
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn lone_surrogates(mut self, policy: LoneSurrogatePolicy) -> Self {
+        self.lone_surrogates = policy;
+        self
+    }
+
+    pub fn preserve_number_literals(mut self, preserve: bool) -> Self {
+        self.preserve_number_literals = preserve;
+        self
+    }
+
+    pub fn require_exact_integers(mut self, require: bool) -> Self {
+        self.require_exact_integers = require;
+        self
+    }
+
+    #[cfg(feature = "arc_str")]
+    pub fn key_interner(mut self, interner: crate::KeyInterner) -> Self {
+        self.key_interner = Some(interner);
+        self
+    }
+
+    pub fn progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn duplicate_key_callback(mut self, callback: DuplicateKeyCallback) -> Self {
+        self.duplicate_key_callback = Some(callback);
+        self
+    }
+
+    pub fn cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    pub fn max_string_length(mut self, bytes: usize) -> Self {
+        self.max_string_length = Some(bytes);
+        self
+    }
+
+    pub fn max_array_length(mut self, elements: usize) -> Self {
+        self.max_array_length = Some(elements);
+        self
+    }
+
+    pub fn max_object_entries(mut self, entries: usize) -> Self {
+        self.max_object_entries = Some(entries);
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn reject_byte_order_mark(mut self, reject: bool) -> Self {
+        self.reject_byte_order_mark = reject;
+        self
+    }
+
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.allow_trailing_commas = allow;
+        self
+    }
+
+    pub fn allow_trailing_content(mut self, allow: bool) -> Self {
+        self.allow_trailing_content = allow;
+        self
+    }
+
+    pub fn allow_invalid_escapes(mut self, allow: bool) -> Self {
+        self.allow_invalid_escapes = allow;
+        self
+    }
+
+    pub fn allow_control_characters(mut self, allow: bool) -> Self {
+        self.allow_control_characters = allow;
+        self
+    }
+
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    pub fn allow_single_quotes(mut self, allow: bool) -> Self {
+        self.allow_single_quotes = allow;
+        self
+    }
+
+    pub fn allow_unquoted_keys(mut self, allow: bool) -> Self {
+        self.allow_unquoted_keys = allow;
+        self
+    }
+
+    pub fn allow_hex_numbers(mut self, allow: bool) -> Self {
+        self.allow_hex_numbers = allow;
+        self
+    }
+
+    pub fn allow_leading_plus(mut self, allow: bool) -> Self {
+        self.allow_leading_plus = allow;
+        self
+    }
+
+    pub fn allow_non_finite_numbers(mut self, allow: bool) -> Self {
+        self.allow_non_finite_numbers = allow;
+        self
+    }
+
+    pub fn allow_line_continuations(mut self, allow: bool) -> Self {
+        self.allow_line_continuations = allow;
+        self
+    }
+
+    pub fn allow_unicode_whitespace(mut self, allow: bool) -> Self {
+        self.allow_unicode_whitespace = allow;
+        self
+    }
+
+    /// Sets each of the options that [`ParseProfile`] covers
+    pub fn profile(self, profile: ParseProfile) -> Self {
+        let relaxed = profile == ParseProfile::Relaxed;
+        let json5 = profile == ParseProfile::Json5;
+        self.allow_trailing_commas(relaxed || json5)
+            .allow_trailing_content(relaxed)
+            .allow_invalid_escapes(relaxed || json5)
+            .allow_control_characters(relaxed)
+            .allow_comments(json5)
+            .allow_single_quotes(json5)
+            .allow_unquoted_keys(json5)
+            .allow_hex_numbers(json5)
+            .allow_leading_plus(json5)
+            .allow_non_finite_numbers(json5)
+            .allow_line_continuations(json5)
+            .allow_unicode_whitespace(json5)
+    }
+}
+
+/// A named set of the options deciding how closely input has to follow the
+/// JSON grammar, see [`ParseOptions::profile`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseProfile {
+    /// Only input that is valid according to RFC 8259 is accepted, which is
+    /// what the default options do
+    #[default]
+    Rfc8259Strict,
+
+    /// Trailing commas, text after the top-level value, any escaped
+    /// character and unescaped control characters are accepted
+    Relaxed,
+
+    /// The JSON5 extensions for files edited by hand are accepted: comments,
+    /// trailing commas, single-quoted strings, unquoted keys, hexadecimal
+    /// numbers, a leading `+`, `NaN` and `Infinity`, any escaped character,
+    /// strings continued over several lines and Unicode whitespace
+    Json5,
+}
+
+/// How far tokenizing has got, see [`ProgressCallback`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of input consumed
+    pub bytes: usize,
+    /// Tokens produced, including punctuation
+    pub tokens: usize,
+    /// Values started, counting each object and array once and not counting
+    /// object keys
+    pub values: usize,
+}
+
+/// A function called each time tokenizing passes another `every_bytes` of
+/// input, and once more when the input ends, so that a long parse can show
+/// progress or log that it's still running.
+///
+/// It's used by [`parse_with`](crate::parse_with), the functions reading
+/// from a file or reader, and [`PushParser`](crate::PushParser).
+#[derive(Clone)]
+pub struct ProgressCallback {
+    every_bytes: usize,
+    callback: Arc<dyn Fn(Progress) + Send + Sync>,
+}
+
+impl ProgressCallback {
+    pub fn new(every_bytes: usize, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self {
+            every_bytes: every_bytes.max(1),
+            callback: Arc::new(callback),
+        }
+    }
+
+    pub(crate) fn every_bytes(&self) -> usize {
+        self.every_bytes
+    }
+
+    pub(crate) fn call(&self, progress: Progress) {
+        (self.callback)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressCallback")
+            .field("every_bytes", &self.every_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Callbacks are equal when they are clones of the same one
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        self.every_bytes == other.every_bytes && Arc::ptr_eq(&self.callback, &other.callback)
+    }
+}
+
+/// An object key that repeats an earlier key of the same object, see
+/// [`DuplicateKeyCallback`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// The key, unescaped
+    pub key: String,
+    /// Byte offset in the input of the opening quote of the first occurrence
+    pub first: usize,
+    /// Byte offset in the input of the opening quote of this occurrence
+    pub second: usize,
+}
+
+/// A function called as the input is tokenized, for each object key that
+/// repeats an earlier key of the same object, so that a linter or security
+/// scanner can see duplicates even when the parse keeps one of the values.
+///
+/// When a key appears more than twice, each repeat is reported along with
+/// the first occurrence. It's used by [`parse_with`](crate::parse_with), the
+/// functions reading from a file or reader, and
+/// [`PushParser`](crate::PushParser).
+#[derive(Clone)]
+pub struct DuplicateKeyCallback {
+    callback: Arc<dyn Fn(DuplicateKey) + Send + Sync>,
+}
+
+impl DuplicateKeyCallback {
+    pub fn new(callback: impl Fn(DuplicateKey) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Arc::new(callback),
+        }
+    }
+
+    pub(crate) fn call(&self, duplicate: DuplicateKey) {
+        (self.callback)(duplicate)
+    }
+}
+
+impl fmt::Debug for DuplicateKeyCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplicateKeyCallback")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Callbacks are equal when they are clones of the same one
+impl PartialEq for DuplicateKeyCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.callback, &other.callback)
+    }
+}
+
+/// A flag that aborts a parse from another thread, or from a
+/// [`ProgressCallback`], such as when a server gives up on a request that is
+/// taking too long.
+///
+/// Clones share the same flag, so a clone can be put in
+/// [`ParseOptions::cancel`] and the original kept to cancel with.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes every parse using this token, or a clone of it, stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal when they are clones of the same one
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+/// How to handle an object key that appears more than once
+///
+/// RFC 8259 leaves this up to the implementation, and different consumers of
+/// the same document disagreeing about which value "won" is a common source
+/// of bugs and security issues.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// The value of the last occurrence replaces earlier values
+    #[default]
+    LastWins,
+
+    /// The value of the first occurrence is kept, later values are ignored
+    FirstWins,
+
+    /// Duplicate keys are a parse error
+    Error,
+
+    /// Values of a repeated key are gathered into an array, in source order
+    CollectAll,
+}
+
+/// How to handle a `\u` escape of a UTF-16 surrogate that isn't part of a
+/// pair
+///
+/// Such strings can't be decoded to valid UTF-8, but are produced by
+/// JavaScript, which strings are sequences of UTF-16 code units, ex. when a
+/// string is cut in the middle of an emoji.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoneSurrogatePolicy {
+    /// Lone surrogates are a parse error
+    #[default]
+    Error,
+
+    /// Each lone surrogate is replaced by U+FFFD REPLACEMENT CHARACTER
+    Replace,
+
+    /// The escape is kept as written, ex. `"\uD800"` becomes the six
+    /// characters `\uD800`, so that the code unit can be recovered from the
+    /// string
+    Preserve,
+}
@@ -0,0 +1,178 @@
+//! Parsing the elements of a large top-level array on many threads, see
+//! [`parse_parallel`]
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::simd::{is_whitespace, skip_whitespace};
+use crate::tokenize::closing_quote;
+use crate::{parse_with, Error, ParseOptions, Value};
+
+/// Minimum number of bytes of elements that each thread is given at a time,
+/// so that small elements aren't each sent to a thread separately
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Parses JSON text with the given options, parsing the elements of a
+/// top-level array in parallel on the rayon thread pool.
+///
+/// The input is first scanned for the commas between the array's elements,
+/// which only needs to track strings and brackets, then groups of elements
+/// are parsed on separate threads and put back together in order. Documents
+/// that aren't an array at the top level are parsed as usual, as are
+/// invalid documents, so that errors are the same as from
+/// [`parse_with`].
+pub fn parse_parallel(input: impl AsRef<str>, options: &ParseOptions) -> Result<Value, Error> {
+    let input = input.as_ref();
+    let Some(elements) = split_elements(input) else {
+        return parse_with(input, options);
+    };
+
+    let chunks = group_elements(&elements);
+    let parsed: Vec<Result<Vec<Value>, Error>> = chunks
+        .into_par_iter()
+        .map(|chunk| {
+            elements[chunk]
+                .iter()
+                .map(|element| parse_with(&input[element.clone()], options))
+                .collect()
+        })
+        .collect();
+
+    let mut values = Vec::with_capacity(elements.len());
+    for chunk in parsed {
+        match chunk {
+            Ok(chunk) => values.extend(chunk),
+            // parsed again as a whole so that the error is located in the
+            // whole input rather than the element
+            Err(_) => return parse_with(input, options),
+        }
+    }
+    Ok(Value::Array(values))
+}
+
+/// The byte ranges of the elements of a top-level array, without the
+/// whitespace around them, or `None` when the input isn't an array or isn't
+/// structurally valid
+fn split_elements(input: &str) -> Option<Vec<Range<usize>>> {
+    let bytes = input.as_bytes();
+    let open = skip_whitespace(bytes, 0);
+    if bytes.get(open) != Some(&b'[') {
+        return None;
+    }
+
+    let mut elements = Vec::new();
+    let mut depth = 0usize;
+    let mut start = open + 1;
+    let mut index = open;
+    let close = loop {
+        match *bytes.get(index)? {
+            b'"' => index = closing_quote(bytes, index).ok()?,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break index;
+                }
+            }
+            b',' if depth == 1 => {
+                elements.push(trim(bytes, start..index)?);
+                start = index + 1;
+            }
+            _ => {}
+        }
+        index += 1;
+    };
+
+    let last = trim(bytes, start..close);
+    match last {
+        Some(last) => elements.push(last),
+        // a trailing comma, which the usual parser reports
+        None if !elements.is_empty() => return None,
+        None => {}
+    }
+    if bytes[close] != b']' || skip_whitespace(bytes, close + 1) != bytes.len() {
+        return None;
+    }
+    Some(elements)
+}
+
+/// `range` without the whitespace at either end, or `None` when it's empty
+fn trim(bytes: &[u8], range: Range<usize>) -> Option<Range<usize>> {
+    let start = skip_whitespace(bytes, range.start).min(range.end);
+    let end = bytes[start..range.end]
+        .iter()
+        .rposition(|&byte| !is_whitespace(byte))?;
+    Some(start..start + end + 1)
+}
+
+/// Ranges of indices into `elements` for each thread to parse
+fn group_elements(elements: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (i, element) in elements.iter().enumerate() {
+        bytes += element.len();
+        if bytes >= CHUNK_BYTES {
+            chunks.push(start..i + 1);
+            start = i + 1;
+            bytes = 0;
+        }
+    }
+    if start < elements.len() {
+        chunks.push(start..elements.len());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_parallel, split_elements};
+    use crate::{parse, ParseOptions, Value};
+
+    fn check(input: &str) {
+        let actual = parse_parallel(input, &ParseOptions::default());
+
+        assert_eq!(actual, parse(input), "{input}");
+    }
+
+    #[test]
+    fn matches_sequential_parsing() {
+        check(r#" [ {"a": [1, 2]}, "x,]", [[]], null ]"#);
+        check("[]");
+        check(r#"{"not": "an array"}"#);
+
+        let rows: Vec<String> = (0..20_000)
+            .map(|i| format!(r#"{{"id": {i}, "name": "row {i}", "tags": ["a", "b"]}}"#))
+            .collect();
+        let input = format!("[{}]", rows.join(",\n"));
+        let Ok(Value::Array(values)) = parse_parallel(&input, &ParseOptions::default()) else {
+            panic!("expected an array");
+        };
+        assert_eq!(values.len(), 20_000);
+        assert_eq!(Value::Array(values), parse(&input).unwrap());
+    }
+
+    #[test]
+    fn splits_at_top_level_commas() {
+        let input = r#"[1, {"a": [2, 3]}, "4,5"]"#;
+        let elements: Vec<&str> = split_elements(input)
+            .unwrap()
+            .into_iter()
+            .map(|range| &input[range])
+            .collect();
+
+        assert_eq!(elements, ["1", r#"{"a": [2, 3]}"#, r#""4,5""#]);
+        assert_eq!(split_elements("[1, 2"), None);
+        assert_eq!(split_elements("[1,]"), None);
+        assert_eq!(split_elements("[1] 2"), None);
+    }
+
+    #[test]
+    fn errors() {
+        for input in ["[1,, 2]", r#"[1, {"a" 2}]"#, "[1, 2,]", "[1, 2] 3"] {
+            check(input);
+        }
+        assert!(parse_parallel("[1,, 2]", &ParseOptions::default()).is_err());
+    }
+}
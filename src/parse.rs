@@ -1,168 +1,632 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::HashSet;
 
-use crate::Value;
+use crate::map::{DefaultMapKind, MapKind, ObjectMap};
+use crate::{DuplicateKeyPolicy, LoneSurrogatePolicy, Number, ParseOptions, Str, Value};
 
 use super::tokenize::Token;
 
-pub type ParseResult = Result<Value, TokenParseError>;
-
-pub fn parse_tokens(tokens: &[Token], index: &mut usize) -> ParseResult {
-    let token = &tokens[*index];
-    if matches!(
-        token,
-        Token::Null | Token::False | Token::True | Token::Number(_) | Token::String(_)
-    ) {
-        *index += 1
-    }
-    match token {
-        Token::Null => Ok(Value::Null),
-        Token::False => Ok(Value::Boolean(false)),
-        Token::True => Ok(Value::Boolean(true)),
-        Token::Number(number) => Ok(Value::Number(*number)),
-        Token::String(string) => parse_string(string),
-        Token::LeftBracket => parse_array(tokens, index),
-        Token::LeftBrace => parse_object(tokens, index),
-        _ => Err(TokenParseError::ExpectedValue),
+pub type ParseResult<K = DefaultMapKind> = Result<Value<K>, TokenParseError>;
+
+pub fn parse_tokens<K: MapKind>(
+    tokens: &[Token],
+    index: &mut usize,
+    options: &ParseOptions,
+) -> ParseResult<K> {
+    let mut budget = Budget {
+        used: 0,
+        limit: options.max_memory,
+    };
+    parse_value(&mut &*tokens, index, options, &mut budget)
+}
+
+/// Tokens that the parser reads in order, by their index among all of the
+/// tokens. Only the next token is looked at before moving past it, so the
+/// tokens can be produced as they're needed rather than collected first.
+pub(crate) trait TokenSource<'t> {
+    /// The token at `index`, which is either the next token or, when the
+    /// next one has been looked at, the one after it
+    fn token(&mut self, index: usize) -> Option<&Token<'t>>;
+
+    /// Moves past the token at `index` like [`token`](Self::token),
+    /// returning it
+    fn take(&mut self, index: usize) -> Option<Token<'t>>;
+}
+
+impl<'t> TokenSource<'t> for &'t [Token<'_>] {
+    fn token(&mut self, index: usize) -> Option<&Token<'t>> {
+        self.get(index)
+    }
+
+    fn take(&mut self, index: usize) -> Option<Token<'t>> {
+        self.get(index).map(Token::reborrow)
+    }
+}
+
+impl<'t, S: TokenSource<'t>> TokenSource<'t> for &mut S {
+    fn token(&mut self, index: usize) -> Option<&Token<'t>> {
+        (**self).token(index)
+    }
+
+    fn take(&mut self, index: usize) -> Option<Token<'t>> {
+        (**self).take(index)
+    }
+}
+
+/// Parses the tokens of a whole document, which hold a single value unless
+/// [`ParseOptions::allow_trailing_content`] is set. `trailing_offset` finds
+/// where the tokens after the value start, given the index of the first of
+/// them, which is only needed to report them.
+///
+/// An error is returned with the index of the token it was found at, which
+/// is the number of tokens when they ended too soon.
+pub(crate) fn parse_document<'t, K: MapKind, S: TokenSource<'t>>(
+    mut tokens: S,
+    options: &ParseOptions,
+    trailing_offset: impl FnOnce(&mut S, usize) -> usize,
+) -> Result<Value<K>, (TokenParseError, usize)> {
+    if tokens.token(0).is_none() {
+        return Err((TokenParseError::EmptyInput, 0));
+    }
+    let mut index = 0;
+    let mut budget = Budget {
+        used: 0,
+        limit: options.max_memory,
+    };
+    let value =
+        parse_value(&mut tokens, &mut index, options, &mut budget).map_err(|err| (err, index))?;
+    // the text after the value is ignored without being tokenized when
+    // it's allowed
+    if !options.allow_trailing_content && tokens.token(index).is_some() {
+        let offset = trailing_offset(&mut tokens, index);
+        return Err((TokenParseError::UnexpectedTrailingTokens { offset }, index));
     }
+    Ok(value)
 }
 
-fn parse_string(input: &str) -> ParseResult {
-    let unescaped = unescape_string(input)?;
-    Ok(Value::String(unescaped))
+/// Approximate bytes allocated for the value being built, which is checked
+/// against [`ParseOptions::max_memory`]
+struct Budget {
+    used: usize,
+    limit: Option<usize>,
 }
 
-fn unescape_string(input: &str) -> Result<String, TokenParseError> {
+impl Budget {
+    fn charge(&mut self, bytes: usize) -> Result<(), TokenParseError> {
+        self.used = self.used.saturating_add(bytes);
+        match self.limit {
+            Some(limit) if self.used > limit => Err(TokenParseError::MemoryLimitExceeded),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parses the value starting at `index`, keeping the arrays and objects that
+/// it's inside of on a stack rather than recursing, so that deeply nested
+/// input can't overflow the call stack. An error leaves `index` at the
+/// token it was found at.
+fn parse_value<'t, K: MapKind>(
+    tokens: &mut impl TokenSource<'t>,
+    index: &mut usize,
+    options: &ParseOptions,
+    budget: &mut Budget,
+) -> ParseResult<K> {
+    let mut stack: Vec<Frame<'t, K>> = Vec::new();
+    loop {
+        let token = tokens.take(*index).ok_or_else(|| match stack.last() {
+            Some(frame) => frame.unclosed(),
+            None => TokenParseError::EmptyInput,
+        })?;
+        *index += 1;
+        let mut value = match token {
+            Token::Null => Value::Null,
+            Token::False => Value::Boolean(false),
+            Token::True => Value::Boolean(true),
+            Token::Number(number) => {
+                if let Number::Literal(literal) = &number {
+                    budget.charge(literal.len()).inspect_err(|_| *index -= 1)?;
+                }
+                Value::Number(number)
+            }
+            Token::String(string) => {
+                // the unescaped string is never longer than its source text,
+                // and an error in it is reported at the string
+                budget
+                    .charge(string.len())
+                    .and_then(|()| parse_string(&string, options))
+                    .inspect_err(|_| *index -= 1)?
+            }
+            Token::LeftBracket if tokens.token(*index) == Some(&Token::RightBracket) => {
+                *index += 1;
+                Value::Array(Vec::new())
+            }
+            Token::LeftBracket => {
+                let mut array = Vec::new();
+                reserve_element(&mut array, options, budget)?;
+                stack.push(Frame::Array(array));
+                continue;
+            }
+            Token::LeftBrace if tokens.token(*index) == Some(&Token::RightBrace) => {
+                *index += 1;
+                Value::Object(K::Map::default())
+            }
+            Token::LeftBrace => {
+                let map = K::Map::default();
+                let key = next_key::<K>(&map, tokens, index, options, budget)?;
+                stack.push(Frame::Object {
+                    map,
+                    collected: HashSet::new(),
+                    key,
+                });
+                continue;
+            }
+            _ => {
+                // the error is reported at the token found instead
+                *index -= 1;
+                return Err(TokenParseError::ExpectedValue);
+            }
+        };
+
+        // add the value to the container it's in, closing each container
+        // that ends after it
+        loop {
+            let Some(frame) = stack.last_mut() else {
+                return Ok(value);
+            };
+            frame.add(value, options)?;
+
+            let closing = frame.closing_token();
+            let mut token = tokens.token(*index).ok_or(frame.unclosed())?;
+            if *token == Token::Comma {
+                *index += 1;
+                token = tokens.token(*index).ok_or(frame.unclosed())?;
+                if *token != closing {
+                    match frame {
+                        Frame::Array(array) => reserve_element(array, options, budget)?,
+                        Frame::Object { map, key, .. } => {
+                            *key = next_key::<K>(map, tokens, index, options, budget)?;
+                        }
+                    }
+                    break;
+                }
+                if !options.allow_trailing_commas {
+                    *index -= 1;
+                    return Err(TokenParseError::TrailingComma);
+                }
+            }
+            if *token != closing {
+                return Err(TokenParseError::ExpectedComma);
+            }
+            *index += 1;
+            value = stack.pop().expect("a container is open").into_value();
+        }
+    }
+}
+
+/// An array or object that has been opened but not yet closed
+enum Frame<'t, K: MapKind> {
+    Array(Vec<Value<K>>),
+    Object {
+        map: K::Map<Value<K>>,
+        /// Keys whose values have been gathered into an array by `CollectAll`
+        collected: HashSet<String>,
+        /// Key of the entry whose value is being parsed
+        key: Cow<'t, str>,
+    },
+}
+
+impl<K: MapKind> Frame<'_, K> {
+    /// Adds the value of the element or entry that was started, which
+    /// [`reserve_element`] or [`next_key`] already checked against the limits
+    fn add(&mut self, value: Value<K>, options: &ParseOptions) -> Result<(), TokenParseError> {
+        match self {
+            Frame::Array(array) => {
+                array.push(value);
+                Ok(())
+            }
+            Frame::Object {
+                map,
+                collected,
+                key,
+            } => insert_entry(map, collected, std::mem::take(key), value, options),
+        }
+    }
+
+    fn closing_token(&self) -> Token<'static> {
+        match self {
+            Frame::Array(_) => Token::RightBracket,
+            Frame::Object { .. } => Token::RightBrace,
+        }
+    }
+
+    /// The error for input that ends before the container is closed
+    fn unclosed(&self) -> TokenParseError {
+        match self {
+            Frame::Array(_) => TokenParseError::UnclosedBracket,
+            Frame::Object { .. } => TokenParseError::UnclosedBrace,
+        }
+    }
+
+    fn into_value(self) -> Value<K> {
+        match self {
+            Frame::Array(array) => Value::Array(array),
+            Frame::Object { map, .. } => Value::Object(map),
+        }
+    }
+}
+
+/// Makes room for the next element of an array before it's parsed, so that
+/// one too many, or growing the array past the memory budget, is reported at
+/// the element's first token
+fn reserve_element<K: MapKind>(
+    array: &mut Vec<Value<K>>,
+    options: &ParseOptions,
+    budget: &mut Budget,
+) -> Result<(), TokenParseError> {
+    if options
+        .max_array_length
+        .is_some_and(|max| array.len() >= max)
+    {
+        return Err(TokenParseError::TooManyElements);
+    }
+    let capacity = array.capacity();
+    array.reserve(1);
+    budget.charge((array.capacity() - capacity) * size_of::<Value<K>>())
+}
+
+/// Reads the key of the next entry of `map` like [`object_key`], reporting a
+/// duplicate key or one entry too many at the key
+fn next_key<'t, K: MapKind>(
+    map: &K::Map<Value<K>>,
+    tokens: &mut impl TokenSource<'t>,
+    index: &mut usize,
+    options: &ParseOptions,
+    budget: &mut Budget,
+) -> Result<Cow<'t, str>, TokenParseError> {
+    let start = *index;
+    let key = object_key::<K>(tokens, index, options, budget)?;
+    let error = if map.get(&key).is_some() {
+        let duplicate = options.duplicate_keys == DuplicateKeyPolicy::Error;
+        duplicate.then(|| TokenParseError::DuplicateKey(key.to_string()))
+    } else {
+        let full = options
+            .max_object_entries
+            .is_some_and(|max| map.len() >= max);
+        full.then_some(TokenParseError::TooManyEntries)
+    };
+    match error {
+        Some(err) => {
+            *index = start;
+            Err(err)
+        }
+        None => Ok(key),
+    }
+}
+
+/// Reads the key and colon of an object entry, leaving `index` at its value
+fn object_key<'t, K: MapKind>(
+    tokens: &mut impl TokenSource<'t>,
+    index: &mut usize,
+    options: &ParseOptions,
+    budget: &mut Budget,
+) -> Result<Cow<'t, str>, TokenParseError> {
+    let key = match tokens.take(*index) {
+        Some(Token::String(key)) => key,
+        Some(_) => return Err(TokenParseError::ExpectedProperty),
+        None => return Err(TokenParseError::UnclosedBrace),
+    };
+    // an error in the key is reported at it, before moving past it
+    budget.charge(key.len() + size_of::<(Str, Value<K>)>())?;
+    let key = match key {
+        Cow::Borrowed(key) => unescape_string(key, options)?,
+        Cow::Owned(key) => Cow::Owned(unescape_string(&key, options)?.into_owned()),
+    };
+    check_length(&key, options)?;
+    *index += 1;
+    match tokens.token(*index) {
+        Some(Token::Colon) => {}
+        Some(_) => return Err(TokenParseError::ExpectedColon),
+        None => return Err(TokenParseError::UnclosedBrace),
+    }
+    *index += 1;
+    Ok(key)
+}
+
+pub(crate) fn parse_string<K: MapKind>(input: &str, options: &ParseOptions) -> ParseResult<K> {
+    let unescaped = unescape_string(input, options)?;
+    check_length(&unescaped, options)?;
+    Ok(Value::String(unescaped.into()))
+}
+
+/// Checks an unescaped string or key against
+/// [`ParseOptions::max_string_length`]
+pub(crate) fn check_length(string: &str, options: &ParseOptions) -> Result<(), TokenParseError> {
+    match options.max_string_length {
+        Some(max) if string.len() > max => Err(TokenParseError::StringTooLong),
+        _ => Ok(()),
+    }
+}
+
+/// Unescapes the text of a string token, which is only copied when it
+/// contains an escape
+pub(crate) fn unescape_string<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Cow<'a, str>, TokenParseError> {
+    if !input.contains('\\') {
+        return Ok(Cow::Borrowed(input));
+    }
     // Create a new string to hold the processed/unescaped characters
-    let mut output = String::new();
+    let mut output = String::with_capacity(input.len());
+    unescape_chars(input.chars(), options, |ch| output.push(ch))?;
+    Ok(Cow::Owned(output))
+}
 
+/// Unescapes the text of a string token, passing each resulting character to
+/// `output`, so that escapes can be checked without building a `String`
+pub(crate) fn unescape_chars(
+    chars: impl Iterator<Item = char>,
+    options: &ParseOptions,
+    mut output: impl FnMut(char),
+) -> Result<(), TokenParseError> {
+    let mut chars = chars.peekable();
     let mut is_escaping = false;
-    let mut chars = input.chars();
+    // byte offset of the current character within the string's text
+    let mut offset = 0;
+    // a high surrogate waiting for the low surrogate that completes the pair
+    let mut high_surrogate = None;
     while let Some(next_char) = chars.next() {
+        offset += next_char.len_utf8();
+        // only a `\u` escape straight after a high surrogate can pair with it
+        let may_pair = if is_escaping {
+            next_char == 'u'
+        } else {
+            next_char == '\\'
+        };
+        if !may_pair {
+            if let Some(high) = high_surrogate.take() {
+                lone_surrogate(high, options, &mut output)?;
+            }
+        }
         if is_escaping {
             match next_char {
-                '"' => output.push('"'),
-                '\\' => output.push('\\'),
+                '"' => output('"'),
+                '\\' => output('\\'),
+                '/' => output('/'),
+                '\'' if options.allow_single_quotes => output('\''),
+                // a line continuation, where the line break isn't part of
+                // the string
+                '\r' if options.allow_line_continuations => {
+                    if chars.next_if_eq(&'\n').is_some() {
+                        offset += 1;
+                    }
+                }
+                '\n' | '\u{2028}' | '\u{2029}' if options.allow_line_continuations => {}
                 // `\b` (backspace) is a valid escape in JSON, but not Rust
-                'b' => output.push('\u{8}'),
+                'b' => output('\u{8}'),
                 // `\f` (formfeed) is a valid escape in JSON, but not Rust
-                'f' => output.push('\u{12}'),
-                'n' => output.push('\n'),
-                'r' => output.push('\r'),
-                't' => output.push('\t'),
+                'f' => output('\u{c}'),
+                'n' => output('\n'),
+                'r' => output('\r'),
+                't' => output('\t'),
                 'u' => {
-                    let mut sum = 0;
-                    for i in 0..4 {
-                        let next_char = chars.next().ok_or(TokenParseError::UnfinishedEscape)?;
-                        let digit = next_char
-                            .to_digit(16)
-                            .ok_or(TokenParseError::InvalidHexValue)?;
-                        sum += (16u32).pow(3 - i) * digit;
+                    let start = offset - 2;
+                    let invalid = TokenParseError::InvalidHexValue { offset: start };
+                    let mut digits = ['0'; 4];
+                    let mut unit = 0;
+                    for digit in &mut digits {
+                        // the string ending is the closing quote taking the
+                        // place of a digit
+                        let next_char = chars.next().ok_or_else(|| invalid.clone())?;
+                        offset += next_char.len_utf8();
+                        unit = unit * 16 + next_char.to_digit(16).ok_or_else(|| invalid.clone())?;
+                        *digit = next_char;
+                    }
+                    match (high_surrogate.take(), unit) {
+                        (Some(high), 0xDC00..=0xDFFF) => {
+                            let code_point =
+                                0x10000 + ((high.unit - 0xD800) << 10) + (unit - 0xDC00);
+                            output(char::from_u32(code_point).expect("surrogate pairs are valid"));
+                        }
+                        (high, _) => {
+                            if let Some(high) = high {
+                                lone_surrogate(high, options, &mut output)?;
+                            }
+                            let surrogate = Surrogate {
+                                unit,
+                                digits,
+                                offset: start,
+                            };
+                            match unit {
+                                0xD800..=0xDBFF => high_surrogate = Some(surrogate),
+                                0xDC00..=0xDFFF => lone_surrogate(surrogate, options, &mut output)?,
+                                _ => output(char::from_u32(unit).expect("not a surrogate")),
+                            }
+                        }
                     }
-                    let unescaped_char =
-                        char::from_u32(sum).ok_or(TokenParseError::InvalidHexValue)?;
-                    output.push(unescaped_char);
                 }
-                // any other character *may* be escaped, ex. `\q` just push that letter `q`
-                _ => output.push(next_char),
+                // any other character may be escaped when lenient, ex. `\q`
+                // just pushes that letter `q`
+                _ if options.allow_invalid_escapes => output(next_char),
+                _ => {
+                    return Err(TokenParseError::InvalidEscape {
+                        escape: next_char,
+                        offset: offset - next_char.len_utf8() - 1,
+                    })
+                }
             }
             is_escaping = false;
         } else if next_char == '\\' {
             is_escaping = true;
         } else {
-            output.push(next_char);
+            output(next_char);
         }
     }
-    Ok(output)
+    if is_escaping {
+        return Err(TokenParseError::UnfinishedEscape);
+    }
+    if let Some(high) = high_surrogate {
+        lone_surrogate(high, options, &mut output)?;
+    }
+    Ok(())
 }
 
-fn parse_array(tokens: &[Token], index: &mut usize) -> ParseResult {
-    debug_assert!(tokens[*index] == Token::LeftBracket);
+/// A `\u` escape of a UTF-16 surrogate
+struct Surrogate {
+    unit: u32,
+    /// The hex digits as written
+    digits: [char; 4],
+    /// Byte offset of the escape's backslash within the string's text
+    offset: usize,
+}
 
-    let mut array: Vec<Value> = Vec::new();
-    loop {
-        // consume the previous LeftBracket or Comma token
-        *index += 1;
-        if tokens[*index] == Token::RightBracket {
-            break;
+/// Handles a surrogate that isn't part of a pair according to
+/// [`ParseOptions::lone_surrogates`]
+fn lone_surrogate(
+    surrogate: Surrogate,
+    options: &ParseOptions,
+    output: &mut impl FnMut(char),
+) -> Result<(), TokenParseError> {
+    match options.lone_surrogates {
+        LoneSurrogatePolicy::Error => Err(TokenParseError::LoneSurrogate {
+            surrogate: surrogate.unit as u16,
+            offset: surrogate.offset,
+        }),
+        LoneSurrogatePolicy::Replace => {
+            output(char::REPLACEMENT_CHARACTER);
+            Ok(())
         }
-
-        let value = parse_tokens(tokens, index)?;
-        array.push(value);
-
-        let token = &tokens[*index];
-        match token {
-            Token::Comma => {}
-            Token::RightBracket => break,
-            _ => return Err(TokenParseError::ExpectedComma),
+        LoneSurrogatePolicy::Preserve => {
+            output('\\');
+            output('u');
+            surrogate.digits.into_iter().for_each(output);
+            Ok(())
         }
     }
-    // consume the RightBracket token
-    *index += 1;
+}
 
-    Ok(Value::Array(array))
+/// The stored form of an object key, shared with earlier occurrences when
+/// there is a key interner
+#[cfg(feature = "arc_str")]
+fn key_str(key: Cow<str>, options: &ParseOptions) -> Str {
+    match &options.key_interner {
+        Some(interner) => interner.intern(&key),
+        None => key.into(),
+    }
 }
 
-fn parse_object(tokens: &[Token], index: &mut usize) -> ParseResult {
-    debug_assert!(tokens[*index] == Token::LeftBrace);
+#[cfg(not(feature = "arc_str"))]
+fn key_str(key: Cow<str>, _: &ParseOptions) -> Str {
+    key.into()
+}
 
-    let mut map = HashMap::new();
-    loop {
-        // consume the previous LeftBrace or Comma token
-        *index += 1;
-        if tokens[*index] == Token::RightBrace {
-            break;
+pub(crate) fn insert_entry<K: MapKind>(
+    map: &mut K::Map<Value<K>>,
+    collected: &mut HashSet<String>,
+    key: Cow<str>,
+    value: Value<K>,
+    options: &ParseOptions,
+) -> Result<(), TokenParseError> {
+    let existing = match map.get_mut(&key) {
+        Some(existing) => existing,
+        None => {
+            map.insert(key_str(key, options), value);
+            return Ok(());
         }
-
-        if let Token::String(s) = &tokens[*index] {
-            *index += 1;
-            if Token::Colon == tokens[*index] {
-                *index += 1;
-                let key = unescape_string(s)?;
-                let value = parse_tokens(tokens, index)?;
-                map.insert(key, value);
+    };
+
+    match options.duplicate_keys {
+        DuplicateKeyPolicy::LastWins => *existing = value,
+        DuplicateKeyPolicy::FirstWins => {}
+        DuplicateKeyPolicy::Error => return Err(TokenParseError::DuplicateKey(key.into_owned())),
+        DuplicateKeyPolicy::CollectAll => {
+            if collected.contains(key.as_ref()) {
+                if let Value::Array(values) = existing {
+                    values.push(value);
+                }
             } else {
-                return Err(TokenParseError::ExpectedColon);
+                let first = std::mem::replace(existing, Value::Null);
+                *existing = Value::Array(vec![first, value]);
+                collected.insert(key.into_owned());
             }
-
-            match &tokens[*index] {
-                Token::Comma => {}
-                Token::RightBrace => break,
-                _ => return Err(TokenParseError::ExpectedComma),
-            }
-        } else {
-            return Err(TokenParseError::ExpectedProperty);
         }
     }
-    *index += 1;
-
-    Ok(Value::Object(map))
+    Ok(())
 }
 
-#[derive(Debug, PartialEq)]
-pub enum TokenParseError {
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenParseError {
+    /// The input has no value, only whitespace or comments if anything
+    EmptyInput,
     EarlyEOF,
     UnclosedBracket,
     UnclosedBrace,
 
     UnfinishedEscape,
-    InvalidHexValue,
-    InvalidCodePointValue,
+
+    /// A `\u` escape without four hex digits, where `offset` is the byte
+    /// offset of its backslash within the string's text
+    InvalidHexValue {
+        offset: usize,
+    },
 
     ExpectedColon,
     ExpectedComma,
     ExpectedValue,
     ExpectedProperty,
 
-    NeedsComma,
     TrailingComma,
+
+    DuplicateKey(String),
+
+    /// A backslash followed by a character that isn't a valid escape, where
+    /// `offset` is the byte offset of the backslash within the string's text
+    InvalidEscape {
+        escape: char,
+        offset: usize,
+    },
+
+    /// A `\u` escape of a UTF-16 surrogate that isn't part of a pair, where
+    /// `offset` is the byte offset of its backslash within the string's text,
+    /// see [`ParseOptions::lone_surrogates`]
+    LoneSurrogate {
+        surrogate: u16,
+        offset: usize,
+    },
+
+    /// There are more tokens after the top-level value, the first of which
+    /// starts at the byte `offset` in the input
+    UnexpectedTrailingTokens {
+        offset: usize,
+    },
+
+    /// The parsed value would use more memory than
+    /// [`ParseOptions::max_memory`] allows
+    MemoryLimitExceeded,
+
+    /// A string or object key is longer than
+    /// [`ParseOptions::max_string_length`] allows
+    StringTooLong,
+
+    /// An array has more elements than [`ParseOptions::max_array_length`]
+    /// allows
+    TooManyElements,
+
+    /// An object has more entries than [`ParseOptions::max_object_entries`]
+    /// allows
+    TooManyEntries,
 }
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+
     use crate::tokenize::Token;
-    use crate::Value;
+    use crate::{
+        DefaultMapKind, DuplicateKeyPolicy, LoneSurrogatePolicy, Number, ParseOptions, Value,
+    };
 
-    use super::{parse_tokens, TokenParseError};
+    use super::{parse_tokens, unescape_string, TokenParseError};
 
     /// Helper to reduce boilerplate of asserting on the expected value
     ///
@@ -172,12 +636,13 @@ mod tests {
     ///
     /// In other cases, a function like this may really help with readability.
     fn check(input: &[Token], expected: Value) {
-        let actual = parse_tokens(input, &mut 0).unwrap();
+        let actual = parse_tokens(input, &mut 0, &ParseOptions::default()).unwrap();
         assert_eq!(actual, expected);
     }
 
     fn check_error(input: &[Token], expected: TokenParseError) {
-        let actual = parse_tokens(&input, &mut 0).unwrap_err();
+        let actual =
+            parse_tokens::<DefaultMapKind>(input, &mut 0, &ParseOptions::default()).unwrap_err();
         assert_eq!(actual, expected);
     }
 
@@ -207,8 +672,8 @@ mod tests {
 
     #[test]
     fn parses_number() {
-        let input = [Token::Number(12.34)];
-        let expected = Value::Number(12.34);
+        let input = [Token::Number(Number::F64(12.34))];
+        let expected = Value::Number(Number::F64(12.34));
 
         check(&input, expected);
     }
@@ -216,7 +681,7 @@ mod tests {
     #[test]
     fn parses_string_no_escapes() {
         let input = [Token::string("hello world")];
-        let expected = Value::String(String::from("hello world"));
+        let expected = Value::string("hello world");
 
         check(&input, expected);
     }
@@ -224,7 +689,7 @@ mod tests {
     #[test]
     fn parses_string_non_ascii() {
         let input = [Token::string("olá_こんにちは_नमस्ते_привіт")];
-        let expected = Value::String(String::from("olá_こんにちは_नमस्ते_привіт"));
+        let expected = Value::string("olá_こんにちは_नमस्ते_привіт");
 
         check(&input, expected);
     }
@@ -232,7 +697,7 @@ mod tests {
     #[test]
     fn parses_string_with_unescaped_emoji() {
         let input = [Token::string("hello 💩 world")];
-        let expected = Value::String(String::from("hello 💩 world"));
+        let expected = Value::string("hello 💩 world");
 
         check(&input, expected);
     }
@@ -240,15 +705,32 @@ mod tests {
     #[test]
     fn parses_string_with_unnecessarily_escaped_emoji() {
         let input = [Token::string(r#"hello \💩 world"#)];
-        let expected = Value::String(String::from("hello 💩 world"));
+        let expected = Value::string("hello 💩 world");
+        let options = ParseOptions::new().allow_invalid_escapes(true);
+
+        assert_eq!(parse_tokens(&input, &mut 0, &options), Ok(expected));
+        let expected = TokenParseError::InvalidEscape {
+            escape: '💩',
+            offset: 6,
+        };
+        check_error(&input, expected);
+    }
 
-        check(&input, expected);
+    #[test]
+    fn invalid_escape_offset() {
+        let input = [Token::string(r#"é\t\u0041\x"#)];
+        let expected = TokenParseError::InvalidEscape {
+            escape: 'x',
+            offset: 10,
+        };
+
+        check_error(&input, expected);
     }
 
     #[test]
     fn parses_string_unescape_backslash() {
         let input = [Token::string(r#"hello\\world"#)];
-        let expected = Value::String(String::from(r#"hello\world"#));
+        let expected = Value::string(r#"hello\world"#);
 
         check(&input, expected);
     }
@@ -256,24 +738,53 @@ mod tests {
     #[test]
     fn parses_string_unescape_newline() {
         let input = [Token::string(r#"hello\nworld"#)];
-        let expected = Value::String(String::from("hello\nworld"));
+        let expected = Value::string("hello\nworld");
 
         check(&input, expected);
     }
 
     #[test]
-    #[ignore = "decoding of UTF-16 surrogate pairs is not implemented"]
+    fn unescape_only_copies_escaped_strings() {
+        assert!(matches!(
+            unescape_string("no escapes", &ParseOptions::default()),
+            Ok(Cow::Borrowed("no escapes"))
+        ));
+        assert!(matches!(
+            unescape_string(r#"a\tb"#, &ParseOptions::default()),
+            Ok(Cow::Owned(s)) if s == "a\tb"
+        ));
+    }
+
+    #[test]
     fn parses_string_with_escaped_surrogate_pairs_for_an_emoji() {
         let input = [Token::string(r#"hello\uD83C\uDF3Cworld"#)];
-        let expected = Value::String(String::from("hello🌼world"));
+        let expected = Value::string("hello🌼world");
 
         check(&input, expected);
     }
 
+    #[test]
+    fn lone_surrogates() {
+        let input = [Token::string(r#"a\uD800b\udc00\uD83C\uD83C\uDF3C\uD83C"#)];
+        let expected = TokenParseError::LoneSurrogate {
+            surrogate: 0xD800,
+            offset: 1,
+        };
+        check_error(&input, expected);
+
+        let options = ParseOptions::new().lone_surrogates(LoneSurrogatePolicy::Replace);
+        let expected = Value::string("a\u{FFFD}b\u{FFFD}\u{FFFD}🌼\u{FFFD}");
+        assert_eq!(parse_tokens(&input, &mut 0, &options), Ok(expected));
+
+        let options = ParseOptions::new().lone_surrogates(LoneSurrogatePolicy::Preserve);
+        let expected = Value::string(r#"a\uD800b\udc00\uD83C🌼\uD83C"#);
+        assert_eq!(parse_tokens(&input, &mut 0, &options), Ok(expected));
+    }
+
     #[test]
     fn all_the_simple_escapes() {
         let input = [Token::string(r#"\"\/\\\b\f\n\r\t"#)];
-        let expected = Value::String(String::from("\"/\\\u{8}\u{12}\n\r\t"));
+        let expected = Value::string("\"/\\\u{8}\u{c}\n\r\t");
 
         check(&input, expected);
     }
@@ -303,10 +814,10 @@ mod tests {
             Token::LeftBracket,
             Token::Null,
             Token::Comma,
-            Token::Number(16.0),
+            Token::Number(Number::I64(16)),
             Token::RightBracket,
         ];
-        let expected = Value::Array(vec![Value::Null, Value::Number(16.0)]);
+        let expected = Value::Array(vec![Value::Null, Value::Number(Number::I64(16))]);
 
         check(&input, expected);
     }
@@ -343,7 +854,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "the current implementation allows trailing commas"]
     fn fails_array_trailing_comma() {
         // [true,]
         let input = [
@@ -357,6 +867,75 @@ mod tests {
         check_error(&input, expected);
     }
 
+    #[test]
+    fn fails_unclosed_array() {
+        // [true,
+        let input = [Token::LeftBracket, Token::True, Token::Comma];
+        let expected = TokenParseError::UnclosedBracket;
+
+        check_error(&input, expected);
+    }
+
+    #[test]
+    fn fails_unclosed_object() {
+        // {"a": true
+        let input = [
+            Token::LeftBrace,
+            Token::string("a"),
+            Token::Colon,
+            Token::True,
+        ];
+        let expected = TokenParseError::UnclosedBrace;
+
+        check_error(&input, expected);
+    }
+
+    #[test]
+    fn truncated_token_streams() {
+        // {"a": [1, {"b": null}]}
+        let input = [
+            Token::LeftBrace,
+            Token::string("a"),
+            Token::Colon,
+            Token::LeftBracket,
+            Token::Number(1.into()),
+            Token::Comma,
+            Token::LeftBrace,
+            Token::string("b"),
+            Token::Colon,
+            Token::Null,
+            Token::RightBrace,
+            Token::RightBracket,
+            Token::RightBrace,
+        ];
+        // the error for the tokens ending after each number of them, which
+        // is the innermost container still open
+        let expected = [
+            TokenParseError::EmptyInput,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBracket,
+            TokenParseError::UnclosedBracket,
+            TokenParseError::UnclosedBracket,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBrace,
+            TokenParseError::UnclosedBracket,
+            TokenParseError::UnclosedBrace,
+        ];
+        assert_eq!(expected.len(), input.len());
+        for (end, expected) in expected.into_iter().enumerate() {
+            let mut index = 0;
+            let result =
+                parse_tokens::<DefaultMapKind>(&input[..end], &mut index, &ParseOptions::default());
+            assert_eq!(result, Err(expected), "{:?}", &input[..end]);
+            // reported at the end of the tokens
+            assert_eq!(index, end, "{:?}", &input[..end]);
+        }
+    }
+
     #[test]
     fn parses_empty_object() {
         let input = [Token::LeftBrace, Token::RightBrace];
@@ -392,4 +971,110 @@ mod tests {
 
         check(&input, expected);
     }
+
+    fn parse_duplicates(policy: DuplicateKeyPolicy) -> Result<Value, TokenParseError> {
+        // {"a": 1, "b": true, "a": 2, "a": 3}
+        let input = [
+            Token::LeftBrace,
+            Token::string("a"),
+            Token::Colon,
+            Token::Number(Number::I64(1)),
+            Token::Comma,
+            Token::string("b"),
+            Token::Colon,
+            Token::True,
+            Token::Comma,
+            Token::string("a"),
+            Token::Colon,
+            Token::Number(Number::I64(2)),
+            Token::Comma,
+            Token::string("a"),
+            Token::Colon,
+            Token::Number(Number::I64(3)),
+            Token::RightBrace,
+        ];
+        let options = ParseOptions {
+            duplicate_keys: policy,
+            ..ParseOptions::default()
+        };
+        parse_tokens(&input, &mut 0, &options)
+    }
+
+    #[test]
+    fn duplicate_keys_last_wins() {
+        let actual = parse_duplicates(DuplicateKeyPolicy::LastWins).unwrap();
+        let expected = Value::object([
+            ("a", Value::Number(Number::I64(3))),
+            ("b", Value::Boolean(true)),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn duplicate_keys_first_wins() {
+        let actual = parse_duplicates(DuplicateKeyPolicy::FirstWins).unwrap();
+        let expected = Value::object([
+            ("a", Value::Number(Number::I64(1))),
+            ("b", Value::Boolean(true)),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn duplicate_keys_error() {
+        let actual = parse_duplicates(DuplicateKeyPolicy::Error).unwrap_err();
+        let expected = TokenParseError::DuplicateKey(String::from("a"));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn duplicate_keys_collect_all() {
+        let actual = parse_duplicates(DuplicateKeyPolicy::CollectAll).unwrap();
+        let expected = Value::object([
+            (
+                "a",
+                Value::Array(vec![
+                    Value::Number(Number::I64(1)),
+                    Value::Number(Number::I64(2)),
+                    Value::Number(Number::I64(3)),
+                ]),
+            ),
+            ("b", Value::Boolean(true)),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn memory_limit() {
+        // ["a string", {"key": "value"}]
+        let input = [
+            Token::LeftBracket,
+            Token::string("a string"),
+            Token::Comma,
+            Token::LeftBrace,
+            Token::string("key"),
+            Token::Colon,
+            Token::string("value"),
+            Token::RightBrace,
+            Token::RightBracket,
+        ];
+        let parse_with_limit = |max_memory| {
+            let options = ParseOptions {
+                max_memory,
+                ..ParseOptions::default()
+            };
+            parse_tokens::<DefaultMapKind>(&input, &mut 0, &options)
+        };
+
+        assert!(parse_with_limit(None).is_ok());
+        assert!(parse_with_limit(Some(1024)).is_ok());
+        assert_eq!(
+            parse_with_limit(Some(16)),
+            Err(TokenParseError::MemoryLimitExceeded)
+        );
+    }
 }
@@ -0,0 +1,321 @@
+//! JSON Patch (RFC 6902) documents, lists of operations such as
+//! `{"op": "add", "path": "/a/0", "value": 1}` that transform one document into
+//! another
+
+use crate::map::{MapKind, ObjectMap};
+use crate::Value;
+
+/// When the differing parts of two arrays have more pairs of elements than
+/// this, they are compared index by index instead of by longest common
+/// subsequence, which needs memory proportional to the product of their lengths
+const LCS_LIMIT: usize = 1_000_000;
+
+/// Creates a JSON Patch that transforms `old` into `new`.
+///
+/// Objects are compared key by key. Arrays are aligned by their longest common
+/// subsequence so that inserting or removing one element produces a single
+/// operation; an element that is replaced by another object or array of the
+/// same kind is diffed recursively. Any other change replaces the value as a
+/// whole.
+///
+/// Only `add`, `remove` and `replace` operations are produced, and each path
+/// refers to the document as changed by the operations before it.
+pub fn diff<K: MapKind>(old: &Value<K>, new: &Value<K>) -> Value<K> {
+    let mut ops = Vec::new();
+    diff_values(old, new, &mut String::new(), &mut ops);
+    Value::Array(ops)
+}
+
+fn diff_values<K: MapKind>(
+    old: &Value<K>,
+    new: &Value<K>,
+    path: &mut String,
+    ops: &mut Vec<Value<K>>,
+) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(old, new, path, ops),
+        (Value::Array(old), Value::Array(new)) => diff_arrays(old, new, path, ops),
+        _ => ops.push(operation("replace", path, Some(new))),
+    }
+}
+
+fn diff_objects<K: MapKind>(
+    old: &K::Map<Value<K>>,
+    new: &K::Map<Value<K>>,
+    path: &mut String,
+    ops: &mut Vec<Value<K>>,
+) {
+    for (key, old_value) in old.iter() {
+        with_key(path, key, |path| match new.get(key) {
+            Some(new_value) => diff_values(old_value, new_value, path, ops),
+            None => ops.push(operation("remove", path, None)),
+        });
+    }
+    for (key, new_value) in new.iter() {
+        if !old.contains_key(key) {
+            with_key(path, key, |path| {
+                ops.push(operation("add", path, Some(new_value)))
+            });
+        }
+    }
+}
+
+/// One step of turning the old array into the new one, with indices into the
+/// old and new arrays
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Remove(usize),
+    Insert(usize),
+}
+
+fn diff_arrays<K: MapKind>(
+    old: &[Value<K>],
+    new: &[Value<K>],
+    path: &mut String,
+    ops: &mut Vec<Value<K>>,
+) {
+    // a shared prefix and suffix can't be part of a better alignment, and
+    // trimming them keeps the LCS table small for the common case of a few
+    // changes in a long array
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let edits = if old.len().saturating_mul(new.len()) <= LCS_LIMIT {
+        lcs_edits(old, new)
+    } else {
+        (0..old.len())
+            .map(Edit::Remove)
+            .chain((0..new.len()).map(Edit::Insert))
+            .collect()
+    };
+
+    // `index` is the position in the array as changed by the ops so far
+    let mut index = prefix;
+    let mut edits = edits.into_iter().peekable();
+    while let Some(edit) = edits.next() {
+        if edit == Edit::Keep {
+            index += 1;
+            continue;
+        }
+
+        // gather the run of changes between two kept elements
+        let mut removed = Vec::new();
+        let mut inserted = Vec::new();
+        let mut next = Some(edit);
+        while let Some(edit) = next.filter(|edit| *edit != Edit::Keep) {
+            match edit {
+                Edit::Remove(i) => removed.push(i),
+                Edit::Insert(j) => inserted.push(j),
+                Edit::Keep => unreachable!(),
+            }
+            next = edits.next_if(|edit| *edit != Edit::Keep);
+        }
+
+        // removals paired with insertions become replacements
+        let paired = removed.len().min(inserted.len());
+        for (&i, &j) in removed.iter().zip(&inserted) {
+            with_index(path, index, |path| diff_values(&old[i], &new[j], path, ops));
+            index += 1;
+        }
+        for _ in &removed[paired..] {
+            with_index(path, index, |path| {
+                ops.push(operation("remove", path, None))
+            });
+        }
+        for &j in &inserted[paired..] {
+            with_index(path, index, |path| {
+                ops.push(operation("add", path, Some(&new[j])))
+            });
+            index += 1;
+        }
+    }
+}
+
+/// Aligns two arrays by their longest common subsequence
+fn lcs_edits<K: MapKind>(old: &[Value<K>], new: &[Value<K>]) -> Vec<Edit> {
+    // lengths[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            edits.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            edits.push(Edit::Remove(i));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(j));
+            j += 1;
+        }
+    }
+    edits.extend((i..old.len()).map(Edit::Remove));
+    edits.extend((j..new.len()).map(Edit::Insert));
+    edits
+}
+
+/// Runs `f` with `path` extended by an object key
+fn with_key(path: &mut String, key: &str, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    for ch in key.chars() {
+        match ch {
+            '~' => path.push_str("~0"),
+            '/' => path.push_str("~1"),
+            ch => path.push(ch),
+        }
+    }
+    f(path);
+    path.truncate(len);
+}
+
+/// Runs `f` with `path` extended by an array index
+fn with_index(path: &mut String, index: usize, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&index.to_string());
+    f(path);
+    path.truncate(len);
+}
+
+fn operation<K: MapKind>(op: &str, path: &str, value: Option<&Value<K>>) -> Value<K> {
+    let mut map = K::Map::default();
+    map.insert("op".into(), Value::String(op.into()));
+    map.insert("path".into(), Value::String(path.into()));
+    if let Some(value) = value {
+        map.insert("value".into(), value.clone());
+    }
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use crate::{parse, OrderedMapKind, Value};
+
+    // objects keep their order so that the order of the ops is predictable
+    fn check(old: &str, new: &str, expected: &str) {
+        let old: Value<OrderedMapKind> = old.parse().unwrap();
+        let new: Value<OrderedMapKind> = new.parse().unwrap();
+        let expected: Value<OrderedMapKind> = expected.parse().unwrap();
+
+        assert_eq!(diff(&old, &new), expected);
+    }
+
+    #[test]
+    fn equal_documents() {
+        check(
+            r#"{"a": [1, {"b": null}]}"#,
+            r#"{"a": [1, {"b": null}]}"#,
+            "[]",
+        );
+    }
+
+    #[test]
+    fn replaces_root() {
+        check(
+            "1",
+            r#""one""#,
+            r#"[{"op": "replace", "path": "", "value": "one"}]"#,
+        );
+    }
+
+    #[test]
+    fn object_changes() {
+        check(
+            r#"{"keep": 1, "change": {"x": 1}, "drop": true}"#,
+            r#"{"keep": 1, "change": {"x": 2}, "new": [null]}"#,
+            r#"[
+                {"op": "replace", "path": "/change/x", "value": 2},
+                {"op": "remove", "path": "/drop"},
+                {"op": "add", "path": "/new", "value": [null]}
+            ]"#,
+        );
+    }
+
+    #[test]
+    fn escapes_keys() {
+        check(
+            r#"{"a/b": 1, "c~d": 2}"#,
+            r#"{"a/b": 3, "c~d": 2}"#,
+            r#"[{"op": "replace", "path": "/a~1b", "value": 3}]"#,
+        );
+    }
+
+    #[test]
+    fn array_insert_is_one_op() {
+        check(
+            "[1, 2, 3, 4]",
+            "[1, 2, 9, 3, 4]",
+            r#"[{"op": "add", "path": "/2", "value": 9}]"#,
+        );
+    }
+
+    #[test]
+    fn array_removals_shift_indices() {
+        check(
+            "[1, 2, 3, 4, 5]",
+            "[1, 3, 5]",
+            r#"[{"op": "remove", "path": "/1"}, {"op": "remove", "path": "/2"}]"#,
+        );
+    }
+
+    #[test]
+    fn array_replacement_recurses() {
+        check(
+            r#"[{"id": 1, "n": "a"}, 2, 3]"#,
+            r#"[{"id": 1, "n": "b"}, 2, 3, 4]"#,
+            r#"[
+                {"op": "replace", "path": "/0/n", "value": "b"},
+                {"op": "add", "path": "/3", "value": 4}
+            ]"#,
+        );
+    }
+
+    #[test]
+    fn array_mixed_changes() {
+        check(
+            "[1, 2, 3]",
+            "[0, 2, 4, 5]",
+            r#"[
+                {"op": "replace", "path": "/0", "value": 0},
+                {"op": "replace", "path": "/2", "value": 4},
+                {"op": "add", "path": "/3", "value": 5}
+            ]"#,
+        );
+    }
+
+    #[test]
+    fn type_change_replaces() {
+        let old: Value = Value::object([("a", Value::Array(vec![]))]);
+        let new: Value = Value::object([("a", Value::object([]))]);
+        let expected = parse(String::from(
+            r#"[{"op": "replace", "path": "/a", "value": {}}]"#,
+        ))
+        .unwrap();
+
+        assert_eq!(diff(&old, &new), expected);
+    }
+}
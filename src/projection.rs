@@ -0,0 +1,240 @@
+//! Parsing only the parts of a document that are needed, see
+//! [`parse_projection`]
+
+use std::fmt;
+
+use crate::borrowed::read_value;
+use crate::map::{DefaultMapKind, MapKind};
+use crate::pull::{Event, JsonReader};
+use crate::value::{pointer_index, split_pointer};
+use crate::{Error, InvalidPath, ParseOptions, TokenParseError, Value};
+
+/// The set of paths to keep when parsing, see [`Projection::parse`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    /// The tokens of each JSON Pointer, where `*` matches any key or index
+    patterns: Vec<Vec<String>>,
+}
+
+/// A projection's paths were invalid, or the input could not be parsed
+#[derive(Debug, PartialEq)]
+pub enum ProjectionError {
+    InvalidPath(InvalidPath),
+    Parse(Error),
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProjectionError::InvalidPath(_) => "invalid projection path",
+            ProjectionError::Parse(_) => "failed to parse input",
+        })
+    }
+}
+
+impl std::error::Error for ProjectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectionError::InvalidPath(err) => Some(err),
+            ProjectionError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<InvalidPath> for ProjectionError {
+    fn from(err: InvalidPath) -> Self {
+        Self::InvalidPath(err)
+    }
+}
+
+impl From<Error> for ProjectionError {
+    fn from(err: Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Parses only the parts of `input` at the given JSON Pointers, see
+/// [`Projection`]
+pub fn parse_projection(input: &str, pointers: &[&str]) -> Result<Value, ProjectionError> {
+    Ok(Projection::new(pointers)?.parse(input)?)
+}
+
+/// How the current path relates to the patterns
+enum Match {
+    /// A pattern matches the path or one of its ancestors
+    Whole,
+    /// The path leads towards a pattern
+    Partial,
+    None,
+}
+
+/// One step into the document
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl Projection {
+    /// Creates a projection keeping the values at the given JSON Pointers
+    /// (RFC 6901), where a `*` token matches any key or index, ex.
+    /// `/users/*/name`
+    pub fn new(pointers: &[&str]) -> Result<Self, InvalidPath> {
+        let patterns = pointers
+            .iter()
+            .map(|pointer| split_pointer(pointer))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Parses `input`, building a [`Value`] with only the values that the
+    /// projection selects, and the objects and arrays that lead to them.
+    ///
+    /// Everything else is skipped without being decoded. Elements of an array
+    /// that don't lead to a selected value are left out, so the indices of
+    /// the remaining elements can change. When the root itself isn't an
+    /// object or array and isn't selected, the result is `null`.
+    ///
+    /// Skipped values are only checked for closed strings and balanced
+    /// brackets, as with [`JsonReader::skip_value`].
+    pub fn parse(&self, input: &str) -> Result<Value, Error> {
+        let mut reader = JsonReader::new(input, ParseOptions::default());
+        let value = self.project(&mut reader, &mut Vec::new())?;
+        Ok(value.unwrap_or_default())
+    }
+
+    /// Reads the value at `path`, or skips it and returns `None` when it
+    /// isn't part of the projection
+    fn project(
+        &self,
+        reader: &mut JsonReader,
+        path: &mut Vec<Step>,
+    ) -> Result<Option<Value>, Error> {
+        match self.match_path(path) {
+            Match::Whole => {
+                let event = reader.next_event()?.ok_or(TokenParseError::EarlyEOF)?;
+                return Ok(Some(read_value(reader, event)?.to_value()));
+            }
+            Match::None => {
+                reader.skip_value()?;
+                return Ok(None);
+            }
+            Match::Partial => {}
+        }
+
+        match reader.next_event()? {
+            Some(Event::StartObject) => {
+                let mut map = <DefaultMapKind as MapKind>::Map::default();
+                while let Some(Event::Key(key)) = reader.next_event()? {
+                    path.push(Step::Key(key.to_string()));
+                    let value = self.project(reader, path)?;
+                    path.pop();
+                    if let Some(value) = value {
+                        map.insert(key.into(), value);
+                    }
+                }
+                Ok(Some(Value::Object(map)))
+            }
+            Some(Event::StartArray) => {
+                let mut array = Vec::new();
+                let mut index = 0;
+                while reader.next_element()? {
+                    path.push(Step::Index(index));
+                    array.extend(self.project(reader, path)?);
+                    path.pop();
+                    index += 1;
+                }
+                Ok(Some(Value::Array(array)))
+            }
+            // a value that the patterns would need to look inside of
+            Some(_) => Ok(None),
+            None => Err(TokenParseError::EarlyEOF.into()),
+        }
+    }
+
+    fn match_path(&self, path: &[Step]) -> Match {
+        let mut partial = false;
+        for pattern in &self.patterns {
+            let matches = pattern.iter().zip(path).all(|(token, step)| match step {
+                _ if token == "*" => true,
+                Step::Key(key) => token == key,
+                Step::Index(index) => pointer_index(token) == Some(*index),
+            });
+            if matches && pattern.len() <= path.len() {
+                return Match::Whole;
+            }
+            partial |= matches;
+        }
+        if partial {
+            Match::Partial
+        } else {
+            Match::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_projection, Projection, ProjectionError};
+    use crate::{parse, InvalidPath, Value};
+
+    fn check(input: &str, pointers: &[&str], expected: &str) {
+        let actual = parse_projection(input, pointers).unwrap();
+
+        assert_eq!(actual, parse(expected).unwrap());
+    }
+
+    const INPUT: &str = r#"{
+        "users": [
+            {"name": "ken", "email": "k@example.com", "roles": ["admin"]},
+            {"name": "ann", "email": "a@example.com"},
+            {"email": "x@example.com"}
+        ],
+        "meta": {"page": 1, "next": null},
+        "data": [[1, 2], {"big": "ignored"}]
+    }"#;
+
+    #[test]
+    fn keeps_selected_paths() {
+        check(
+            INPUT,
+            &["/users/*/name", "/meta"],
+            r#"{
+                "users": [{"name": "ken"}, {"name": "ann"}, {}],
+                "meta": {"page": 1, "next": null}
+            }"#,
+        );
+    }
+
+    #[test]
+    fn selects_indices() {
+        check(
+            INPUT,
+            &["/users/1/email", "/data/0"],
+            r#"{"users": [{"email": "a@example.com"}], "data": [[1, 2]]}"#,
+        );
+    }
+
+    #[test]
+    fn root_pointer_keeps_everything() {
+        check(INPUT, &[""], INPUT);
+    }
+
+    #[test]
+    fn nothing_selected() {
+        check(INPUT, &["/missing/*"], "{}");
+        check("[1, 2]", &["/0/a"], "[]");
+        assert_eq!(parse_projection("12", &["/a"]), Ok(Value::Null));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Projection::new(&["users"]),
+            Err(InvalidPath(String::from("users")))
+        );
+        assert!(matches!(
+            parse_projection(r#"{"a": [1, 2}"#, &["/a"]),
+            Err(ProjectionError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,558 @@
+//! Pull parsing, where the caller asks for each part of a document in turn
+//! with [`JsonReader::next_event`]
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::parse::unescape_string;
+use crate::tokenize::{trailing_error, Lexer, Token};
+use crate::{Error, Number, ParseOptions, RawValue, TokenParseError};
+
+/// A part of a document, see [`JsonReader::next_event`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+
+    /// An object key, which is followed by the events of its value
+    Key(Cow<'a, str>),
+
+    String(Cow<'a, str>),
+    Number(Number),
+    Boolean(bool),
+    Null,
+}
+
+/// What the reader expects to come next
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// A value, or the end of the array that was just opened
+    ArrayStart,
+    /// A key, or the end of the object that was just opened
+    ObjectStart,
+    /// A key, after a comma
+    Key,
+    /// A value, after a key or comma or at the start
+    Value,
+    /// A comma or the end of the innermost container
+    AfterValue,
+    /// The document was read completely, or an error was returned
+    Done,
+}
+
+/// Reads a document one [`Event`] at a time, as the caller asks for them.
+///
+/// Strings and keys borrow from the input unless they contain escapes. The
+/// input is checked as it's read, so a document that turns out to be invalid
+/// may already have produced events for its start.
+pub struct JsonReader<'a> {
+    lexer: Lexer<'a>,
+    state: State,
+    /// Whether each open container is an object, innermost last
+    open: Vec<bool>,
+    /// Byte offset of the start of the last event
+    offset: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    pub fn new(input: &'a str, options: ParseOptions) -> Self {
+        Self {
+            lexer: Lexer::new(input, options),
+            state: State::Value,
+            open: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Byte offset in the input where the last event returned by
+    /// [`next_event`](Self::next_event) starts, ex. of the quote that opens
+    /// a key
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of objects and arrays that are open at the current position
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Reads the next part of the document, or returns `None` once the
+    /// document is complete or after an error
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, Error> {
+        let result = self.read_event();
+        if result.is_err() {
+            self.state = State::Done;
+        }
+        result
+    }
+
+    /// Skips the value that would come next, including everything inside it
+    /// if it's an object or array, without producing its events. After a
+    /// [`Key`](Event::Key) event this skips the key's value, and within an
+    /// array it skips the next element.
+    ///
+    /// This is much cheaper than reading the events, but the skipped text is
+    /// only checked for closed strings and matching brackets, so some invalid
+    /// documents aren't detected. It's an error to call this where a value
+    /// isn't expected, such as right after the last value of an array.
+    pub fn skip_value(&mut self) -> Result<(), Error> {
+        self.skip().map(|_| ())
+    }
+
+    /// Within an array, checks whether another element follows, in which
+    /// case the next event starts it. At the end of the array this returns
+    /// `false` and consumes the [`EndArray`](Event::EndArray) event.
+    ///
+    /// This lets a caller decide whether to read or skip each element, since
+    /// [`skip_value`](Self::skip_value) can't be used once the event that
+    /// starts an element has been read.
+    pub fn next_element(&mut self) -> Result<bool, Error> {
+        let in_array = self.open.last() == Some(&false);
+        let has_next = match self.state {
+            State::ArrayStart => self.lexer.peek_char() != Some(']'),
+            State::AfterValue if in_array && self.lexer.peek_char() == Some(',') => {
+                let result = self.next_token().and_then(|_| self.trailing_comma());
+                if result.is_err() {
+                    self.state = State::Done;
+                }
+                if !result? {
+                    self.state = State::Value;
+                    return Ok(true);
+                }
+                // the end of the array is read as if it had just opened
+                self.state = State::ArrayStart;
+                false
+            }
+            State::AfterValue if in_array => false,
+            _ => {
+                self.state = State::Done;
+                return Err(self.expected_value());
+            }
+        };
+        if !has_next {
+            // reports the end of the array, or the error where it should be
+            self.next_event()?;
+        }
+        Ok(has_next)
+    }
+
+    /// Skips the value that would come next like [`skip_value`](Self::skip_value),
+    /// returning its text, or `None` once the document is complete
+    pub fn raw_value(&mut self) -> Result<Option<RawValue<'a>>, Error> {
+        let span = self.skip()?;
+        Ok(span.map(|span| RawValue::new_unchecked(&self.lexer.input()[span])))
+    }
+
+    /// Skips the next value, returning its span in the input
+    fn skip(&mut self) -> Result<Option<Range<usize>>, Error> {
+        let result = self.skip_span();
+        if result.is_err() {
+            self.state = State::Done;
+        }
+        result
+    }
+
+    fn skip_span(&mut self) -> Result<Option<Range<usize>>, Error> {
+        match self.state {
+            State::Value | State::ArrayStart => {}
+            State::AfterValue if self.open.last() == Some(&false) => {
+                if self.lexer.peek_char() != Some(',') {
+                    return Err(self.expected_value());
+                }
+                self.next_token()?;
+                if self.trailing_comma()? {
+                    return Err(self.expected_value());
+                }
+            }
+            State::Done => return Ok(None),
+            _ => return Err(self.expected_value()),
+        }
+        match self.lexer.peek_char() {
+            None => return Err(self.eof_error()),
+            Some(']' | '}' | ',' | ':') => return Err(self.expected_value()),
+            Some(_) => self.offset = self.lexer.skip_value()?,
+        }
+        self.finish_value();
+        Ok(Some(self.offset..self.lexer.offset()))
+    }
+
+    #[cfg(feature = "arena")]
+    pub(crate) fn input(&self) -> &'a str {
+        self.lexer.input()
+    }
+
+    /// Byte offset of the first thing other than whitespace after the
+    /// document, if there is one
+    pub(crate) fn trailing_content(&mut self) -> Option<usize> {
+        self.lexer.peek_char().map(|_| self.lexer.offset())
+    }
+
+    /// Fails when there is more than whitespace after the document, unless
+    /// [`ParseOptions::allow_trailing_content`] is set, in which case the
+    /// text after it isn't looked at
+    pub(crate) fn check_trailing_content(&mut self) -> Result<(), Error> {
+        if self.lexer.options.allow_trailing_content {
+            return Ok(());
+        }
+        match self.trailing_content() {
+            Some(offset) => Err(trailing_error(
+                self.lexer.input,
+                offset,
+                &self.lexer.options,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn read_event(&mut self) -> Result<Option<Event<'a>>, Error> {
+        loop {
+            let event = match self.state {
+                State::Done => return Ok(None),
+                State::Value | State::ArrayStart => {
+                    let token = self.next_token()?;
+                    if self.state == State::ArrayStart && token == Token::RightBracket {
+                        self.close()
+                    } else {
+                        self.value(token)?
+                    }
+                }
+                State::ObjectStart | State::Key => match self.next_token()? {
+                    Token::RightBrace if self.state == State::ObjectStart => self.close(),
+                    Token::String(key) => {
+                        let key = self.string(key)?;
+                        let start = self.offset;
+                        if self.next_token()? != Token::Colon {
+                            return Err(self.error(TokenParseError::ExpectedColon));
+                        }
+                        // a key's event starts at the key rather than the
+                        // colon after it
+                        self.offset = start;
+                        self.state = State::Value;
+                        Event::Key(key)
+                    }
+                    _ => return Err(self.error(TokenParseError::ExpectedProperty)),
+                },
+                State::AfterValue => {
+                    let in_object = *self.open.last().expect("a container is open");
+                    match (in_object, self.next_token()?) {
+                        (_, Token::Comma) if self.trailing_comma()? => {
+                            self.next_token()?;
+                            return Ok(Some(self.close()));
+                        }
+                        (true, Token::Comma) => self.state = State::Key,
+                        (false, Token::Comma) => self.state = State::Value,
+                        (true, Token::RightBrace) | (false, Token::RightBracket) => {
+                            return Ok(Some(self.close()));
+                        }
+                        _ => return Err(self.error(TokenParseError::ExpectedComma)),
+                    }
+                    continue;
+                }
+            };
+            return Ok(Some(event));
+        }
+    }
+
+    /// The event for a token that starts a value
+    fn value(&mut self, token: Token<'a>) -> Result<Event<'a>, Error> {
+        let event = match token {
+            Token::Null => Event::Null,
+            Token::False => Event::Boolean(false),
+            Token::True => Event::Boolean(true),
+            Token::Number(number) => Event::Number(number),
+            Token::String(string) => Event::String(self.string(string)?),
+            Token::LeftBracket => {
+                self.open.push(false);
+                self.state = State::ArrayStart;
+                return Ok(Event::StartArray);
+            }
+            Token::LeftBrace => {
+                self.open.push(true);
+                self.state = State::ObjectStart;
+                return Ok(Event::StartObject);
+            }
+            _ => return Err(self.error(TokenParseError::ExpectedValue)),
+        };
+        self.finish_value();
+        Ok(event)
+    }
+
+    /// After a comma, checks whether the innermost container ends instead
+    /// of having another element, which is only allowed with
+    /// [`ParseOptions::allow_trailing_commas`]
+    fn trailing_comma(&mut self) -> Result<bool, Error> {
+        let closing = match self.open.last() {
+            Some(true) => '}',
+            _ => ']',
+        };
+        if self.lexer.peek_char() != Some(closing) {
+            return Ok(false);
+        }
+        if !self.lexer.options.allow_trailing_commas {
+            return Err(self.error(TokenParseError::TrailingComma));
+        }
+        Ok(true)
+    }
+
+    /// Closes the innermost container
+    fn close(&mut self) -> Event<'a> {
+        let event = match self.open.pop() {
+            Some(true) => Event::EndObject,
+            _ => Event::EndArray,
+        };
+        self.finish_value();
+        event
+    }
+
+    fn finish_value(&mut self) {
+        self.state = if self.open.is_empty() {
+            State::Done
+        } else {
+            State::AfterValue
+        };
+    }
+
+    /// The next token, where the input ending is an error because a value or
+    /// the end of a container is still expected
+    fn next_token(&mut self) -> Result<Token<'a>, Error> {
+        let Some((token, offset)) = self.lexer.next_token()? else {
+            return Err(self.eof_error());
+        };
+        self.offset = offset;
+        Ok(token)
+    }
+
+    /// The error for the input ending, located at its end
+    fn eof_error(&self) -> Error {
+        let err = match self.open.last() {
+            None => TokenParseError::EmptyInput,
+            Some(false) => TokenParseError::UnclosedBracket,
+            Some(true) => TokenParseError::UnclosedBrace,
+        };
+        let input = self.lexer.input;
+        err.locate(input, || input.len())
+    }
+
+    /// Converts `err`, found at the token that was just read, locating it in
+    /// the input
+    fn error(&self, err: TokenParseError) -> Error {
+        err.locate(self.lexer.input, || self.offset)
+    }
+
+    /// The error for a value being skipped where one isn't expected, located
+    /// at the next token
+    fn expected_value(&mut self) -> Error {
+        self.lexer.peek_char();
+        TokenParseError::ExpectedValue.locate(self.lexer.input, || self.lexer.offset())
+    }
+
+    /// Unescapes the text of a string token that was just read, borrowing it
+    /// from the input when it has no escapes
+    fn string(&self, raw: Cow<'a, str>) -> Result<Cow<'a, str>, Error> {
+        let locate = |err| self.error(err);
+        match raw {
+            Cow::Borrowed(raw) => unescape_string(raw, &self.lexer.options).map_err(locate),
+            Cow::Owned(raw) => match unescape_string(&raw, &self.lexer.options) {
+                Ok(string) => Ok(Cow::Owned(string.into_owned())),
+                Err(err) => Err(locate(err)),
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for JsonReader<'a> {
+    type Item = Result<Event<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{Event, JsonReader};
+    use crate::{Error, ErrorKind, ParseOptions, TokenParseError, TokenizeError};
+
+    fn events(input: &str) -> Result<Vec<(usize, Event<'_>)>, Error> {
+        let mut reader = JsonReader::new(input, ParseOptions::default());
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event()? {
+            events.push((reader.offset(), event));
+        }
+        Ok(events)
+    }
+
+    #[test]
+    fn reads_events_with_offsets() {
+        let input = r#"{"a": [1, "é", true], "b": {}, "c": null}"#;
+
+        let expected = [
+            (0, Event::StartObject),
+            (1, Event::Key(Cow::Borrowed("a"))),
+            (6, Event::StartArray),
+            (7, Event::Number(1.into())),
+            (10, Event::String(Cow::Borrowed("é"))),
+            (16, Event::Boolean(true)),
+            (20, Event::EndArray),
+            (23, Event::Key(Cow::Borrowed("b"))),
+            (28, Event::StartObject),
+            (29, Event::EndObject),
+            (32, Event::Key(Cow::Borrowed("c"))),
+            (37, Event::Null),
+            (41, Event::EndObject),
+        ];
+        assert_eq!(events(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn borrows_strings_without_escapes() {
+        let events = events(r#"["plain", "esc\"aped"]"#).unwrap();
+
+        assert!(matches!(
+            &events[1].1,
+            Event::String(Cow::Borrowed("plain"))
+        ));
+        assert!(matches!(&events[2].1, Event::String(Cow::Owned(s)) if s == "esc\"aped"));
+    }
+
+    #[test]
+    fn empty_containers() {
+        let actual: Vec<_> = events("[[], {}]")
+            .unwrap()
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect();
+
+        let expected = [
+            Event::StartArray,
+            Event::StartArray,
+            Event::EndArray,
+            Event::StartObject,
+            Event::EndObject,
+            Event::EndArray,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn reads_on_demand() {
+        let mut reader = JsonReader::new("[1, @]", ParseOptions::default());
+
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(reader.depth(), 1);
+        assert_eq!(reader.next_event(), Ok(Some(Event::Number(1.into()))));
+        assert!(reader.next_event().is_err());
+        assert_eq!(reader.next_event(), Ok(None));
+    }
+
+    #[test]
+    fn errors() {
+        let check = |input, expected: TokenParseError, offset| {
+            let expected = expected.locate(input, || offset);
+            assert_eq!(events(input).unwrap_err(), expected, "{input}");
+        };
+
+        check("", TokenParseError::EmptyInput, 0);
+        check("[1,", TokenParseError::UnclosedBracket, 3);
+        check(r#"{"a": 1"#, TokenParseError::UnclosedBrace, 7);
+        check("[1 2]", TokenParseError::ExpectedComma, 3);
+        check(r#"{"a" 1}"#, TokenParseError::ExpectedColon, 5);
+        check("{,}", TokenParseError::ExpectedProperty, 1);
+        check("[1,]", TokenParseError::TrailingComma, 2);
+        check("[:]", TokenParseError::ExpectedValue, 1);
+    }
+
+    #[test]
+    fn truncated_input() {
+        let input = r#"{"a": [1, {"b": null}], "c": "d"}"#;
+        for end in 0..input.len() {
+            assert!(events(&input[..end]).is_err(), "{}", &input[..end]);
+        }
+        assert!(events(input).is_ok());
+    }
+
+    #[test]
+    fn skips_values() {
+        let input = r#"{"skip": {"a": ["]", "\"}", {}]}, "keep": [1, "x", [2]], "last": 3}"#;
+        let mut reader = JsonReader::new(input, ParseOptions::default());
+
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartObject)));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Key("skip".into()))));
+        reader.skip_value().unwrap();
+        assert_eq!(reader.offset(), 9);
+        assert_eq!(reader.next_event(), Ok(Some(Event::Key("keep".into()))));
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        reader.skip_value().unwrap();
+        reader.skip_value().unwrap();
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Number(2.into()))));
+        assert_eq!(reader.next_event(), Ok(Some(Event::EndArray)));
+        assert_eq!(reader.next_event(), Ok(Some(Event::EndArray)));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Key("last".into()))));
+        reader.skip_value().unwrap();
+        assert_eq!(reader.next_event(), Ok(Some(Event::EndObject)));
+        assert_eq!(reader.next_event(), Ok(None));
+    }
+
+    #[test]
+    fn skip_errors() {
+        let skip = |input| {
+            let mut reader = JsonReader::new(input, ParseOptions::default());
+            reader.next_event().unwrap();
+            reader.skip_value()
+        };
+
+        assert_eq!(
+            skip("[]"),
+            Err(TokenParseError::ExpectedValue.locate("[]", || 1))
+        );
+        assert_eq!(
+            skip("[[1, 2"),
+            Err(TokenizeError::UnexpectedEof.locate("[[1, 2", 6))
+        );
+        assert_eq!(
+            skip(r#"["a"#),
+            Err(TokenizeError::UnclosedQuotes.locate(r#"["a"#, 1))
+        );
+        assert_eq!(
+            skip("["),
+            Err(TokenParseError::UnclosedBracket.locate("[", || 1))
+        );
+    }
+
+    #[test]
+    fn skip_mismatched_brackets() {
+        let skip = |input| JsonReader::new(input, ParseOptions::default()).skip_value();
+        let error = |kind, input, offset| Err(Error::new(kind).at(input, Some(offset)));
+
+        assert_eq!(skip("[}"), error(ErrorKind::UnclosedArray, "[}", 1));
+        assert_eq!(skip("{]"), error(ErrorKind::UnclosedObject, "{]", 1));
+        assert_eq!(skip("[1}"), error(ErrorKind::UnclosedArray, "[1}", 2));
+        let nested = r#"{"a": [{"b": 1]}]}"#;
+        assert_eq!(skip(nested), error(ErrorKind::UnclosedObject, nested, 14));
+        assert_eq!(skip(r#"[{"a": "}]"}]"#), Ok(()));
+    }
+
+    #[test]
+    fn steps_through_elements() {
+        let mut reader = JsonReader::new("[[], [1, 2]]", ParseOptions::default());
+
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(reader.next_element(), Ok(true));
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(reader.next_element(), Ok(false));
+        assert_eq!(reader.next_element(), Ok(true));
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartArray)));
+        assert_eq!(reader.next_element(), Ok(true));
+        reader.skip_value().unwrap();
+        assert_eq!(reader.next_element(), Ok(true));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Number(2.into()))));
+        assert_eq!(reader.next_element(), Ok(false));
+        assert_eq!(reader.next_element(), Ok(false));
+        assert_eq!(reader.next_event(), Ok(None));
+    }
+}
@@ -0,0 +1,173 @@
+//! Parsing input that the caller receives a piece at a time, see
+//! [`PushParser`]
+
+use crate::tokenize::ChunkTokenizer;
+use crate::{Error, ParseOptions, Value};
+
+/// Parses a document from chunks of UTF-8 bytes that are pushed to it as
+/// they arrive, such as reads from a non-blocking socket, rather than pulled
+/// from a reader.
+///
+/// A chunk can end anywhere, including partway through a token or a
+/// character, and the parser continues from there with the next chunk. Only
+/// the tokens read so far and the text of an unfinished token are kept, not
+/// the chunks themselves. Once an error has been returned the parser
+/// shouldn't be fed any further.
+pub struct PushParser {
+    tokenizer: ChunkTokenizer,
+}
+
+impl PushParser {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            tokenizer: ChunkTokenizer::new(options),
+        }
+    }
+
+    /// Adds the next chunk of input, returning an error as soon as the text
+    /// so far can't be tokenized
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if chunk.is_empty() {
+            // an empty chunk marks the end of the input for the tokenizer
+            return Ok(());
+        }
+        self.tokenizer.push(chunk)
+    }
+
+    /// Ends the input and parses the document
+    pub fn finish(mut self) -> Result<Value, Error> {
+        self.tokenizer.push(&[])?;
+        self.tokenizer.parse_document()
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::PushParser;
+    use crate::encoding::{Encoding, EncodingError};
+    use crate::{
+        parse, parse_with, ParseOptions, Progress, ProgressCallback, TokenParseError,
+        TokenizeError, Value,
+    };
+
+    #[test]
+    fn parses_chunks_split_anywhere() {
+        let input = r#"{"key": [1.25, "é 😀", true, null], "n": -12}"#;
+        let expected = parse(input).unwrap();
+
+        for size in 1..=input.len() {
+            let mut parser = PushParser::new();
+            for chunk in input.as_bytes().chunks(size) {
+                parser.feed(chunk).unwrap();
+                parser.feed(&[]).unwrap();
+            }
+            assert_eq!(parser.finish(), Ok(expected.clone()), "chunks of {size}");
+        }
+    }
+
+    #[test]
+    fn number_completed_by_finish() {
+        let mut parser = PushParser::new();
+        parser.feed(b"12").unwrap();
+        parser.feed(b"34").unwrap();
+
+        assert_eq!(parser.finish(), Ok(1234.into()));
+    }
+
+    #[test]
+    fn comments_split_across_chunks() {
+        let input = "// settings\n{\"a\": /* one */ 1}\n// end";
+        let options = ParseOptions::new().allow_comments(true);
+
+        for size in 1..=input.len() {
+            let mut parser = PushParser::with_options(options.clone());
+            for chunk in input.as_bytes().chunks(size) {
+                parser.feed(chunk).unwrap();
+            }
+            let expected = Value::object([("a", 1.into())]);
+            assert_eq!(parser.finish(), Ok(expected), "chunks of {size}");
+        }
+    }
+
+    #[test]
+    fn unquoted_keys_split_across_chunks() {
+        let input = "{first: 1, second : {true: null}}";
+        let options = ParseOptions::new().allow_unquoted_keys(true);
+        let expected = parse_with(input, &options).unwrap();
+
+        for size in 1..=input.len() {
+            let mut parser = PushParser::with_options(options.clone());
+            for chunk in input.as_bytes().chunks(size) {
+                parser.feed(chunk).unwrap();
+            }
+            assert_eq!(parser.finish(), Ok(expected.clone()), "chunks of {size}");
+        }
+    }
+
+    #[test]
+    fn reports_progress() {
+        let last = Arc::new(Mutex::new(None));
+        let sink = Arc::clone(&last);
+        let options = ParseOptions {
+            progress: Some(ProgressCallback::new(4, move |progress| {
+                *sink.lock().unwrap() = Some(progress)
+            })),
+            ..ParseOptions::default()
+        };
+        let mut parser = PushParser::with_options(options);
+        parser.feed(b"[\"a\", tr").unwrap();
+        assert_eq!(last.lock().unwrap().map(|progress| progress.bytes), Some(5));
+
+        parser.feed(b"ue]").unwrap();
+        parser.finish().unwrap();
+        let expected = Progress {
+            bytes: 11,
+            tokens: 5,
+            values: 3,
+        };
+        assert_eq!(*last.lock().unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn errors() {
+        let mut parser = PushParser::new();
+        assert_eq!(
+            parser.feed(b"[1, @"),
+            Err(TokenizeError::CharNotRecognized('@').locate("[1, @", 4))
+        );
+
+        let mut parser = PushParser::new();
+        parser.feed(b"\"abc\xFF").unwrap_err();
+        let mut parser = PushParser::new();
+        parser.feed(b"[\"\xC3").unwrap();
+        let expected = EncodingError {
+            encoding: Encoding::Utf8,
+            offset: 2,
+        };
+        assert_eq!(parser.finish(), Err(expected.into()));
+
+        let mut parser = PushParser::new();
+        parser.feed(b"\"unclosed").unwrap();
+        assert_eq!(
+            parser.finish(),
+            Err(TokenizeError::UnclosedQuotes.locate("\"unclosed", 0))
+        );
+
+        assert_eq!(
+            PushParser::new().finish(),
+            Err(TokenParseError::EmptyInput.locate("", || 0))
+        );
+    }
+}
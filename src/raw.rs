@@ -0,0 +1,128 @@
+//! Values kept as their source text, to be parsed later or passed on as they
+//! were written
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::pull::{Event, JsonReader};
+use crate::simd::is_whitespace;
+use crate::{parse, validate, Error, ParseOptions, Value};
+
+/// The exact source text of one JSON value, without the whitespace around it.
+///
+/// Writing a `RawValue` with [`Display`](fmt::Display) produces its text
+/// unchanged, so a value can be forwarded exactly as it was received, and
+/// [`parse`](Self::parse) turns it into a [`Value`] when it's needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawValue<'a> {
+    text: &'a str,
+}
+
+impl<'a> RawValue<'a> {
+    /// Checks that `text` is a single valid JSON value, which may have
+    /// whitespace around it
+    pub fn new(text: &'a str) -> Result<Self, Error> {
+        validate(text)?;
+        Ok(Self::new_unchecked(text.trim_matches(|ch: char| {
+            ch.is_ascii() && is_whitespace(ch as u8)
+        })))
+    }
+
+    pub(crate) fn new_unchecked(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    /// The source text of the value
+    pub fn get(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn parse(&self) -> Result<Value, Error> {
+        parse(self.text)
+    }
+
+    /// Splits an object into its keys and the raw text of their values,
+    /// without parsing the values, or returns `None` when this isn't an
+    /// object.
+    ///
+    /// This suits a proxy that needs to look at one field, and forward the
+    /// others exactly as they were.
+    pub fn entries(&self) -> Result<Option<Entries<'a>>, Error> {
+        let mut reader = JsonReader::new(self.text, ParseOptions::default());
+        if reader.next_event()? != Some(Event::StartObject) {
+            return Ok(None);
+        }
+        let mut entries = Vec::new();
+        while let Some(Event::Key(key)) = reader.next_event()? {
+            let value = reader.raw_value()?.expect("a value follows each key");
+            entries.push((key, value));
+        }
+        Ok(Some(entries))
+    }
+}
+
+/// Keys of an object and the text of their values, in source order
+type Entries<'a> = Vec<(Cow<'a, str>, RawValue<'a>)>;
+
+/// Writes the source text unchanged
+impl fmt::Display for RawValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawValue;
+    use crate::pull::{Event, JsonReader};
+    use crate::{ParseOptions, TokenParseError, Value};
+
+    #[test]
+    fn keeps_source_text() {
+        let raw = RawValue::new(" {\"a\" : [1.50, \"\\u0041\"]}\n").unwrap();
+
+        assert_eq!(raw.get(), "{\"a\" : [1.50, \"\\u0041\"]}");
+        assert_eq!(raw.to_string(), raw.get());
+        assert_eq!(
+            raw.parse().unwrap(),
+            Value::object([("a", Value::Array(vec![1.5.into(), Value::string("A")]))])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_text() {
+        assert!(RawValue::new("[1,]").is_err());
+        assert_eq!(
+            RawValue::new("1 2"),
+            Err(TokenParseError::UnexpectedTrailingTokens { offset: 2 }.locate("1 2", || 2))
+        );
+    }
+
+    #[test]
+    fn reads_raw_values() {
+        let input = r#"{"route": "users", "body": {"id": 7, "tags": ["x"]}}"#;
+        let mut reader = JsonReader::new(input, ParseOptions::default());
+
+        assert_eq!(reader.next_event(), Ok(Some(Event::StartObject)));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Key("route".into()))));
+        assert_eq!(reader.next_event(), Ok(Some(Event::String("users".into()))));
+        assert_eq!(reader.next_event(), Ok(Some(Event::Key("body".into()))));
+        let body = reader.raw_value().unwrap().unwrap();
+        assert_eq!(body.get(), r#"{"id": 7, "tags": ["x"]}"#);
+        assert_eq!(reader.next_event(), Ok(Some(Event::EndObject)));
+        assert_eq!(reader.raw_value(), Ok(None));
+    }
+
+    #[test]
+    fn splits_entries() {
+        let raw = RawValue::new(r#"{"id": 7, "payload": {"keep": [1,2]}}"#).unwrap();
+        let entries = raw.entries().unwrap().unwrap();
+
+        let texts: Vec<_> = entries
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.get()))
+            .collect();
+        assert_eq!(texts, [("id", "7"), ("payload", r#"{"keep": [1,2]}"#)]);
+        assert_eq!(RawValue::new("[]").unwrap().entries(), Ok(None));
+    }
+}
@@ -0,0 +1,587 @@
+//! Parsing that carries on after syntax errors, to find all of them with
+//! [`parse_collecting_errors`] or to build what's there of the value with
+//! [`parse_with_recovery`]
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::vec;
+
+use crate::map::{DefaultMapKind, MapKind};
+use crate::parse::{check_length, insert_entry, parse_string, unescape_string, TokenParseError};
+use crate::tokenize::{tokenize_recovering, Token};
+use crate::{Error, ErrorKind, ParseOptions, Value};
+
+/// Parses JSON text with the given options, but rather than stopping at the
+/// first error, carries on to report every error in the input at once, in
+/// the order found.
+///
+/// After a token that can't be read, tokenizing resumes at the next
+/// punctuation or whitespace, or after the end of a string. After a
+/// misplaced token, parsing resumes at the next element, treating a missing
+/// value as `null` and a missing comma as if it were there. A closing
+/// bracket that belongs to an outer container closes the ones inside it.
+pub fn parse_collecting_errors(input: &str, options: &ParseOptions) -> Result<Value, Vec<Error>> {
+    let (value, errors) = parse_with_recovery(input, options);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses JSON text with the given options, returning as much of the value as
+/// could be parsed together with the errors found, which are empty when the
+/// input is valid. Editors and formatters can use it to keep working on a
+/// document while it's being written.
+///
+/// Errors are recovered from as for [`parse_collecting_errors`]. A value that
+/// is missing or can't be parsed is `null`, an object entry without a
+/// usable key is left out, and unclosed arrays and objects end where the
+/// input does.
+pub fn parse_with_recovery(input: &str, options: &ParseOptions) -> (Value, Vec<Error>) {
+    Recovery::new(input, options).parse()
+}
+
+type Map = <DefaultMapKind as MapKind>::Map<Value>;
+
+/// A token that was read, or the error for one that couldn't be
+type Item<'a> = Result<(Token<'a>, Range<usize>), Error>;
+
+/// What the next token is, without borrowing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Next {
+    End,
+    Damaged,
+    Comma,
+    Colon,
+    String,
+    Open,
+    Close(u8),
+    Other,
+}
+
+/// The result of starting to read a value
+enum Step {
+    Value(Value),
+    /// An array or object was opened
+    Opened,
+    End,
+}
+
+/// An array or object that has been opened but not yet closed
+enum Frame {
+    Array(Vec<Value>),
+    Object {
+        map: Map,
+        /// Keys whose values have been gathered into an array by `CollectAll`
+        collected: HashSet<String>,
+        /// Key of the entry whose value is being parsed and the byte offset
+        /// where it starts, or `None` when the key is missing or damaged, in
+        /// which case the value is dropped
+        key: Option<(String, usize)>,
+    },
+}
+
+impl Frame {
+    fn closing(&self) -> u8 {
+        match self {
+            Frame::Array(_) => b']',
+            Frame::Object { .. } => b'}',
+        }
+    }
+
+    /// The error for a container that isn't closed
+    fn unclosed(&self) -> ErrorKind {
+        match self {
+            Frame::Array(_) => ErrorKind::UnclosedArray,
+            Frame::Object { .. } => ErrorKind::UnclosedObject,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Frame::Array(array) => Value::Array(array),
+            Frame::Object { map, .. } => Value::Object(map),
+        }
+    }
+}
+
+struct Recovery<'a, 'o> {
+    input: &'a str,
+    options: &'o ParseOptions,
+    tokens: Peekable<vec::IntoIter<Item<'a>>>,
+    errors: Vec<Error>,
+}
+
+impl<'a, 'o> Recovery<'a, 'o> {
+    fn new(input: &'a str, options: &'o ParseOptions) -> Self {
+        let tokens = tokenize_recovering(input, options);
+        Self {
+            input,
+            options,
+            tokens: tokens.into_iter().peekable(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Parses the whole input, returning the value with `null` in place of
+    /// anything that couldn't be parsed, and the errors found
+    fn parse(mut self) -> (Value, Vec<Error>) {
+        if self.tokens.peek().is_none() {
+            self.error(TokenParseError::EmptyInput, self.input.len());
+            return (Value::Null, self.errors);
+        }
+        let value = self.value();
+        let (next, offset) = self.peek();
+        if next != Next::End && !self.options.allow_trailing_content {
+            self.error(ErrorKind::TrailingContent, offset);
+        }
+        // nothing after a cancellation or too deep a nesting was read, so
+        // isn't an error
+        let stopped = self
+            .errors
+            .iter()
+            .position(|error| matches!(error.kind, ErrorKind::Cancelled | ErrorKind::TooDeep));
+        if let Some(index) = stopped {
+            self.errors.truncate(index + 1);
+        }
+        (value, self.errors)
+    }
+
+    /// Parses a value, keeping the arrays and objects that it's inside of on
+    /// a stack rather than recursing, like the main parser
+    fn value(&mut self) -> Value {
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            let mut value = match self.start_value(&mut stack) {
+                Step::Value(value) => Some(value),
+                Step::Opened => {
+                    let frame = stack.last_mut().expect("a container was opened");
+                    match self.peek().0 {
+                        Next::Close(closing) if closing == frame.closing() => {
+                            self.tokens.next();
+                            Some(stack.pop().expect("a container is open").into_value())
+                        }
+                        _ if matches!(frame, Frame::Object { .. }) && !self.key(frame) => None,
+                        _ => continue,
+                    }
+                }
+                Step::End => {
+                    if let Some(frame) = stack.last() {
+                        self.error(frame.unclosed(), self.input.len());
+                    }
+                    return self.unwind(stack, None);
+                }
+            };
+
+            // add the value to the container it's in, then find the next
+            // element or close the container
+            loop {
+                let (next, offset) = self.peek();
+                let closes_open = matches!(next, Next::Close(closing) if stack.iter().any(|open| open.closing() == closing));
+                let Some(frame) = stack.last_mut() else {
+                    return value.unwrap_or(Value::Null);
+                };
+                if let Some(value) = value.take() {
+                    self.add(frame, value);
+                }
+                match next {
+                    Next::Close(closing) if closing == frame.closing() => {
+                        self.tokens.next();
+                        value = Some(stack.pop().expect("a container is open").into_value());
+                    }
+                    // closes an outer container, so this one wasn't closed
+                    Next::Close(_) if closes_open => {
+                        let frame = stack.pop().expect("a container is open");
+                        self.error(frame.unclosed(), offset);
+                        value = Some(frame.into_value());
+                    }
+                    Next::Close(_) => {
+                        self.error(ErrorKind::ExpectedComma, offset);
+                        self.tokens.next();
+                    }
+                    Next::End => {
+                        self.error(frame.unclosed(), self.input.len());
+                        return self.unwind(stack, None);
+                    }
+                    // a token that couldn't be read where a comma should be
+                    Next::Damaged => self.skip_damaged(),
+                    // a colon is taken for a comma, since it can only be
+                    // misplaced here
+                    Next::Comma | Next::Colon => {
+                        if next == Next::Colon {
+                            self.error(ErrorKind::ExpectedComma, offset);
+                        }
+                        self.tokens.next();
+                        match self.peek() {
+                            (Next::Close(closing), _) if closing == frame.closing() => {
+                                if next == Next::Comma && !self.options.allow_trailing_commas {
+                                    self.error(ErrorKind::TrailingComma, offset);
+                                }
+                            }
+                            _ if matches!(frame, Frame::Object { .. }) && !self.key(frame) => {}
+                            _ => break,
+                        }
+                    }
+                    // the start of the next element, without a comma before it
+                    _ => {
+                        self.error(ErrorKind::ExpectedComma, offset);
+                        if !matches!(frame, Frame::Object { .. }) || self.key(frame) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads a scalar value, or opens an array or object. A value that's
+    /// missing is `null`, and the token found instead is left to be read as
+    /// punctuation.
+    fn start_value(&mut self, stack: &mut Vec<Frame>) -> Step {
+        let (next, offset) = self.peek();
+        match next {
+            Next::End => return Step::End,
+            Next::Damaged => {
+                self.skip_damaged();
+                return Step::Value(Value::Null);
+            }
+            Next::Comma | Next::Colon | Next::Close(_) => {
+                self.error(ErrorKind::ExpectedValue, offset);
+                return Step::Value(Value::Null);
+            }
+            Next::String | Next::Open | Next::Other => {}
+        }
+        let Some(Ok((token, span))) = self.tokens.next() else {
+            unreachable!("the next token was peeked");
+        };
+        Step::Value(match token {
+            Token::Null => Value::Null,
+            Token::False => Value::Boolean(false),
+            Token::True => Value::Boolean(true),
+            Token::Number(number) => Value::Number(number),
+            Token::String(raw) => match parse_string::<DefaultMapKind>(&raw, self.options) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.string_error(err, span.start);
+                    Value::Null
+                }
+            },
+            Token::LeftBracket => {
+                stack.push(Frame::Array(Vec::new()));
+                return Step::Opened;
+            }
+            Token::LeftBrace => {
+                stack.push(Frame::Object {
+                    map: Map::default(),
+                    collected: HashSet::new(),
+                    key: None,
+                });
+                return Step::Opened;
+            }
+            Token::RightBracket | Token::RightBrace | Token::Comma | Token::Colon => {
+                unreachable!("punctuation was peeked")
+            }
+        })
+    }
+
+    /// Reads the key and colon of an object entry into `frame`, returning
+    /// whether a value follows
+    fn key(&mut self, frame: &mut Frame) -> bool {
+        let Frame::Object { key, .. } = frame else {
+            return true;
+        };
+        *key = None;
+        let mut string_key = false;
+        loop {
+            let (next, offset) = self.peek();
+            match next {
+                // left for the container to close
+                Next::End | Next::Close(_) => return false,
+                Next::String => {
+                    let Some(Ok((Token::String(raw), _))) = self.tokens.next() else {
+                        unreachable!("a string was peeked");
+                    };
+                    string_key = true;
+                    let unescaped = unescape_string(&raw, self.options)
+                        .and_then(|key| check_length(&key, self.options).map(|()| key));
+                    match unescaped {
+                        Ok(unescaped) => *key = Some((unescaped.into_owned(), offset)),
+                        Err(err) => self.string_error(err, offset),
+                    }
+                }
+                Next::Damaged => self.skip_damaged(),
+                // an extra comma
+                Next::Comma => {
+                    self.error(ErrorKind::ExpectedKey, offset);
+                    self.tokens.next();
+                    continue;
+                }
+                // a missing key, before its colon
+                Next::Colon => self.error(ErrorKind::ExpectedKey, offset),
+                // a value without a key
+                Next::Open => {
+                    self.error(ErrorKind::ExpectedKey, offset);
+                    return true;
+                }
+                Next::Other => {
+                    self.error(ErrorKind::ExpectedKey, offset);
+                    self.tokens.next();
+                }
+            }
+            break;
+        }
+
+        let (next, offset) = self.peek();
+        match next {
+            Next::Colon => {
+                self.tokens.next();
+                true
+            }
+            _ => {
+                // a key that isn't a string has already been reported
+                if string_key {
+                    self.error(ErrorKind::ExpectedColon, offset);
+                }
+                // only a value that's there is read, without the colon
+                let ends_entry = matches!(next, Next::End | Next::Comma | Next::Close(_));
+                if ends_entry {
+                    *key = None;
+                }
+                !ends_entry
+            }
+        }
+    }
+
+    fn add(&mut self, frame: &mut Frame, value: Value) {
+        match frame {
+            Frame::Array(array) => array.push(value),
+            Frame::Object {
+                map,
+                collected,
+                key,
+            } => {
+                let Some((key, offset)) = key.take() else {
+                    return;
+                };
+                let inserted = insert_entry::<DefaultMapKind>(
+                    map,
+                    collected,
+                    Cow::Owned(key),
+                    value,
+                    self.options,
+                );
+                if let Err(err) = inserted {
+                    self.error(err, offset);
+                }
+            }
+        }
+    }
+
+    /// Closes every open container once the input has ended
+    fn unwind(&mut self, mut stack: Vec<Frame>, mut value: Option<Value>) -> Value {
+        while let Some(mut frame) = stack.pop() {
+            if let Some(value) = value.take() {
+                self.add(&mut frame, value);
+            }
+            value = Some(frame.into_value());
+        }
+        value.unwrap_or(Value::Null)
+    }
+
+    /// What the next token is and the byte offset where it starts
+    fn peek(&mut self) -> (Next, usize) {
+        let end = self.input.len();
+        let Some(item) = self.tokens.peek() else {
+            return (Next::End, end);
+        };
+        let (token, span) = match item {
+            Ok((token, span)) => (token, span),
+            Err(error) => {
+                let offset = error.location.map_or(end, |location| location.offset);
+                return (Next::Damaged, offset);
+            }
+        };
+        let next = match token {
+            Token::Comma => Next::Comma,
+            Token::Colon => Next::Colon,
+            Token::String(_) => Next::String,
+            Token::LeftBracket | Token::LeftBrace => Next::Open,
+            Token::RightBracket => Next::Close(b']'),
+            Token::RightBrace => Next::Close(b'}'),
+            Token::Null | Token::False | Token::True | Token::Number(_) => Next::Other,
+        };
+        (next, span.start)
+    }
+
+    /// Reports the error for the next token, which couldn't be read
+    fn skip_damaged(&mut self) {
+        if let Some(Err(error)) = self.tokens.next() {
+            self.errors.push(error);
+        }
+    }
+
+    fn error(&mut self, error: impl Into<Error>, offset: usize) {
+        let error = error.into().at(self.input, Some(offset));
+        self.errors.push(error);
+    }
+
+    /// Records an error in the string that starts at `offset`, where an
+    /// escape is located within it
+    fn string_error(&mut self, err: TokenParseError, offset: usize) {
+        self.errors.push(err.locate(self.input, || offset));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_collecting_errors, parse_with_recovery};
+    use crate::{parse_with, CancelToken, ErrorKind, ParseOptions, Value};
+
+    /// The kind and byte offset of each error found in `input`
+    fn errors(input: &str) -> Vec<(ErrorKind, usize)> {
+        parse_collecting_errors(input, &ParseOptions::default())
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.kind, error.location.unwrap().offset))
+            .collect()
+    }
+
+    #[test]
+    fn valid_input_parses() {
+        let options = ParseOptions::default();
+        let input = r#"{"a": [1, {"b": null}], "c": "d"}"#;
+        assert_eq!(
+            parse_collecting_errors(input, &options),
+            parse_with(input, &options).map_err(|error| vec![error])
+        );
+    }
+
+    #[test]
+    fn finds_every_error() {
+        let input = "{\n  \"a\": [1, @, 3],\n  \"b\": tru,\n  \"c\" 4,\n  \"d\": [1 2],\n}";
+        assert_eq!(
+            errors(input),
+            [
+                (ErrorKind::UnexpectedCharacter('@'), 13),
+                (ErrorKind::InvalidLiteral, 27),
+                (ErrorKind::ExpectedColon, 38),
+                (ErrorKind::ExpectedComma, 51),
+                (ErrorKind::TrailingComma, 53),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_in_strings() {
+        let input = "[\"a\\qb\", \"ok\" \"x\",\n \"unclosed],\n \"\\u12\"]";
+        assert_eq!(
+            errors(input),
+            [
+                (
+                    ErrorKind::InvalidEscape {
+                        escape: 'q',
+                        offset: 3
+                    },
+                    3
+                ),
+                (ErrorKind::ExpectedComma, 14),
+                (ErrorKind::ControlCharacter('\n'), 31),
+                (ErrorKind::InvalidUnicodeEscape, 34),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_parts_of_entries() {
+        let input = r#"{"a": , : 1, "b", 2: 3, [4], "c": 5}"#;
+        assert_eq!(
+            errors(input),
+            [
+                (ErrorKind::ExpectedValue, 6),
+                (ErrorKind::ExpectedKey, 8),
+                (ErrorKind::ExpectedColon, 16),
+                (ErrorKind::ExpectedKey, 18),
+                (ErrorKind::ExpectedKey, 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_containers() {
+        assert_eq!(
+            errors(r#"{"a": [1, {"b": 2]"#),
+            [
+                (ErrorKind::UnclosedObject, 17),
+                (ErrorKind::UnclosedObject, 18),
+            ]
+        );
+        assert_eq!(errors("[1, [2,"), [(ErrorKind::UnclosedArray, 7)]);
+        assert_eq!(errors("[1]]"), [(ErrorKind::TrailingContent, 3)]);
+    }
+
+    #[test]
+    fn misplaced_colons() {
+        assert_eq!(
+            errors("[1: 2, :]"),
+            [
+                (ErrorKind::ExpectedComma, 2),
+                (ErrorKind::ExpectedValue, 7),
+                (ErrorKind::ExpectedComma, 7),
+            ]
+        );
+        assert_eq!(
+            errors(r#"{"a": 1: 2}"#),
+            [(ErrorKind::ExpectedComma, 7), (ErrorKind::ExpectedKey, 9)]
+        );
+    }
+
+    #[test]
+    fn partial_values() {
+        let options = ParseOptions::default();
+        let input = r#"{"a": [1, @, 3], "b": {"c": tru, "d" 4}, "e": [5, "#;
+        let (value, errors) = parse_with_recovery(input, &options);
+
+        let expected = Value::object([
+            ("a", Value::Array(vec![1.into(), Value::Null, 3.into()])),
+            ("b", Value::object([("c", Value::Null), ("d", 4.into())])),
+            ("e", Value::Array(vec![5.into()])),
+        ]);
+        assert_eq!(value, expected);
+        let kinds: Vec<_> = errors.into_iter().map(|error| error.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ErrorKind::UnexpectedCharacter('@'),
+                ErrorKind::InvalidLiteral,
+                ErrorKind::ExpectedColon,
+                ErrorKind::UnclosedArray,
+            ]
+        );
+
+        let (value, errors) = parse_with_recovery(r#"{"a": 1, 2: "b", "c": }"#, &options);
+        assert_eq!(value, Value::object([("a", 1.into()), ("c", Value::Null)]));
+        assert_eq!(errors.len(), 2);
+
+        assert_eq!(
+            parse_with_recovery("[1]", &options),
+            (Value::Array(vec![1.into()]), vec![])
+        );
+    }
+
+    #[test]
+    fn empty_and_cancelled_input() {
+        let result = parse_collecting_errors(" ", &ParseOptions::default());
+        let kinds: Vec<_> = result.unwrap_err().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ErrorKind::EmptyInput]);
+
+        let token = CancelToken::new();
+        token.cancel();
+        let options = ParseOptions::new().cancel(token);
+        let result = parse_collecting_errors("[true, false]", &options);
+        let kinds: Vec<_> = result.unwrap_err().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ErrorKind::Cancelled]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Parsing hand-written JSON with common mistakes in it, see [`parse_lenient`]
+
+use crate::parse::parse_document;
+use crate::tokenize::{tokenize_spanned, trailing_offset, SpannedToken, Token};
+use crate::{Error, ParseOptions, Value};
+
+/// A mistake in the input that [`parse_lenient`] fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repair {
+    pub kind: RepairKind,
+
+    /// Byte offset in the input of the mistake. For a missing comma, this is
+    /// just after the value that should have been followed by one.
+    pub offset: usize,
+}
+
+/// One of the mistakes that [`parse_lenient`] fixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// A string in single quotes rather than double quotes
+    SingleQuotes,
+
+    /// An object key that isn't quoted at all
+    UnquotedKey,
+
+    /// A comma after the last element of an array or object
+    TrailingComma,
+
+    /// No comma between two elements on different lines
+    MissingComma,
+}
+
+/// Parses JSON text that may have been written by hand, fixing common
+/// mistakes and listing what was fixed, in the order found in the input.
+///
+/// Single-quoted strings, unquoted keys and trailing commas are accepted, as
+/// are elements of an array or object that are missing the comma between
+/// them as long as they're on separate lines. Anything else that isn't valid
+/// JSON is still an error.
+pub fn parse_lenient(input: &str) -> Result<(Value, Vec<Repair>), Error> {
+    parse_lenient_with(input, &ParseOptions::default())
+}
+
+/// Parses JSON text like [`parse_lenient`], with the given options. The
+/// options for the repaired mistakes are turned on regardless.
+pub fn parse_lenient_with(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Value, Vec<Repair>), Error> {
+    let options = options
+        .clone()
+        .allow_single_quotes(true)
+        .allow_unquoted_keys(true);
+
+    let mut repairs = Vec::new();
+    let mut tokens: Vec<Token> = Vec::new();
+    // where each token starts, with added commas at the end of the value
+    // before them
+    let mut offsets = Vec::new();
+    let mut previous_end = 0;
+    let mut depth = 0usize;
+    let spans = tokenize_spanned(input, &options)?;
+    let mut spans = spans.into_iter().peekable();
+    while let Some(SpannedToken { token, span, .. }) = spans.next() {
+        match &token {
+            Token::String(_) if input.as_bytes()[span.start] == b'\'' => {
+                repairs.push(Repair {
+                    kind: RepairKind::SingleQuotes,
+                    offset: span.start,
+                });
+            }
+            Token::String(_) if input.as_bytes()[span.start] != b'"' => {
+                repairs.push(Repair {
+                    kind: RepairKind::UnquotedKey,
+                    offset: span.start,
+                });
+            }
+            Token::Comma
+                if tokens.last().is_some_and(ends_value)
+                    && matches!(
+                        spans.peek(),
+                        Some(SpannedToken {
+                            token: Token::RightBracket | Token::RightBrace,
+                            ..
+                        })
+                    ) =>
+            {
+                repairs.push(Repair {
+                    kind: RepairKind::TrailingComma,
+                    offset: span.start,
+                });
+                previous_end = span.end;
+                continue;
+            }
+            _ => {}
+        }
+
+        let missing_comma = depth > 0
+            && tokens.last().is_some_and(ends_value)
+            && starts_value(&token)
+            && input[previous_end..span.start].contains('\n');
+        if missing_comma {
+            repairs.push(Repair {
+                kind: RepairKind::MissingComma,
+                offset: previous_end,
+            });
+            tokens.push(Token::Comma);
+            offsets.push(previous_end);
+        }
+
+        match token {
+            Token::LeftBrace | Token::LeftBracket => depth += 1,
+            Token::RightBrace | Token::RightBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        previous_end = span.end;
+        tokens.push(token);
+        offsets.push(span.start);
+    }
+
+    let value = parse_document(&tokens[..], &options, |_, _| {
+        trailing_offset(input, &options)
+    })
+    .map_err(|(err, index)| {
+        err.locate(input, || offsets.get(index).copied().unwrap_or(input.len()))
+    })?;
+    Ok((value, repairs))
+}
+
+fn ends_value(token: &Token) -> bool {
+    !matches!(
+        token,
+        Token::LeftBrace | Token::LeftBracket | Token::Comma | Token::Colon
+    )
+}
+
+fn starts_value(token: &Token) -> bool {
+    !matches!(
+        token,
+        Token::RightBrace | Token::RightBracket | Token::Comma | Token::Colon
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_lenient, Repair, RepairKind};
+    use crate::{parse, TokenParseError, Value};
+
+    fn repair(kind: RepairKind, offset: usize) -> Repair {
+        Repair { kind, offset }
+    }
+
+    #[test]
+    fn valid_json_needs_no_repairs() {
+        let input = r#"{"a": [1, "two", null], "b": {"c": true}}"#;
+        assert_eq!(parse_lenient(input), Ok((parse(input).unwrap(), vec![])));
+    }
+
+    #[test]
+    fn repairs_mistakes() {
+        let input = "{\n  name: 'Ferris',\n  \"tags\": [\n    'crab',\n    \"rust\",\n  ]\n  \"age\": 8,\n}";
+        let expected = Value::object([
+            ("name", Value::string("Ferris")),
+            (
+                "tags",
+                Value::Array(vec![Value::string("crab"), Value::string("rust")]),
+            ),
+            ("age", Value::Number(8.into())),
+        ]);
+
+        let (value, repairs) = parse_lenient(input).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(
+            repairs,
+            vec![
+                repair(RepairKind::UnquotedKey, 4),
+                repair(RepairKind::SingleQuotes, 10),
+                repair(RepairKind::SingleQuotes, 36),
+                repair(RepairKind::TrailingComma, 54),
+                repair(RepairKind::MissingComma, 59),
+                repair(RepairKind::TrailingComma, 70),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_commas_on_one_line_are_errors() {
+        assert_eq!(
+            parse_lenient("[1 2]"),
+            Err(TokenParseError::ExpectedComma.locate("[1 2]", || 3))
+        );
+        // separate documents aren't joined
+        assert!(parse_lenient("1\n2").is_err());
+
+        let (value, repairs) = parse_lenient("[\n  [1]\n  {}\n]").unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::Array(vec![Value::Number(1.into())]),
+                parse("{}").unwrap()
+            ])
+        );
+        assert_eq!(repairs, vec![repair(RepairKind::MissingComma, 7)]);
+    }
+}
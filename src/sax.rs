@@ -0,0 +1,235 @@
+//! Event-driven parsing that reports each part of a document to a
+//! [`JsonHandler`] as it's read, without building a [`Value`](crate::Value)
+
+use std::ops::ControlFlow;
+
+use crate::pull::{Event, JsonReader};
+use crate::{Error, Number, ParseOptions};
+
+/// Receives the parts of a document in source order, see [`parse_events`].
+///
+/// Every method does nothing by default, so a handler only implements the
+/// events it's interested in. Returning `ControlFlow::Break` stops parsing
+/// early, ex. once the fields that were needed have been found.
+pub trait JsonHandler {
+    fn start_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// An object key, which is followed by the events of its value
+    fn key(&mut self, _key: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn end_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn start_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn end_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn string(&mut self, _value: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn number(&mut self, _value: &Number) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn boolean(&mut self, _value: bool) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn null(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Parses `input` with the default options, calling `handler` for each part
+/// of the document
+pub fn parse_events(input: &str, handler: &mut impl JsonHandler) -> Result<(), Error> {
+    parse_events_with(input, &ParseOptions::default(), handler)
+}
+
+/// Parses `input` with the given options, calling `handler` for each part of
+/// the document.
+///
+/// Errors are found as parsing reaches them, so the handler may already have
+/// received events from the start of an invalid document. Duplicate keys are
+/// passed on as they appear, whatever the
+/// [`duplicate_keys`](ParseOptions::duplicate_keys) policy.
+pub fn parse_events_with(
+    input: &str,
+    options: &ParseOptions,
+    handler: &mut impl JsonHandler,
+) -> Result<(), Error> {
+    let mut reader = JsonReader::new(input, options.clone());
+    while let Some(event) = reader.next_event()? {
+        let flow = match event {
+            Event::StartObject => handler.start_object(),
+            Event::EndObject => handler.end_object(),
+            Event::StartArray => handler.start_array(),
+            Event::EndArray => handler.end_array(),
+            Event::Key(key) => handler.key(&key),
+            Event::String(value) => handler.string(&value),
+            Event::Number(value) => handler.number(&value),
+            Event::Boolean(value) => handler.boolean(value),
+            Event::Null => handler.null(),
+        };
+        if flow.is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use super::{parse_events, JsonHandler};
+    use crate::{Number, TokenParseError};
+
+    /// Records every event as a line of text
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl JsonHandler for Recorder {
+        fn start_object(&mut self) -> ControlFlow<()> {
+            self.0.push(String::from("{"));
+            ControlFlow::Continue(())
+        }
+
+        fn key(&mut self, key: &str) -> ControlFlow<()> {
+            self.0.push(format!("key {key}"));
+            ControlFlow::Continue(())
+        }
+
+        fn end_object(&mut self) -> ControlFlow<()> {
+            self.0.push(String::from("}"));
+            ControlFlow::Continue(())
+        }
+
+        fn start_array(&mut self) -> ControlFlow<()> {
+            self.0.push(String::from("["));
+            ControlFlow::Continue(())
+        }
+
+        fn end_array(&mut self) -> ControlFlow<()> {
+            self.0.push(String::from("]"));
+            ControlFlow::Continue(())
+        }
+
+        fn string(&mut self, value: &str) -> ControlFlow<()> {
+            self.0.push(format!("string {value}"));
+            ControlFlow::Continue(())
+        }
+
+        fn number(&mut self, value: &Number) -> ControlFlow<()> {
+            self.0.push(format!("number {value}"));
+            ControlFlow::Continue(())
+        }
+
+        fn boolean(&mut self, value: bool) -> ControlFlow<()> {
+            self.0.push(format!("boolean {value}"));
+            ControlFlow::Continue(())
+        }
+
+        fn null(&mut self) -> ControlFlow<()> {
+            self.0.push(String::from("null"));
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn check(input: &str, expected: &[&str]) {
+        let mut recorder = Recorder::default();
+        parse_events(input, &mut recorder).unwrap();
+
+        assert_eq!(recorder.0, expected);
+    }
+
+    /// Checks that `input` fails with `expected` at the byte `offset`
+    fn check_error(input: &str, expected: TokenParseError, offset: usize) {
+        let actual = parse_events(input, &mut Recorder::default());
+
+        assert_eq!(actual, Err(expected.locate(input, || offset)));
+    }
+
+    #[test]
+    fn reports_events_in_order() {
+        check(
+            r#"{"id": 1, "tags": ["a\nb", true, null], "empty": {}, "none": []}"#,
+            &[
+                "{",
+                "key id",
+                "number 1",
+                "key tags",
+                "[",
+                "string a\nb",
+                "boolean true",
+                "null",
+                "]",
+                "key empty",
+                "{",
+                "}",
+                "key none",
+                "[",
+                "]",
+                "}",
+            ],
+        );
+    }
+
+    #[test]
+    fn scalar_document() {
+        check(r#""only""#, &["string only"]);
+    }
+
+    #[test]
+    fn stops_early() {
+        /// Finds the value of the first `name` key
+        #[derive(Default)]
+        struct FindName {
+            in_name: bool,
+            name: Option<String>,
+        }
+
+        impl JsonHandler for FindName {
+            fn key(&mut self, key: &str) -> ControlFlow<()> {
+                self.in_name = key == "name";
+                ControlFlow::Continue(())
+            }
+
+            fn string(&mut self, value: &str) -> ControlFlow<()> {
+                if self.in_name {
+                    self.name = Some(value.to_string());
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            }
+        }
+
+        // the invalid text after the name is never reached
+        let mut handler = FindName::default();
+        parse_events(r#"{"id": "x", "name": "ken", @"#, &mut handler).unwrap();
+
+        assert_eq!(handler.name.as_deref(), Some("ken"));
+    }
+
+    #[test]
+    fn errors() {
+        check_error("", TokenParseError::EmptyInput, 0);
+        check_error("[1, 2", TokenParseError::UnclosedBracket, 5);
+        check_error(r#"{"a": 1"#, TokenParseError::UnclosedBrace, 7);
+        check_error("[1 2]", TokenParseError::ExpectedComma, 3);
+        check_error("[1,]", TokenParseError::TrailingComma, 2);
+        check_error(r#"{"a" 1}"#, TokenParseError::ExpectedColon, 5);
+        check_error(r#"{"a": 1, 2: 3}"#, TokenParseError::ExpectedProperty, 9);
+        check_error("[1}", TokenParseError::ExpectedComma, 2);
+    }
+}
@@ -0,0 +1,255 @@
+//! JSON Schema inference from sample documents
+
+use crate::map::{MapKind, ObjectMap, OrderedMap};
+use crate::Value;
+
+/// The JSON Schema dialect of the inferred schemas
+pub const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Produces a JSON Schema describing the structure of `value`.
+///
+/// Every value gets a `type`: `null`, `boolean`, `integer` (numbers without a
+/// fractional part), `number`, `string`, `array` or `object`. Objects list
+/// their `properties` and the `required` keys; arrays describe their elements
+/// with a single `items` schema.
+///
+/// The elements of an array are merged into one schema, so that an array of
+/// records describes every key that appears in any record, and a key is only
+/// `required` when every record has it. When values of several types are
+/// seen in one place, `type` lists all of them, ex. `["null", "string"]`,
+/// and an `integer` seen alongside a `number` is widened to `number`.
+pub fn infer<K: MapKind>(value: &Value<K>) -> Value<K> {
+    let mut map = K::Map::default();
+    map.insert("$schema".into(), Value::String(DRAFT.into()));
+    for (key, value) in Shape::of(value).into_schema::<K>() {
+        map.insert(key, value);
+    }
+    Value::Object(map)
+}
+
+/// The types and structure seen at one place in the document
+#[derive(Default)]
+pub(crate) struct Shape {
+    pub(crate) null: bool,
+    pub(crate) boolean: bool,
+    pub(crate) integer: bool,
+    pub(crate) number: bool,
+    pub(crate) string: bool,
+    /// The merged shape of all elements, with `None` when every array seen
+    /// was empty
+    pub(crate) array: Option<Option<Box<Shape>>>,
+    pub(crate) object: Option<ObjectShape>,
+}
+
+pub(crate) struct ObjectShape {
+    pub(crate) properties: OrderedMap<String, Shape>,
+    /// Parallel to `properties`, whether every object seen had the key
+    pub(crate) required: OrderedMap<String, bool>,
+}
+
+impl Shape {
+    pub(crate) fn of<K: MapKind>(value: &Value<K>) -> Shape {
+        let mut shape = Shape::default();
+        match value {
+            Value::Null => shape.null = true,
+            Value::Boolean(_) => shape.boolean = true,
+            Value::Number(n) if n.is_integer() => shape.integer = true,
+            Value::Number(_) => shape.number = true,
+            Value::String(_) => shape.string = true,
+            Value::Array(array) => {
+                let items = array.iter().map(Shape::of).reduce(Shape::merge);
+                shape.array = Some(items.map(Box::new));
+            }
+            Value::Object(map) => {
+                let properties: OrderedMap<String, Shape> = map
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), Shape::of(value)))
+                    .collect();
+                let required = properties.keys().map(|key| (key.clone(), true)).collect();
+                shape.object = Some(ObjectShape {
+                    properties,
+                    required,
+                });
+            }
+        }
+        shape
+    }
+
+    fn merge(mut self, other: Shape) -> Shape {
+        self.null |= other.null;
+        self.boolean |= other.boolean;
+        self.integer |= other.integer;
+        self.number |= other.number;
+        self.string |= other.string;
+
+        self.array = match (self.array, other.array) {
+            (Some(Some(a)), Some(Some(b))) => Some(Some(Box::new(a.merge(*b)))),
+            (Some(a), Some(b)) => Some(a.or(b)),
+            (a, b) => a.or(b),
+        };
+
+        self.object = match (self.object, other.object) {
+            (Some(mut a), Some(b)) => {
+                for (key, required) in a.required.iter_mut() {
+                    *required &= b.properties.contains_key(key);
+                }
+                for (key, shape) in b.properties {
+                    match a.properties.remove(&key) {
+                        Some(existing) => {
+                            a.properties.insert(key, existing.merge(shape));
+                        }
+                        None => {
+                            a.required.insert(key.clone(), false);
+                            a.properties.insert(key, shape);
+                        }
+                    }
+                }
+                Some(a)
+            }
+            (a, b) => a.or(b),
+        };
+
+        self
+    }
+
+    fn into_schema<K: MapKind>(self) -> K::Map<Value<K>> {
+        let mut types = Vec::new();
+        if self.null {
+            types.push("null");
+        }
+        if self.boolean {
+            types.push("boolean");
+        }
+        // an integer is also a number, so only the wider type is needed
+        if self.number {
+            types.push("number");
+        } else if self.integer {
+            types.push("integer");
+        }
+        if self.string {
+            types.push("string");
+        }
+        if self.array.is_some() {
+            types.push("array");
+        }
+        if self.object.is_some() {
+            types.push("object");
+        }
+
+        let mut schema = K::Map::default();
+        let type_value = match types.as_slice() {
+            [single] => Value::String((*single).into()),
+            types => Value::Array(types.iter().map(|t| Value::String((*t).into())).collect()),
+        };
+        schema.insert("type".into(), type_value);
+
+        if let Some(Some(items)) = self.array {
+            schema.insert("items".into(), Value::Object(items.into_schema::<K>()));
+        }
+        if let Some(object) = self.object {
+            let required = object
+                .required
+                .iter()
+                .filter(|(_, required)| **required)
+                .map(|(key, _)| Value::String(key.as_str().into()))
+                .collect();
+            let properties = object
+                .properties
+                .into_iter()
+                .map(|(key, shape)| (key.into(), Value::Object(shape.into_schema::<K>())))
+                .collect();
+            schema.insert("properties".into(), Value::Object(properties));
+            schema.insert("required".into(), Value::Array(required));
+        }
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::infer;
+    use crate::{OrderedMapKind, Value};
+
+    fn check(sample: &str, expected: &str) {
+        let sample: Value<OrderedMapKind> = sample.parse().unwrap();
+        let expected: Value<OrderedMapKind> = expected.parse().unwrap();
+
+        assert_eq!(infer(&sample).to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn infers_scalars() {
+        check(
+            "1",
+            r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": "integer"}"#,
+        );
+        check(
+            r#""a""#,
+            r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": "string"}"#,
+        );
+    }
+
+    #[test]
+    fn infers_objects() {
+        check(
+            r#"{"id": 1, "name": "ken", "tags": [], "score": 1.5}"#,
+            r#"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "name": {"type": "string"},
+                    "tags": {"type": "array"},
+                    "score": {"type": "number"}
+                },
+                "required": ["id", "name", "tags", "score"]
+            }"#,
+        );
+    }
+
+    #[test]
+    fn merges_array_elements() {
+        check(
+            r#"[
+                {"id": 1, "email": null, "nested": {"a": true}},
+                {"id": 2.5, "email": "a@example.com", "nested": {"b": [1]}, "extra": false}
+            ]"#,
+            r#"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "number"},
+                        "email": {"type": ["null", "string"]},
+                        "nested": {
+                            "type": "object",
+                            "properties": {
+                                "a": {"type": "boolean"},
+                                "b": {"type": "array", "items": {"type": "integer"}}
+                            },
+                            "required": []
+                        },
+                        "extra": {"type": "boolean"}
+                    },
+                    "required": ["id", "email", "nested"]
+                }
+            }"#,
+        );
+    }
+
+    #[test]
+    fn mixed_types() {
+        check(
+            r#"[1, "a", [true], null]"#,
+            r#"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {
+                    "type": ["null", "integer", "string", "array"],
+                    "items": {"type": "boolean"}
+                }
+            }"#,
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::{self, Write};
+
+use crate::map::{MapKind, ObjectMap};
+use crate::{Number, Value};
+
+/// Writes the value as compact JSON text, ex. `{"key":[1,true]}`
+///
+/// Numbers that are not finite (`NaN` and infinities) have no JSON form and
+/// are written as `null`. An `F64` is always written with a fraction or an
+/// exponent, ex. `1.0` or `1e300`, so that it parses back as an `F64`.
+impl<K: MapKind> fmt::Display for Value<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::String(s) => write_string(f, s),
+            Value::Number(n) => write_number(f, n),
+            Value::Array(array) => {
+                f.write_char('[')?;
+                for (i, value) in array.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(map) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+pub(crate) fn write_number(f: &mut impl Write, n: &Number) -> fmt::Result {
+    match n {
+        Number::F64(n) if !n.is_finite() => f.write_str("null"),
+        Number::F64(n) => write_float(f, *n),
+        n => write!(f, "{n}"),
+    }
+}
+
+/// Writes a finite float so that it parses back as the same `F64`: always
+/// with a fraction or an exponent, since a whole number written as an
+/// integer would parse as one, and with an exponent when it's very large or
+/// small, where `f64`'s `Display` would write every digit
+fn write_float(f: &mut impl Write, n: f64) -> fmt::Result {
+    let magnitude = n.abs();
+    if magnitude != 0.0 && !(1e-5..1e16).contains(&magnitude) {
+        write!(f, "{n:e}")
+    } else if n.fract() == 0.0 {
+        write!(f, "{n:.1}")
+    } else {
+        write!(f, "{n}")
+    }
+}
+
+/// Writes the string surrounded by quotes, escaping the characters that JSON
+/// does not allow to appear directly
+pub(crate) fn write_string(f: &mut impl Write, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str(r#"\""#)?,
+            '\\' => f.write_str(r"\\")?,
+            '\n' => f.write_str(r"\n")?,
+            '\r' => f.write_str(r"\r")?,
+            '\t' => f.write_str(r"\t")?,
+            '\u{8}' => f.write_str(r"\b")?,
+            '\u{c}' => f.write_str(r"\f")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, parse_with, Number, ParseOptions, Value};
+
+    fn check(input: &str) {
+        let value = parse(String::from(input)).unwrap();
+        assert_eq!(value.to_string(), input);
+    }
+
+    #[test]
+    fn writes_literals() {
+        check("null");
+        check("true");
+        check("[false,-12,3.5]");
+    }
+
+    #[test]
+    fn writes_nested_containers() {
+        check(r#"[{"key":[[],{}]}]"#);
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let value: Value = Value::string("quote \" slash \\ newline \n bell \u{7}");
+        let expected = r#""quote \" slash \\ newline \n bell \u0007""#;
+
+        assert_eq!(value.to_string(), expected);
+    }
+
+    #[test]
+    fn non_finite_as_null() {
+        let value: Value = Value::Array(vec![
+            Value::Number(Number::F64(f64::NAN)),
+            Value::Number(Number::F64(f64::NEG_INFINITY)),
+        ]);
+
+        assert_eq!(value.to_string(), "[null,null]");
+    }
+
+    #[test]
+    fn keeps_numbers_beyond_f64() {
+        check("[1.5e400,-1e999]");
+    }
+
+    #[test]
+    fn round_trips_number_literals() {
+        let options = ParseOptions {
+            preserve_number_literals: true,
+            ..ParseOptions::default()
+        };
+        let input = "[1.0,-0,1.50,0.10,12345678901234567890123]";
+
+        let value = parse_with(String::from(input), &options).unwrap();
+        assert_eq!(value.to_string(), input);
+
+        let reparsed = parse_with(value.to_string(), &options).unwrap();
+        assert_eq!(reparsed.to_string(), input);
+    }
+
+    #[test]
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn normalizes_numbers_by_default() {
+        let value = parse(String::from("[1.0,1.50,2.5e5,-0.0,1E300,0.000001]")).unwrap();
+
+        assert_eq!(value.to_string(), "[1.0,1.5,250000.0,-0.0,1e300,1e-6]");
+    }
+
+    #[test]
+    fn writes_floats_as_floats() {
+        let floats = [
+            1.0,
+            -0.0,
+            2.5e5,
+            1e300,
+            -1.5e-7,
+            1e16,
+            0.1,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ];
+        for n in floats {
+            let value: Value = Value::Number(Number::F64(n));
+            let text = value.to_string();
+            assert!(text.contains(['.', 'e']), "{text}");
+            assert_eq!(parse(&text).unwrap(), value, "{text}");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Byte scanning used by the parsers, which checks 16 bytes at a time with
+//! SSE2 instructions when the `simd` feature is enabled on x86-64, and one
+//! byte at a time otherwise
+
+/// Whitespace between tokens, which RFC 8259 limits to space, tab, line
+/// feed and carriage return. Unlike `u8::is_ascii_whitespace`, form feed
+/// isn't.
+pub(crate) fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// A byte that ends a number or literal
+fn is_delimiter(byte: u8) -> bool {
+    is_whitespace(byte) || matches!(byte, b'{' | b'}' | b'[' | b']' | b':' | b',' | b'"')
+}
+
+/// Index of the first byte at or after `start` that isn't whitespace, or the
+/// length of `bytes` when there is none
+pub(crate) fn skip_whitespace(bytes: &[u8], start: usize) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let found = sse2::position(
+        bytes,
+        start,
+        |chunk| !sse2::whitespace(chunk),
+        |byte| !is_whitespace(byte),
+    );
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let found = position(bytes, start, |byte| !is_whitespace(byte));
+    found.unwrap_or(bytes.len())
+}
+
+/// Index of the first `"` or `\` at or after `start`, which is where a
+/// string ends or has an escape
+pub(crate) fn find_quote_or_backslash(bytes: &[u8], start: usize) -> Option<usize> {
+    let is_match = |byte| byte == b'"' || byte == b'\\';
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return sse2::position(bytes, start, sse2::quote_or_backslash, is_match);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    return position(bytes, start, is_match);
+}
+
+/// Index of the first whitespace, structural character or quote at or after
+/// `start`, or the length of `bytes` when there is none
+pub(crate) fn find_delimiter(bytes: &[u8], start: usize) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let found = sse2::position(bytes, start, sse2::delimiter, is_delimiter);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let found = position(bytes, start, is_delimiter);
+    found.unwrap_or(bytes.len())
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn position(bytes: &[u8], start: usize, is_match: impl Fn(u8) -> bool) -> Option<usize> {
+    let offset = bytes
+        .get(start..)?
+        .iter()
+        .position(|&byte| is_match(byte))?;
+    Some(start + offset)
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_or_si128, _mm_set1_epi8,
+    };
+
+    /// A mask with a bit set for each of the 16 bytes that matched
+    type Mask = u16;
+
+    /// Index of the first byte at or after `start` that matches, where
+    /// `simd` finds the matches in a chunk of 16 bytes and `scalar` checks
+    /// the bytes after the last full chunk
+    pub(super) fn position(
+        bytes: &[u8],
+        start: usize,
+        simd: impl Fn(__m128i) -> Mask,
+        scalar: impl Fn(u8) -> bool,
+    ) -> Option<usize> {
+        let mut index = start;
+        while index + 16 <= bytes.len() {
+            // SAFETY: the 16 bytes from `index` are within `bytes`, and the
+            // load doesn't need to be aligned
+            let chunk = unsafe { _mm_loadu_si128(bytes.as_ptr().add(index).cast()) };
+            let mask = simd(chunk);
+            if mask != 0 {
+                return Some(index + mask.trailing_zeros() as usize);
+            }
+            index += 16;
+        }
+        let offset = bytes.get(index..)?.iter().position(|&byte| scalar(byte))?;
+        Some(index + offset)
+    }
+
+    /// Bytes of `chunk` that equal any of `needles`
+    fn any_of(chunk: __m128i, needles: &[u8]) -> Mask {
+        // SAFETY: SSE2 is part of every x86-64 processor
+        unsafe {
+            let mut matches = _mm_cmpeq_epi8(chunk, _mm_set1_epi8(needles[0] as i8));
+            for &needle in &needles[1..] {
+                let needle = _mm_cmpeq_epi8(chunk, _mm_set1_epi8(needle as i8));
+                matches = _mm_or_si128(matches, needle);
+            }
+            _mm_movemask_epi8(matches) as Mask
+        }
+    }
+
+    pub(super) fn whitespace(chunk: __m128i) -> Mask {
+        any_of(chunk, b" \t\n\r")
+    }
+
+    pub(super) fn quote_or_backslash(chunk: __m128i) -> Mask {
+        any_of(chunk, b"\"\\")
+    }
+
+    pub(super) fn delimiter(chunk: __m128i) -> Mask {
+        whitespace(chunk) | any_of(chunk, b"{}[]:,\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_delimiter, find_quote_or_backslash, skip_whitespace};
+
+    #[test]
+    fn scans_across_chunks() {
+        // long enough for full chunks and a partial one at the end
+        let spaces = " \t\n\r".repeat(9);
+        let input = format!("{spaces}\"{}\\\"{spaces}\" 12345{spaces}", "x".repeat(20));
+        let bytes = input.as_bytes();
+
+        let quote = skip_whitespace(bytes, 0);
+        assert_eq!(quote, spaces.len());
+        let backslash = find_quote_or_backslash(bytes, quote + 1).unwrap();
+        assert_eq!(backslash, quote + 21);
+        let closing = find_quote_or_backslash(bytes, backslash + 2).unwrap();
+        assert_eq!(closing, backslash + 2 + spaces.len());
+
+        let number = skip_whitespace(bytes, closing + 1);
+        assert_eq!(&input[number..find_delimiter(bytes, number)], "12345");
+        assert_eq!(skip_whitespace(bytes, number + 5), bytes.len());
+        assert_eq!(find_quote_or_backslash(bytes, number), None);
+        assert_eq!(find_delimiter(b"true", 0), 4);
+    }
+}
@@ -0,0 +1,588 @@
+//! A flat representation of a parsed document that can be navigated without
+//! building a tree, see [`Tape`]
+//!
+//! Parsing happens in two passes. The first finds the byte offset of every
+//! structural character (brackets, braces, colons, commas and quotes) and of
+//! the start of every other value, without looking at their meaning. The
+//! second walks those offsets to check the grammar and write each value as
+//! one entry of the tape, in document order. The first pass skips whitespace
+//! and the insides of strings 16 bytes at a time with the `simd` feature.
+
+use std::borrow::Cow;
+
+use crate::map::{DefaultMapKind, MapKind, ObjectMap};
+use crate::parse::{unescape_chars, unescape_string};
+use crate::simd::{find_delimiter, skip_whitespace};
+use crate::tokenize::{
+    byte_order_mark, check_control_characters, closing_quote, non_finite, number_end, parse_number,
+    token_error, trailing_error,
+};
+use crate::{Error, Number, ParseOptions, TokenParseError, TokenizeError, Value};
+
+/// One value of the document, where objects and arrays are followed by the
+/// entries of their contents
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Null,
+    Boolean(bool),
+    Number(Number),
+    /// Byte range of the text between the quotes, and whether it contains
+    /// escapes that need to be decoded
+    String {
+        start: usize,
+        end: usize,
+        escaped: bool,
+    },
+    /// `end` is the index of the first entry after the array's contents
+    Array {
+        end: usize,
+    },
+    /// Keys are `String` entries, each followed by its value
+    Object {
+        end: usize,
+    },
+}
+
+/// A parsed document stored as a flat list of entries rather than a tree.
+///
+/// Each object or array records where its contents end, so navigating with
+/// [`get`](TapeValue::get) and [`get_index`](TapeValue::get_index) skips over
+/// the values in between rather than visiting them. Strings stay in the
+/// input until they're read, and [`to_value`](Self::to_value) builds a
+/// [`Value`] when a tree is needed after all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tape<'a> {
+    input: &'a str,
+    entries: Vec<Entry>,
+    /// The options parsed with, which decoding strings also follows
+    options: ParseOptions,
+}
+
+/// A value within a [`Tape`]
+#[derive(Debug, Clone, Copy)]
+pub struct TapeValue<'t, 'a> {
+    tape: &'t Tape<'a>,
+    index: usize,
+}
+
+impl<'a> Tape<'a> {
+    /// Parses `input` with the default options
+    pub fn parse(input: &'a str) -> Result<Self, Error> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses `input` with the given options, apart from the JSON5 syntax of
+    /// comments, single quotes, unquoted keys, hex numbers and leading `+`
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<Self, Error> {
+        let start = byte_order_mark(input, options).map_err(|err| err.locate(input, 0))?;
+        let (indices, end) = structural_indices(input, start)?;
+        let mut builder = Builder {
+            input,
+            indices,
+            position: 0,
+            options: options.clone(),
+            entries: Vec::new(),
+            depth: 0,
+        };
+        builder.value(TokenParseError::EmptyInput)?;
+        let offset = skip_whitespace(input.as_bytes(), end);
+        if offset < input.len() && !options.allow_trailing_content {
+            return Err(trailing_error(input, offset, options));
+        }
+        Ok(Self {
+            input,
+            entries: builder.entries,
+            options: builder.options,
+        })
+    }
+
+    /// The root value of the document
+    pub fn root(&self) -> TapeValue<'_, 'a> {
+        TapeValue {
+            tape: self,
+            index: 0,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        self.root().to_value()
+    }
+}
+
+impl<'t, 'a> TapeValue<'t, 'a> {
+    fn entry(&self) -> &'t Entry {
+        &self.tape.entries[self.index]
+    }
+
+    fn at(&self, index: usize) -> Self {
+        Self {
+            tape: self.tape,
+            index,
+        }
+    }
+
+    /// Index of the entry after this value, including its contents
+    fn next_index(&self) -> usize {
+        match self.entry() {
+            Entry::Array { end } | Entry::Object { end } => *end,
+            _ => self.index + 1,
+        }
+    }
+
+    /// The value of a key in an object, or `None` when this isn't an object
+    /// or doesn't have the key. When the key appears more than once the last
+    /// value is used, as with [`parse`](crate::parse).
+    pub fn get(&self, key: &str) -> Option<Self> {
+        self.entries()?
+            .filter(|(k, _)| k == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// An element of an array, or `None` when this isn't an array or the
+    /// index is out of bounds
+    pub fn get_index(&self, index: usize) -> Option<Self> {
+        self.elements()?.nth(index)
+    }
+
+    /// The elements of an array, or `None` when this isn't an array
+    pub fn elements(&self) -> Option<impl Iterator<Item = TapeValue<'t, 'a>>> {
+        let Entry::Array { end } = *self.entry() else {
+            return None;
+        };
+        let (this, mut next) = (*self, self.index + 1);
+        Some(std::iter::from_fn(move || {
+            if next >= end {
+                return None;
+            }
+            let element = this.at(next);
+            next = element.next_index();
+            Some(element)
+        }))
+    }
+
+    /// The keys and values of an object in source order, or `None` when this
+    /// isn't an object
+    pub fn entries(&self) -> Option<impl Iterator<Item = (Cow<'a, str>, TapeValue<'t, 'a>)>> {
+        let Entry::Object { end } = *self.entry() else {
+            return None;
+        };
+        let (this, mut next) = (*self, self.index + 1);
+        Some(std::iter::from_fn(move || {
+            if next >= end {
+                return None;
+            }
+            let key = this.at(next).as_str()?;
+            let value = this.at(next + 1);
+            next = value.next_index();
+            Some((key, value))
+        }))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.entry(), Entry::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.entry() {
+            Entry::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&'t Number> {
+        match self.entry() {
+            Entry::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The unescaped string, which borrows the input when it has no escapes
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        let Entry::String {
+            start,
+            end,
+            escaped,
+        } = *self.entry()
+        else {
+            return None;
+        };
+        let raw = &self.tape.input[start..end];
+        if !escaped {
+            return Some(Cow::Borrowed(raw));
+        }
+        Some(unescape_string(raw, &self.tape.options).expect("escapes are checked when parsing"))
+    }
+
+    /// Builds this value and everything inside it as a [`Value`]
+    pub fn to_value(self) -> Value {
+        self.to_value_of_kind::<DefaultMapKind>()
+    }
+
+    fn to_value_of_kind<K: MapKind>(self) -> Value<K> {
+        match self.entry() {
+            Entry::Null => Value::Null,
+            Entry::Boolean(b) => Value::Boolean(*b),
+            Entry::Number(n) => Value::Number(n.clone()),
+            Entry::String { .. } => Value::String(self.as_str().unwrap_or_default().into()),
+            Entry::Array { .. } => Value::Array(
+                self.elements()
+                    .into_iter()
+                    .flatten()
+                    .map(|element| element.to_value_of_kind())
+                    .collect(),
+            ),
+            Entry::Object { .. } => {
+                let mut map = K::Map::default();
+                for (key, value) in self.entries().into_iter().flatten() {
+                    map.insert(key.as_ref().into(), value.to_value_of_kind());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+/// The first pass: byte offsets of the structural characters outside of
+/// strings, of both quotes around each string, and of the first byte of each
+/// other value, up to the end of the top-level value. Also returns the byte
+/// offset where the text after the top-level value starts.
+fn structural_indices(text: &str, start: usize) -> Result<(Vec<usize>, usize), Error> {
+    let input = text.as_bytes();
+    let mut indices = Vec::new();
+    let mut depth = 0_usize;
+    let mut index = skip_whitespace(input, start);
+    while index < input.len() {
+        indices.push(index);
+        let byte = input[index];
+        index = match byte {
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => index + 1,
+            b'"' => {
+                let closing = closing_quote(input, index).map_err(|err| err.locate(text, index))?;
+                indices.push(closing);
+                closing + 1
+            }
+            // a number ends where its grammar does, as when tokenizing, so
+            // anything after it is the next token
+            b'-' | b'0'..=b'9' => {
+                number_end(input, index).unwrap_or_else(|_| find_delimiter(input, index + 1))
+            }
+            // a literal, checked in the second pass
+            _ => find_delimiter(input, index + 1),
+        };
+        match byte {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        // the text after the top-level value is only checked for its first
+        // token, as `parse_with` does
+        if depth == 0 && !matches!(byte, b'{' | b'[' | b':' | b',') {
+            break;
+        }
+        index = skip_whitespace(input, index);
+    }
+    Ok((indices, index))
+}
+
+/// The second pass, which checks the grammar and writes the tape
+struct Builder<'a> {
+    input: &'a str,
+    indices: Vec<usize>,
+    /// Index of the next structural index to read
+    position: usize,
+    options: ParseOptions,
+    entries: Vec<Entry>,
+    /// Number of arrays and objects open around the next structural index
+    depth: usize,
+}
+
+impl Builder<'_> {
+    /// The offset of the next structural index, or `eof` as an error when
+    /// the input has ended
+    fn next(&mut self, eof: &TokenParseError) -> Result<usize, Error> {
+        let offset = *self
+            .indices
+            .get(self.position)
+            .ok_or_else(|| self.error(eof.clone(), self.input.len()))?;
+        self.position += 1;
+        Ok(offset)
+    }
+
+    /// Converts `err`, found at the byte `offset` of the input, locating it
+    fn error(&self, err: TokenParseError, offset: usize) -> Error {
+        err.locate(self.input, || offset)
+    }
+
+    /// Fails with `err` at the byte `offset`, unless the token that starts
+    /// there is invalid itself, which is reported instead as it is by the
+    /// parsers that tokenize first
+    fn unexpected(&self, err: TokenParseError, offset: usize) -> Error {
+        token_error(self.input, offset, &self.options).unwrap_or_else(|| self.error(err, offset))
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        let offset = *self.indices.get(self.position)?;
+        Some(self.input.as_bytes()[offset])
+    }
+
+    /// After a comma, consumes `closing` if it comes next, which is only
+    /// allowed with [`ParseOptions::allow_trailing_commas`]. Returns whether
+    /// the container was closed.
+    fn trailing_comma(&mut self, closing: u8) -> Result<bool, Error> {
+        if self.peek_byte() != Some(closing) {
+            return Ok(false);
+        }
+        if !self.options.allow_trailing_commas {
+            let comma = self.indices[self.position - 1];
+            return Err(self.error(TokenParseError::TrailingComma, comma));
+        }
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Writes the value at the next structural index, where `eof` is the
+    /// error for the input ending before it
+    fn value(&mut self, eof: TokenParseError) -> Result<(), Error> {
+        let start = self.next(&eof)?;
+        let entry = match self.input.as_bytes()[start] {
+            b'[' | b'{' => return self.container(start),
+            b'"' => self.string(start)?,
+            b']' | b'}' | b':' | b',' => {
+                return Err(self.error(TokenParseError::ExpectedValue, start))
+            }
+            _ => self.scalar(start)?,
+        };
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the array or object opened at `start`, checking
+    /// [`ParseOptions::max_depth`] first since both recurse
+    fn container(&mut self, start: usize) -> Result<(), Error> {
+        self.depth += 1;
+        if self.options.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(TokenizeError::TooDeep.locate(self.input, start));
+        }
+        match self.input.as_bytes()[start] {
+            b'[' => self.array()?,
+            _ => self.object()?,
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn array(&mut self) -> Result<(), Error> {
+        let eof = TokenParseError::UnclosedBracket;
+        let index = self.entries.len();
+        self.entries.push(Entry::Array { end: 0 });
+        if self.peek_byte() == Some(b']') {
+            self.position += 1;
+        } else {
+            loop {
+                self.value(eof.clone())?;
+                let offset = self.next(&eof)?;
+                match self.input.as_bytes()[offset] {
+                    b',' if self.trailing_comma(b']')? => break,
+                    b',' => {}
+                    b']' => break,
+                    _ => return Err(self.unexpected(TokenParseError::ExpectedComma, offset)),
+                }
+            }
+        }
+        self.entries[index] = Entry::Array {
+            end: self.entries.len(),
+        };
+        Ok(())
+    }
+
+    fn object(&mut self) -> Result<(), Error> {
+        let eof = TokenParseError::UnclosedBrace;
+        let index = self.entries.len();
+        self.entries.push(Entry::Object { end: 0 });
+        if self.peek_byte() == Some(b'}') {
+            self.position += 1;
+        } else {
+            loop {
+                let start = self.next(&eof)?;
+                if self.input.as_bytes()[start] != b'"' {
+                    return Err(self.unexpected(TokenParseError::ExpectedProperty, start));
+                }
+                let key = self.string(start)?;
+                self.entries.push(key);
+                let offset = self.next(&eof)?;
+                if self.input.as_bytes()[offset] != b':' {
+                    return Err(self.unexpected(TokenParseError::ExpectedColon, offset));
+                }
+                self.value(eof.clone())?;
+                let offset = self.next(&eof)?;
+                match self.input.as_bytes()[offset] {
+                    b',' if self.trailing_comma(b'}')? => break,
+                    b',' => {}
+                    b'}' => break,
+                    _ => return Err(self.unexpected(TokenParseError::ExpectedComma, offset)),
+                }
+            }
+        }
+        self.entries[index] = Entry::Object {
+            end: self.entries.len(),
+        };
+        Ok(())
+    }
+
+    /// The string whose opening quote is at `start`, where the index of the
+    /// closing quote comes next
+    fn string(&mut self, start: usize) -> Result<Entry, Error> {
+        let end = self.next(&TokenParseError::EarlyEOF)?;
+        let raw = &self.input[start + 1..end];
+        check_control_characters(raw, start + 1, &self.options)
+            .map_err(|err| err.locate(self.input, start))?;
+        let escaped = raw.contains('\\');
+        if escaped {
+            unescape_chars(raw.chars(), &self.options, |_| {})
+                .map_err(|err| err.locate(self.input, || start))?;
+        }
+        Ok(Entry::String {
+            start: start + 1,
+            end,
+            escaped,
+        })
+    }
+
+    /// A number or literal, which continues until whitespace or the next
+    /// structural character
+    fn scalar(&mut self, start: usize) -> Result<Entry, Error> {
+        let text = &self.input[start..find_delimiter(self.input.as_bytes(), start)];
+        let locate = |err: TokenizeError, offset| err.locate(self.input, offset);
+        let first = text.chars().next().expect("scalars aren't empty");
+        if self.options.allow_non_finite_numbers {
+            if let Some(n) = non_finite(text) {
+                return Ok(Entry::Number(Number::F64(n)));
+            }
+        }
+
+        let entry = match text {
+            "null" => Entry::Null,
+            "true" => Entry::Boolean(true),
+            "false" => Entry::Boolean(false),
+            _ if matches!(first, 'n' | 't' | 'f') => {
+                return Err(locate(TokenizeError::UnfinishedLiteralValue, start));
+            }
+            _ if first.is_ascii_digit() || first == '-' => {
+                // anything after the number is the next structural index
+                let end =
+                    number_end(self.input.as_bytes(), start).map_err(|err| locate(err, start))?;
+                let number = parse_number(self.input[start..end].to_string(), &self.options);
+                Entry::Number(number.map_err(|err| locate(err, start))?)
+            }
+            _ => return Err(locate(TokenizeError::CharNotRecognized(first), start)),
+        };
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::Tape;
+    use crate::{parse, Number, TokenParseError, TokenizeError};
+
+    const INPUT: &str = r#"{
+        "name": "tape",
+        "tags": ["a", "b\nc", []],
+        "nested": {"n": null, "ok": true, "big": 12345678901234567890},
+        "name": "last"
+    }"#;
+
+    #[test]
+    fn navigates_without_building_values() {
+        let tape = Tape::parse(INPUT).unwrap();
+        let root = tape.root();
+
+        assert_eq!(root.get("name").unwrap().as_str(), Some("last".into()));
+        let tags = root.get("tags").unwrap();
+        assert!(matches!(
+            tags.get_index(0).unwrap().as_str(),
+            Some(Cow::Borrowed("a"))
+        ));
+        assert!(matches!(
+            tags.get_index(1).unwrap().as_str(),
+            Some(Cow::Owned(s)) if s == "b\nc"
+        ));
+        assert_eq!(tags.get_index(2).unwrap().elements().unwrap().count(), 0);
+        assert!(tags.get_index(3).is_none());
+
+        let nested = root.get("nested").unwrap();
+        assert!(nested.get("n").unwrap().is_null());
+        assert_eq!(nested.get("ok").unwrap().as_bool(), Some(true));
+        assert_eq!(
+            nested.get("big").unwrap().as_number(),
+            Some(&Number::U64(12345678901234567890))
+        );
+        assert!(root.get("missing").is_none());
+        assert!(tags.get("name").is_none());
+    }
+
+    #[test]
+    fn converts_to_value() {
+        let tape = Tape::parse(INPUT).unwrap();
+
+        assert_eq!(tape.to_value(), parse(INPUT).unwrap());
+        assert_eq!(
+            Tape::parse("-1.5").unwrap().to_value(),
+            parse("-1.5").unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("", TokenParseError::EmptyInput.locate("", || 0)),
+            (
+                "[1, 2",
+                TokenParseError::UnclosedBracket.locate("[1, 2", || 5),
+            ),
+            (
+                r#"{"a": 1"#,
+                TokenParseError::UnclosedBrace.locate(r#"{"a": 1"#, || 7),
+            ),
+            ("[1,]", TokenParseError::TrailingComma.locate("[1,]", || 2)),
+            (
+                "[1 2]",
+                TokenParseError::ExpectedComma.locate("[1 2]", || 3),
+            ),
+            (
+                "1 2",
+                TokenParseError::UnexpectedTrailingTokens { offset: 2 }.locate("1 2", || 2),
+            ),
+            (
+                r#"{"a" 1}"#,
+                TokenParseError::ExpectedColon.locate(r#"{"a" 1}"#, || 5),
+            ),
+            (
+                "{1: 2}",
+                TokenParseError::ExpectedProperty.locate("{1: 2}", || 1),
+            ),
+            (
+                r#""\uZZZZ""#,
+                TokenizeError::InvalidUnicodeEscape { offset: 1 }.locate(r#""\uZZZZ""#, 0),
+            ),
+            (
+                r#"["open]"#,
+                TokenizeError::UnclosedQuotes.locate(r#"["open]"#, 1),
+            ),
+            (
+                "nul",
+                TokenizeError::UnfinishedLiteralValue.locate("nul", 0),
+            ),
+            (
+                "[1x]",
+                TokenizeError::CharNotRecognized('x').locate("[1x]", 2),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Tape::parse(input), Err(expected), "{input}");
+        }
+    }
+}
@@ -1,8 +1,21 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::iter::FusedIterator;
 use std::num::ParseFloatError;
+use std::ops::Range;
 
-/// A Token is
+use crate::encoding::{Encoding, EncodingError};
+use crate::options::{CancelToken, DuplicateKey, Progress};
+use crate::parse::{parse_document, unescape_chars, unescape_string, TokenParseError, TokenSource};
+use crate::simd::{find_quote_or_backslash, skip_whitespace};
+use crate::{Error, ErrorKind, Location, Number, ParseOptions, ReadError, Value};
+
+/// One of the pieces that JSON text is made of, see [`tokenize_spanned`]
 #[derive(Debug, PartialEq)]
-pub enum Token {
+pub enum Token<'a> {
     /// `{`
     LeftBrace,
     /// `}`
@@ -24,22 +37,81 @@ pub enum Token {
     True,
 
     /// Any number literal
-    Number(f64),
+    Number(Number),
 
-    /// Key of the key/value pair or string value
-    String(String),
+    /// Key of the key/value pair or string value, as the text between the
+    /// quotes with any escapes as written. It borrows the input, except for
+    /// tokens that outlive the text they came from.
+    String(Cow<'a, str>),
+}
+
+impl Token<'_> {
+    /// Copies the text of a string token, so that the token no longer
+    /// borrows the input
+    pub(crate) fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::LeftBrace => Token::LeftBrace,
+            Token::RightBrace => Token::RightBrace,
+            Token::LeftBracket => Token::LeftBracket,
+            Token::RightBracket => Token::RightBracket,
+            Token::Comma => Token::Comma,
+            Token::Colon => Token::Colon,
+            Token::Null => Token::Null,
+            Token::False => Token::False,
+            Token::True => Token::True,
+            Token::Number(number) => Token::Number(number),
+            Token::String(string) => Token::String(Cow::Owned(string.into_owned())),
+        }
+    }
+
+    /// The same token borrowing the text of this one, rather than copying it
+    pub(crate) fn reborrow(&self) -> Token<'_> {
+        match self {
+            Token::Number(number) => Token::Number(number.clone()),
+            Token::String(string) => Token::String(Cow::Borrowed(string)),
+            Token::LeftBrace => Token::LeftBrace,
+            Token::RightBrace => Token::RightBrace,
+            Token::LeftBracket => Token::LeftBracket,
+            Token::RightBracket => Token::RightBracket,
+            Token::Comma => Token::Comma,
+            Token::Colon => Token::Colon,
+            Token::Null => Token::Null,
+            Token::False => Token::False,
+            Token::True => Token::True,
+        }
+    }
+}
+
+/// Writes the token as it appears in JSON text, with a string's escapes as
+/// written, ex. `[` or `"a\n"`
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LeftBrace => f.write_str("{"),
+            Token::RightBrace => f.write_str("}"),
+            Token::LeftBracket => f.write_str("["),
+            Token::RightBracket => f.write_str("]"),
+            Token::Comma => f.write_str(","),
+            Token::Colon => f.write_str(":"),
+            Token::Null => f.write_str("null"),
+            Token::False => f.write_str("false"),
+            Token::True => f.write_str("true"),
+            Token::Number(number) => write!(f, "{number}"),
+            Token::String(string) => write!(f, "\"{string}\""),
+        }
+    }
 }
 
 #[cfg(test)]
-impl Token {
-    pub(crate) fn string(input: &str) -> Self {
-        Self::String(String::from(input))
+impl<'a> Token<'a> {
+    pub(crate) fn string(input: &'a str) -> Self {
+        Self::String(Cow::Borrowed(input))
     }
 }
 
 /// One of the possible errors that could occur while tokenizing the input
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub enum TokenizeError {
+pub(crate) enum TokenizeError {
     /// Character is not part of a JSON token
     CharNotRecognized(char),
 
@@ -54,146 +126,1577 @@ pub enum TokenizeError {
 
     /// The input ended early
     UnexpectedEof,
+
+    /// A number has no digits before its fraction or exponent, ex. `-`
+    MissingIntegerDigits,
+
+    /// A number's integer part has a zero followed by more digits, ex. `01`
+    LeadingZero,
+
+    /// A number's decimal point isn't followed by digits, ex. `1.`
+    MissingFractionDigits,
+
+    /// A number's exponent has no digits, ex. `1e` or `1e+`
+    MissingExponentDigits,
+
+    /// A `\u` escape in a string isn't followed by four hexadecimal digits,
+    /// at the byte `offset` of its backslash in the input
+    InvalidUnicodeEscape { offset: usize },
+
+    /// The input starts with a byte order mark, see
+    /// [`ParseOptions::reject_byte_order_mark`]
+    ByteOrderMark,
+
+    /// An integer can't be represented exactly as an `f64`, see
+    /// [`ParseOptions::require_exact_integers`]
+    PrecisionLoss(String),
+
+    /// A number runs straight into a character that can't follow it,
+    /// ex. the second `-` of `1-2`
+    UnexpectedCharInNumber(char),
+
+    /// A `/*` comment was never closed, starting at the byte `offset` in
+    /// the input
+    UnclosedComment { offset: usize },
+
+    /// A control character (U+0000 to U+001F) that wasn't escaped in a
+    /// string, at the byte `offset` in the input
+    ControlCharacter { character: char, offset: usize },
+
+    /// The parse was stopped by its [`CancelToken`](crate::CancelToken)
+    Cancelled,
+
+    /// An array or object is nested deeper than
+    /// [`ParseOptions::max_depth`] allows
+    TooDeep,
 }
 
-pub fn tokenize(input: String) -> Result<Vec<Token>, TokenizeError> {
-    let chars: Vec<char> = input.chars().collect();
-    let mut index = 0;
+pub fn tokenize<'a>(input: &'a str, options: &ParseOptions) -> Result<Vec<Token<'a>>, Error> {
+    Tokens::new(input, options).collect()
+}
 
+/// Splits `input` into tokens one at a time as they're asked for, so that
+/// the caller can stop early, such as at the first token that doesn't fit,
+/// without the rest of the input being tokenized.
+///
+/// The iterator ends after the last token or the first error.
+pub struct Tokens<'a> {
+    input: &'a str,
+    options: ParseOptions,
+    /// Byte offset in `input` just after the last token, which is `None`
+    /// once the tokens have ended
+    index: Option<usize>,
+    counter: Counter,
+    keys: KeyTracker,
+    depth: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(input: &'a str, options: &ParseOptions) -> Self {
+        Self {
+            input,
+            options: options.clone(),
+            index: Some(0),
+            counter: Counter::default(),
+            keys: KeyTracker::default(),
+            depth: 0,
+        }
+    }
+
+    /// The next token along with the byte range of its text in the input
+    pub(crate) fn next_spanned(&mut self) -> Option<Result<(Token<'a>, Range<usize>), Error>> {
+        let input = self.input;
+        let options = &self.options;
+        let mut index = self.index.take()?;
+        if index == 0 {
+            index = match byte_order_mark(input, options) {
+                Ok(index) => index,
+                Err(err) => return Some(Err(err.locate(input, 0))),
+            };
+        }
+        let start = match skip_ignored(input.as_bytes(), index, options) {
+            Ok(start) => start,
+            Err(err) => return Some(Err(err.locate(input, index))),
+        };
+        if start >= input.len() {
+            self.counter.finish(input.len(), options);
+            return None;
+        }
+
+        let mut end = start;
+        let token = match make_token(input, &mut end, options)
+            .and_then(|token| nest(&mut self.depth, &token, options).map(|()| token))
+        {
+            Ok(token) => token,
+            Err(err) => return Some(Err(err.locate(input, start))),
+        };
+        end += 1;
+        self.counter.count(&token, end, options);
+        self.keys.track(&token, start, options);
+        self.index = Some(end);
+        Some(Ok((token, start..end)))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|result| result.map(|(token, _)| token))
+    }
+}
+
+impl FusedIterator for Tokens<'_> {}
+
+/// Reads from [`Tokens`] as the parser asks for each token, keeping only the
+/// next one rather than all of them
+pub(crate) struct TokenStream<'a> {
+    tokens: Tokens<'a>,
+    /// Index of the next token, which is in `peeked` once it has been read,
+    /// along with the byte offset where it starts
+    next: usize,
+    peeked: Option<(Token<'a>, usize)>,
+    /// Byte offset where the token before the next one starts
+    previous: usize,
+    /// The error that ended the tokens, which comes before anything the
+    /// parser found after running out of them
+    pub(crate) error: Option<Error>,
+}
+
+impl<'a> TokenStream<'a> {
+    pub(crate) fn new(input: &'a str, options: &ParseOptions) -> Self {
+        Self {
+            tokens: Tokens::new(input, options),
+            next: 0,
+            peeked: None,
+            previous: 0,
+            error: None,
+        }
+    }
+
+    /// Byte offset of the token at `index`, which is the next token or the
+    /// one before it, or of the end of the input after the last token
+    pub(crate) fn offset(&mut self, index: usize) -> usize {
+        if index < self.next {
+            return self.previous;
+        }
+        self.advance(index);
+        let end = self.tokens.input.len();
+        self.peeked.as_ref().map_or(end, |(_, start)| *start)
+    }
+
+    /// Moves past the next token if `index` is after it, and reads the token
+    /// at `index` unless it has been already
+    fn advance(&mut self, index: usize) {
+        if index > self.next {
+            debug_assert_eq!(index, self.next + 1, "only the next token can be skipped");
+            if let Some((_, start)) = self.peeked.take() {
+                self.previous = start;
+            }
+            self.next = index;
+        }
+        if self.peeked.is_none() && self.error.is_none() {
+            self.peeked = match self.tokens.next_spanned() {
+                Some(Ok((token, span))) => Some((token, span.start)),
+                Some(Err(err)) => {
+                    self.error = Some(err);
+                    None
+                }
+                None => None,
+            };
+        }
+    }
+}
+
+impl<'a> TokenSource<'a> for TokenStream<'a> {
+    fn token(&mut self, index: usize) -> Option<&Token<'a>> {
+        self.advance(index);
+        self.peeked.as_ref().map(|(token, _)| token)
+    }
+
+    fn take(&mut self, index: usize) -> Option<Token<'a>> {
+        self.advance(index);
+        let (token, start) = self.peeked.take()?;
+        self.previous = start;
+        self.next += 1;
+        Some(token)
+    }
+}
+
+/// A token along with where its text is in the input
+#[derive(Debug, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
+
+    /// Byte range of the token's text in the input, including the quotes of
+    /// a string
+    pub span: Range<usize>,
+
+    /// Location of the token's first byte
+    pub location: Location,
+}
+
+/// Splits `input` into tokens, along with where each one is in the input,
+/// ex. to underline a token or slice its source text
+pub fn tokenize_spanned<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<SpannedToken<'a>>, Error> {
     let mut tokens = Vec::new();
-    while index < chars.len() {
-        let token = make_token(&chars, &mut index)?;
-        tokens.push(token);
-        index += 1;
+    let mut location = Location::default();
+    let mut iter = Tokens::new(input, options);
+    while let Some(result) = iter.next_spanned() {
+        let (token, span) = result?;
+        location = location.advance(input, span.start);
+        tokens.push(SpannedToken {
+            token,
+            span,
+            location,
+        });
     }
     Ok(tokens)
 }
 
-fn make_token(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    let mut ch = chars[*index];
-    while ch.is_ascii_whitespace() {
-        *index += 1;
-        if *index >= chars.len() {
-            return Err(TokenizeError::UnexpectedEof);
+/// Tokenizes all of `input` like [`tokenize_spanned`], but carries on after
+/// an error in a token from where the next token probably starts
+pub(crate) fn tokenize_recovering<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Vec<Result<(Token<'a>, Range<usize>), Error>> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut index = byte_order_mark(input, options).unwrap_or_else(|err| {
+        tokens.push(Err(err.locate(input, 0)));
+        '\u{FEFF}'.len_utf8()
+    });
+    loop {
+        let start = match skip_ignored(input.as_bytes(), index, options) {
+            Ok(start) => start,
+            // an unclosed comment runs to the end of the input
+            Err(err) => {
+                tokens.push(Err(err.locate(input, index)));
+                break;
+            }
+        };
+        if start >= input.len() {
+            break;
+        }
+        let mut end = start;
+        let result = make_token(input, &mut end, options)
+            .and_then(|token| nest(&mut depth, &token, options).map(|()| token));
+        match result {
+            Ok(token) => {
+                index = end + 1;
+                tokens.push(Ok((token, start..index)));
+            }
+            // nothing after these is read
+            Err(err @ (TokenizeError::Cancelled | TokenizeError::TooDeep)) => {
+                tokens.push(Err(err.locate(input, start)));
+                break;
+            }
+            Err(err) => {
+                tokens.push(Err(err.locate(input, start)));
+                index = resume_offset(input, start);
+            }
         }
-        ch = chars[*index];
     }
-    let token = match ch {
-        '[' => Token::LeftBracket,
-        ']' => Token::RightBracket,
-        '{' => Token::LeftBrace,
-        '}' => Token::RightBrace,
-        ',' => Token::Comma,
-        ':' => Token::Colon,
+    tokens
+}
+
+/// Byte offset to carry on tokenizing from after the token at `start`
+/// failed: after the closing quote of a string, or at the end of the line
+/// for one that isn't closed there, since strings can't span lines, or else
+/// at the next punctuation, quote or whitespace
+fn resume_offset(input: &str, start: usize) -> usize {
+    let bytes = input.as_bytes();
+    let first = input[start..].chars().next().map_or(1, char::len_utf8);
+    let rest = &bytes[start + first..];
+    let length = if matches!(bytes[start], b'"' | b'\'') {
+        let mut escaped = false;
+        rest.iter()
+            .position(|&byte| {
+                let end = byte == b'\n' || (byte == bytes[start] && !escaped);
+                escaped = byte == b'\\' && !escaped;
+                end
+            })
+            .map(|length| length + usize::from(rest[length] != b'\n'))
+    } else {
+        rest.iter()
+            .position(|byte| b",:[]{}\"' \t\r\n".contains(byte))
+    };
+    length.map_or(input.len(), |length| start + first + length)
+}
 
-        'n' => tokenize_null(chars, index)?,
-        't' => tokenize_true(chars, index)?,
-        'f' => tokenize_false(chars, index)?,
+/// The error in the token that starts at the byte offset `start`, located
+/// in the input, or `None` when it's a valid token
+pub(crate) fn token_error(input: &str, start: usize, options: &ParseOptions) -> Option<Error> {
+    let mut end = start;
+    let err = make_token(input, &mut end, options).err()?;
+    Some(err.locate(input, start))
+}
 
-        c if c.is_ascii_digit() || c == '-' => tokenize_float(chars, index)?,
+/// The error for text after the top-level value that starts at the byte
+/// `offset`, where only its first token is tokenized as [`parse_with`]
+/// does: the error in that token, or else that there are trailing tokens
+///
+/// [`parse_with`]: crate::parse_with
+pub(crate) fn trailing_error(input: &str, offset: usize, options: &ParseOptions) -> Error {
+    token_error(input, offset, options).unwrap_or_else(|| {
+        TokenParseError::UnexpectedTrailingTokens { offset }.locate(input, || offset)
+    })
+}
+
+/// Byte length of the byte order mark at the start of `input`, if any, which
+/// is skipped unless [`ParseOptions::reject_byte_order_mark`] is set
+pub(crate) fn byte_order_mark(input: &str, options: &ParseOptions) -> Result<usize, TokenizeError> {
+    match input.strip_prefix('\u{FEFF}') {
+        None => Ok(0),
+        Some(_) if options.reject_byte_order_mark => Err(TokenizeError::ByteOrderMark),
+        Some(_) => Ok('\u{FEFF}'.len_utf8()),
+    }
+}
 
-        '"' => tokenize_string(chars, index)?,
+/// Index of the first byte at or after `index` that isn't whitespace, or
+/// part of a comment when [`ParseOptions::allow_comments`] is set
+fn skip_ignored(
+    bytes: &[u8],
+    index: usize,
+    options: &ParseOptions,
+) -> Result<usize, TokenizeError> {
+    let mut index = skip_whitespace(bytes, index);
+    loop {
+        if options.allow_unicode_whitespace {
+            if let Some(length) = unicode_whitespace(&bytes[index..]) {
+                index = skip_whitespace(bytes, index + length);
+                continue;
+            }
+        }
+        if !options.allow_comments || bytes.get(index) != Some(&b'/') {
+            return Ok(index);
+        }
+        let body = index + 2;
+        index = match bytes.get(index + 1) {
+            Some(b'/') => match bytes[body..].iter().position(|&byte| byte == b'\n') {
+                Some(newline) => body + newline + 1,
+                None => bytes.len(),
+            },
+            Some(b'*') => match bytes[body..].windows(2).position(|pair| pair == b"*/") {
+                Some(close) => body + close + 2,
+                None => return Err(TokenizeError::UnclosedComment { offset: index }),
+            },
+            // a slash that doesn't start a comment isn't recognized
+            Some(_) => return Ok(index),
+            // the start of a comment that may continue in the next chunk
+            None => return Err(TokenizeError::UnexpectedEof),
+        };
+        index = skip_whitespace(bytes, index);
+    }
+}
 
-        ch => return Err(TokenizeError::CharNotRecognized(ch)),
+/// Byte length of the character that `bytes` start with when it's
+/// whitespace that [`ParseOptions::allow_unicode_whitespace`] accepts
+fn unicode_whitespace(bytes: &[u8]) -> Option<usize> {
+    let length = match bytes.first()? {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
     };
+    let ch = std::str::from_utf8(bytes.get(..length)?)
+        .ok()?
+        .chars()
+        .next()?;
+    let is_whitespace = matches!(
+        ch,
+        '\u{B}' | '\u{C}' | '\u{A0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200A}'
+                | '\u{2028}'
+                | '\u{2029}'
+                | '\u{202F}'
+                | '\u{205F}'
+                | '\u{3000}'
+                | '\u{FEFF}'
+    );
+    is_whitespace.then_some(length)
+}
 
-    Ok(token)
+/// Counts `token` into the `depth` of arrays and objects that the tokens are
+/// inside of, failing when that's deeper than [`ParseOptions::max_depth`]
+fn nest(depth: &mut usize, token: &Token, options: &ParseOptions) -> Result<(), TokenizeError> {
+    match token {
+        Token::LeftBrace | Token::LeftBracket => {
+            *depth += 1;
+            if options.max_depth.is_some_and(|max| *depth > max) {
+                return Err(TokenizeError::TooDeep);
+            }
+        }
+        Token::RightBrace | Token::RightBracket => *depth = depth.saturating_sub(1),
+        _ => {}
+    }
+    Ok(())
 }
 
-fn tokenize_null(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    for expected_char in "null".chars() {
-        if expected_char != chars[*index] {
-            return Err(TokenizeError::UnfinishedLiteralValue);
+/// Counts what has been tokenized, to report it to the progress callback
+#[derive(Default)]
+struct Counter {
+    progress: Progress,
+    /// Bytes consumed when the callback is next due
+    next_report: usize,
+}
+
+impl Counter {
+    /// Counts `token`, which ends `bytes` into the input
+    fn count(&mut self, token: &Token, bytes: usize, options: &ParseOptions) {
+        let Some(callback) = &options.progress else {
+            return;
+        };
+        let progress = &mut self.progress;
+        progress.bytes = bytes;
+        progress.tokens += 1;
+        match token {
+            Token::Comma | Token::RightBrace | Token::RightBracket => {}
+            // the string before it was a key rather than a value
+            Token::Colon => progress.values = progress.values.saturating_sub(1),
+            _ => progress.values += 1,
+        }
+        if bytes >= self.next_report {
+            if bytes > 0 {
+                callback.call(*progress);
+            }
+            self.next_report = bytes + callback.every_bytes();
+        }
+    }
+
+    /// Reports the final counts once the input has ended at `bytes`
+    fn finish(&mut self, bytes: usize, options: &ParseOptions) {
+        if let Some(callback) = &options.progress {
+            self.progress.bytes = bytes;
+            callback.call(self.progress);
         }
-        *index += 1;
     }
-    *index -= 1; // index is incremented in the main loop
-    Ok(Token::Null)
 }
 
-fn tokenize_true(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    for expected_char in "true".chars() {
-        if expected_char != chars[*index] {
-            return Err(TokenizeError::UnfinishedLiteralValue);
+/// Produces tokens one at a time on demand, for the parsers that don't
+/// collect every token first
+pub(crate) struct Lexer<'a> {
+    pub(crate) input: &'a str,
+    /// Byte offset in `input` just after the last token
+    index: usize,
+    /// Nesting depth of the tokens read so far
+    depth: usize,
+    pub(crate) options: ParseOptions,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(input: &'a str, options: ParseOptions) -> Self {
+        Self {
+            input,
+            // a rejected byte order mark is reported by `make_token`
+            index: byte_order_mark(input, &options).unwrap_or(0),
+            depth: 0,
+            options,
         }
-        *index += 1;
     }
-    *index -= 1; // index is incremented in the main loop
-    Ok(Token::True)
+
+    pub(crate) fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Byte offset in the input just after the last token
+    pub(crate) fn offset(&self) -> usize {
+        self.index
+    }
+
+    /// The next token and the byte offset where it starts, or `None` when
+    /// only whitespace remains
+    pub(crate) fn next_token(&mut self) -> Result<Option<(Token<'a>, usize)>, Error> {
+        if self.peek_char().is_none() {
+            return Ok(None);
+        }
+        let start = self.index;
+        let token = make_token(self.input, &mut self.index, &self.options)
+            .and_then(|token| nest(&mut self.depth, &token, &self.options).map(|()| token))
+            .map_err(|err| err.locate(self.input, start))?;
+        self.index += 1;
+        Ok(Some((token, start)))
+    }
+
+    /// Skips whitespace and comments and returns the character that starts
+    /// the next token, or `None` when only whitespace remains
+    pub(crate) fn peek_char(&mut self) -> Option<char> {
+        let bytes = self.input.as_bytes();
+        self.index = match skip_ignored(bytes, self.index, &self.options) {
+            Ok(index) => index,
+            // the unclosed comment is reported when the token is read
+            Err(_) => skip_whitespace(bytes, self.index),
+        };
+        self.input[self.index..].chars().next()
+    }
+
+    /// Skips the value that starts at the next token, returning the byte
+    /// offset where it starts.
+    ///
+    /// Strings aren't unescaped and the insides of objects and arrays are
+    /// only checked for closed strings and for each closing bracket matching
+    /// the one it closes, so that only the kinds of the open brackets need
+    /// to be kept.
+    pub(crate) fn skip_value(&mut self) -> Result<usize, Error> {
+        let input = self.input;
+        let Some(first) = self.peek_char() else {
+            return Err(TokenizeError::UnexpectedEof.locate(input, self.index));
+        };
+        let start = self.index;
+        let bytes = input.as_bytes();
+        let locate = |err: TokenizeError, start| err.locate(input, start);
+        match first {
+            '"' => self.index = closing_quote(bytes, start).map_err(|err| locate(err, start))? + 1,
+            '[' | '{' => {
+                // whether each open bracket is an object's
+                let mut open = Vec::new();
+                loop {
+                    let index = self.index;
+                    match bytes.get(index) {
+                        None => return Err(locate(TokenizeError::UnexpectedEof, index)),
+                        Some(b'"') => {
+                            self.index =
+                                closing_quote(bytes, index).map_err(|err| locate(err, index))?
+                        }
+                        Some(b'\'') if self.options.allow_single_quotes => {
+                            self.index =
+                                closing_quote(bytes, index).map_err(|err| locate(err, index))?
+                        }
+                        Some(b'/') if self.options.allow_comments => {
+                            let next = skip_ignored(bytes, index, &self.options)
+                                .map_err(|err| locate(err, index))?;
+                            if next > self.index {
+                                self.index = next;
+                                continue;
+                            }
+                        }
+                        Some(&opener @ (b'[' | b'{')) => open.push(opener == b'{'),
+                        Some(&closer @ (b']' | b'}')) => {
+                            // the value started with a bracket, so one is open
+                            let object = open.pop().unwrap_or_default();
+                            if object != (closer == b'}') {
+                                let kind = match object {
+                                    true => ErrorKind::UnclosedObject,
+                                    false => ErrorKind::UnclosedArray,
+                                };
+                                return Err(Error::new(kind).at(input, Some(index)));
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    self.index += 1;
+                    if open.is_empty() {
+                        break;
+                    }
+                }
+            }
+            // other values are small, and only numbers beyond `u64` allocate
+            _ => {
+                make_token(input, &mut self.index, &self.options)
+                    .map_err(|err| locate(err, start))?;
+                self.index += 1;
+            }
+        }
+        Ok(start)
+    }
+
+    /// Moves past the next token without building it, returning the
+    /// character that starts it and the byte offset where it starts, or
+    /// `None` when only whitespace remains. Strings are reported as starting
+    /// with `"`, whichever quote they use.
+    ///
+    /// Strings and numbers are checked as they would be when tokenized and
+    /// parsed, but in place in the input, so nothing is allocated.
+    pub(crate) fn skip_token(&mut self) -> Result<Option<(char, usize)>, Error> {
+        let Some(mut first) = self.peek_char() else {
+            return Ok(None);
+        };
+        let start = self.index;
+        let input = self.input;
+        let locate = |err: TokenizeError| err.locate(input, start);
+        match first {
+            '"' | '\'' if first == '"' || self.options.allow_single_quotes => {
+                let end = closing_quote(input.as_bytes(), start).map_err(locate)?;
+                let raw = &input[start + 1..end];
+                check_control_characters(raw, start + 1, &self.options).map_err(locate)?;
+                check_unicode_escapes(raw, start + 1).map_err(locate)?;
+                unescape_chars(raw.chars(), &self.options, |_| {})
+                    .map_err(|err| err.locate(input, || start))?;
+                self.index = end;
+                first = '"';
+            }
+            // numbers beyond the JSON grammar, or that must be exact, are
+            // checked by `make_token`
+            c if (c.is_ascii_digit() || c == '-')
+                && !self.options.allow_hex_numbers
+                && !self.options.allow_non_finite_numbers
+                && !self.options.require_exact_integers =>
+            {
+                self.index = number_end(input.as_bytes(), start).map_err(locate)? - 1;
+            }
+            // other tokens don't allocate
+            _ => {
+                let token = make_token(input, &mut self.index, &self.options).map_err(locate)?;
+                if let Token::String(_) = token {
+                    // an unquoted key
+                    first = '"';
+                }
+            }
+        }
+        self.index += 1;
+        Ok(Some((first, start)))
+    }
 }
 
-fn tokenize_false(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    for expected_char in "false".chars() {
-        if expected_char != chars[*index] {
-            return Err(TokenizeError::UnfinishedLiteralValue);
+/// The tokens of a value, and the byte offset where each one starts
+pub(crate) type ValueTokens<'a> = (Vec<Token<'a>>, Vec<usize>);
+
+/// Tokenizes the next top-level value from `input`, starting at `index` and
+/// leaving it after the value's last byte, or returns `None` when only
+/// whitespace remains. The tokens of a value that isn't closed before the
+/// input ends are returned too, for the parser to report what's unclosed.
+pub(crate) fn tokenize_value<'a>(
+    input: &'a str,
+    index: &mut usize,
+    options: &ParseOptions,
+) -> Result<Option<ValueTokens<'a>>, Error> {
+    if *index == 0 {
+        *index = byte_order_mark(input, options).map_err(|err| err.locate(input, 0))?;
+    }
+
+    let mut depth = 0usize;
+    let mut tokens = Vec::new();
+    let mut offsets = Vec::new();
+    loop {
+        let end = *index;
+        *index =
+            skip_ignored(input.as_bytes(), end, options).map_err(|err| err.locate(input, end))?;
+        if *index >= input.len() {
+            return Ok((!tokens.is_empty()).then_some((tokens, offsets)));
         }
+        let start = *index;
+        let token = make_token(input, index, options).map_err(|err| err.locate(input, start))?;
+        nest(&mut depth, &token, options).map_err(|err| err.locate(input, start))?;
         *index += 1;
+        tokens.push(token);
+        offsets.push(start);
+        if depth == 0 {
+            return Ok(Some((tokens, offsets)));
+        }
     }
-    *index -= 1; // index is incremented in the main loop
-    Ok(Token::False)
 }
 
-fn tokenize_string(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    debug_assert!(chars[*index] == '"');
-    let mut string = String::new();
-    let mut is_escaping = false;
+/// Finds the object keys that repeat an earlier key of the same object, to
+/// report them to the duplicate key callback
+#[derive(Default)]
+pub(crate) struct KeyTracker {
+    /// The keys seen so far in each open object and `None` for each open
+    /// array, with the byte offset of their first occurrence
+    containers: Vec<Option<HashMap<String, usize>>>,
+    /// A string in an object, which is a key if a colon follows it
+    string: Option<(String, usize)>,
+}
+
+impl KeyTracker {
+    /// Tracks `token`, which starts at the byte offset `start`, calling the
+    /// duplicate key callback for a repeated key
+    fn track(&mut self, token: &Token, start: usize, options: &ParseOptions) {
+        let Some(callback) = &options.duplicate_key_callback else {
+            return;
+        };
+        if let Some(duplicate) = self.duplicate(token, start, options) {
+            callback.call(duplicate);
+        }
+    }
+
+    /// Tracks `token`, which starts at the byte offset `start`, returning the
+    /// key it completes if that key is a repeat
+    pub(crate) fn duplicate(
+        &mut self,
+        token: &Token,
+        start: usize,
+        options: &ParseOptions,
+    ) -> Option<DuplicateKey> {
+        let string = self.string.take();
+        match token {
+            Token::LeftBrace => self.containers.push(Some(HashMap::new())),
+            Token::LeftBracket => self.containers.push(None),
+            Token::RightBrace | Token::RightBracket => {
+                self.containers.pop();
+            }
+            Token::String(raw) if matches!(self.containers.last(), Some(Some(_))) => {
+                self.string = Some((raw.to_string(), start));
+            }
+            Token::Colon => {
+                let (Some((raw, second)), Some(Some(keys))) = (string, self.containers.last_mut())
+                else {
+                    return None;
+                };
+                // an invalid key is reported by the parse
+                let key = unescape_string(&raw, options).ok()?;
+                match keys.entry(key.into_owned()) {
+                    Entry::Occupied(entry) => {
+                        return Some(DuplicateKey {
+                            key: entry.key().clone(),
+                            first: *entry.get(),
+                            second,
+                        });
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(second);
+                    }
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Byte offset of the first token after the top-level value of `input`
+pub(crate) fn trailing_offset(input: &str, options: &ParseOptions) -> usize {
+    let mut index = 0;
+    // only called once the whole input has been tokenized
+    let _ = tokenize_value(input, &mut index, options);
+    skip_ignored(input.as_bytes(), index, options).unwrap_or(index)
+}
+
+/// The token at the start of `input`, if it starts with one
+pub(crate) fn first_token(input: &str) -> Option<Token<'_>> {
+    make_token(input, &mut 0, &ParseOptions::default()).ok()
+}
 
+/// Number of bytes requested from the reader at a time
+pub(crate) const READ_CHUNK: usize = 8 * 1024;
+
+/// Tokenizes UTF-8 text from a reader a chunk at a time as the parser asks
+/// for each token, producing the same tokens as [`tokenize`] would for the
+/// whole input.
+///
+/// Only the text of tokens that haven't been completed yet is kept, and the
+/// tokens that have been parsed are dropped as the next chunk is read, so
+/// neither the input nor all of its tokens are ever held at once.
+pub(crate) struct ReaderTokens<R> {
+    reader: R,
+    chunk: Vec<u8>,
+    tokenizer: ChunkTokenizer,
+    /// Index among all of the tokens of the first one still in `tokenizer`
+    first: usize,
+    /// Index of the token after the last one the parser took
+    next: usize,
+    ended: bool,
+    /// The error that ended the tokens, which comes before anything the
+    /// parser found after running out of them
+    pub(crate) error: Option<ReadError>,
+}
+
+impl<R: Read> ReaderTokens<R> {
+    pub(crate) fn new(reader: R, options: &ParseOptions) -> Self {
+        Self {
+            reader,
+            chunk: vec![0; READ_CHUNK],
+            tokenizer: ChunkTokenizer::new(options.clone()),
+            first: 0,
+            next: 0,
+            ended: false,
+            error: None,
+        }
+    }
+
+    /// Parses the whole document, reading only as much of the input as it
+    /// takes to find where the document ends
+    pub(crate) fn parse_document(mut self) -> Result<Value, ReadError> {
+        let options = self.tokenizer.options.clone();
+        let result = parse_document(&mut self, &options, |tokens, _| {
+            let tokenizer = &tokens.tokenizer;
+            tokenizer.trailing_offset().unwrap_or(tokenizer.bytes_read)
+        });
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        result.map_err(|(err, index)| {
+            let error = self.tokenizer.locate_parsed(err, index - self.first);
+            ReadError::Parse(error)
+        })
+    }
+
+    /// Reads chunks until the token at `index` has been tokenized, returning
+    /// its position in `tokenizer`, or `None` when the input ends first
+    fn fill(&mut self, index: usize) -> Option<usize> {
+        while index - self.first >= self.tokenizer.tokens.len() {
+            if self.ended || self.error.is_some() {
+                return None;
+            }
+            // the last token taken is kept, since an error in a string is
+            // located within its text
+            let parsed = self.next.saturating_sub(1) - self.first;
+            self.tokenizer.tokens.drain(..parsed);
+            self.tokenizer.locations.drain(..parsed);
+            self.first += parsed;
+
+            let len = match self.reader.read(&mut self.chunk) {
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.error = Some(ReadError::Io(err));
+                    return None;
+                }
+            };
+            self.ended = len == 0;
+            if let Err(err) = self.tokenizer.push(&self.chunk[..len]) {
+                self.error = Some(ReadError::Parse(err));
+                return None;
+            }
+        }
+        Some(index - self.first)
+    }
+}
+
+impl<R: Read> TokenSource<'static> for ReaderTokens<R> {
+    fn token(&mut self, index: usize) -> Option<&Token<'static>> {
+        let position = self.fill(index)?;
+        self.tokenizer.tokens.get(position)
+    }
+
+    fn take(&mut self, index: usize) -> Option<Token<'static>> {
+        let position = self.fill(index)?;
+        self.next = index + 1;
+        let token = &mut self.tokenizer.tokens[position];
+        Some(match token {
+            // only a string with escapes can have an error to locate in it
+            Token::String(raw) if raw.contains('\\') => Token::String(raw.clone()),
+            token => std::mem::replace(token, Token::Null),
+        })
+    }
+}
+
+/// Tokenizes UTF-8 text from an async `reader` a chunk at a time, yielding
+/// to the executor while waiting for input. Unlike [`ReaderTokens`], every
+/// token is kept until the input ends, since the parser can't wait for more.
+#[cfg(feature = "tokio")]
+pub(crate) async fn tokenize_async(
+    reader: impl tokio::io::AsyncRead,
+    options: &ParseOptions,
+) -> Result<ChunkTokenizer, ReadError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut reader = std::pin::pin!(reader);
+    let mut chunk = vec![0; READ_CHUNK];
+    let mut tokenizer = ChunkTokenizer::new(options.clone());
     loop {
-        *index += 1;
-        if *index >= chars.len() {
-            return Err(TokenizeError::UnclosedQuotes);
+        let len = match reader.read(&mut chunk).await {
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(ReadError::Io(err)),
+        };
+        tokenizer.push(&chunk[..len])?;
+        if len == 0 {
+            return Ok(tokenizer);
+        }
+    }
+}
+
+/// Tokenizes text that arrives in chunks of bytes, which may split both
+/// tokens and UTF-8 characters
+pub(crate) struct ChunkTokenizer {
+    options: ParseOptions,
+    /// Bytes of a character that was split between two chunks
+    partial: Vec<u8>,
+    bytes_read: usize,
+    /// Text from the start of the first token that hasn't been completed
+    text: String,
+    /// Line and column in the input where `text` starts
+    line: usize,
+    column: usize,
+    /// Completed tokens, which own their text since it's dropped from `text`
+    pub(crate) tokens: Vec<Token<'static>>,
+    /// Where each of `tokens` starts in the input
+    pub(crate) locations: Vec<Location>,
+    counter: Counter,
+    keys: KeyTracker,
+    /// Nesting depth of the top-level value, and whether it's complete
+    depth: usize,
+    value_done: bool,
+    /// Location of the first token after the top-level value
+    trailing: Option<Location>,
+    /// Whether the input is made of documents one after another, so that
+    /// all of them are tokenized rather than stopping after the first
+    documents: bool,
+}
+
+impl ChunkTokenizer {
+    pub(crate) fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            partial: Vec::new(),
+            bytes_read: 0,
+            text: String::new(),
+            line: 0,
+            column: 0,
+            tokens: Vec::new(),
+            locations: Vec::new(),
+            counter: Counter::default(),
+            keys: KeyTracker::default(),
+            depth: 0,
+            value_done: false,
+            trailing: None,
+            documents: false,
+        }
+    }
+
+    /// Tokenizes every document of an input made of documents placed one
+    /// after another
+    pub(crate) fn documents(options: ParseOptions) -> Self {
+        Self {
+            documents: true,
+            ..Self::new(options)
+        }
+    }
+
+    /// Whether the rest of the input doesn't need to be tokenized, which is
+    /// once the top-level value is complete and either trailing content is
+    /// allowed or its first token has been found, as [`parse_with`] does
+    ///
+    /// [`parse_with`]: crate::parse_with
+    fn stopped(&self) -> bool {
+        self.value_done
+            && !self.documents
+            && (self.options.allow_trailing_content || self.trailing.is_some())
+    }
+
+    /// Tokenizes as much as possible after adding `bytes` to the input, where
+    /// an empty chunk marks the end of the input
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let eof = bytes.is_empty();
+        if self.stopped() {
+            // the rest of the input is only counted
+            self.partial.clear();
+            self.text.clear();
+            self.bytes_read += bytes.len();
+            if eof {
+                self.counter.finish(self.bytes_read, &self.options);
+            }
+            return Ok(());
+        }
+        self.partial.extend_from_slice(bytes);
+
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text,
+            // only an incomplete character at the end can be finished by the
+            // next chunk
+            Err(err) if err.error_len().is_none() && !eof => {
+                std::str::from_utf8(&self.partial[..err.valid_up_to()]).expect("valid up to here")
+            }
+            Err(err) => {
+                let offset = self.bytes_read + err.valid_up_to();
+                let encoding = Encoding::Utf8;
+                return Err(EncodingError { encoding, offset }.into());
+            }
+        };
+        self.text.push_str(valid);
+        let consumed = valid.len();
+        self.bytes_read += consumed;
+        self.partial.drain(..consumed);
+
+        // until a token is complete, the text starts at the input's start
+        let mut index = 0;
+        if self.bytes_read == self.text.len() {
+            index =
+                byte_order_mark(&self.text, &self.options).map_err(|err| self.locate(err, 0))?;
+        }
+        while !self.stopped() {
+            // whitespace after the last token isn't an error, unlike when
+            // `make_token` reaches the end while skipping it. The text after
+            // the last token is kept until the next token is complete, since
+            // a line comment at the end may continue in the next chunk.
+            let start = match skip_ignored(self.text.as_bytes(), index, &self.options) {
+                Ok(start) => start,
+                Err(err) if !eof && is_truncation(&err) => break,
+                Err(err) => return Err(self.locate(err, index)),
+            };
+            if start >= self.text.len() {
+                break;
+            }
+
+            // a number that reaches the end of the buffered text may continue
+            // in the next chunk, so it is only accepted once more text is
+            // buffered
+            let mut end = start;
+            let result = make_token(&self.text, &mut end, &self.options);
+            let may_continue = match &result {
+                Ok(Token::Number(_)) => end + 1 >= self.text.len(),
+                // a literal is a key when a colon follows it
+                Ok(Token::True | Token::False | Token::Null)
+                    if self.options.allow_unquoted_keys =>
+                {
+                    skip_ignored(self.text.as_bytes(), end + 1, &self.options)
+                        .map_or(true, |next| next >= self.text.len())
+                }
+                Ok(_) => false,
+                // more digits may still make an integer inexact
+                Err(TokenizeError::PrecisionLoss(_)) => self.text.as_bytes()[start..]
+                    .iter()
+                    .all(|b| b.is_ascii_digit() || *b == b'-' || *b == b'+'),
+                Err(err) => is_truncation(err),
+            };
+            if !eof && may_continue {
+                break;
+            }
+            let base = self.bytes_read - self.text.len();
+            let token = match result {
+                Ok(token) => token,
+                // only a mark at the very start of the input is one
+                Err(TokenizeError::ByteOrderMark) if base > 0 => {
+                    let err = TokenizeError::CharNotRecognized('\u{FEFF}');
+                    return Err(self.locate(err, start));
+                }
+                Err(err) => return Err(self.locate(err, start)),
+            };
+            index = end + 1;
+            let consumed = base + index;
+            self.counter.count(&token, consumed, &self.options);
+            self.keys.track(&token, base + start, &self.options);
+            let token = token.into_owned();
+            self.track_depth(&token, start)?;
+            self.tokens.push(token);
+            self.locations.push(self.location(start));
+        }
+        if eof {
+            self.counter.finish(self.bytes_read, &self.options);
+        }
+
+        // drop the text of completed tokens
+        let index = index.min(self.text.len());
+        let dropped = self.location(index);
+        (self.line, self.column) = (dropped.line, dropped.column);
+        self.text.drain(..index);
+        Ok(())
+    }
+
+    /// Parses the tokens of the whole input, once it has ended
+    pub(crate) fn parse_document(&self) -> Result<Value, Error> {
+        let trailing_offset = |_: &mut _, _| self.trailing_offset().unwrap_or(self.bytes_read);
+        parse_document(&self.tokens[..], &self.options, trailing_offset)
+            .map_err(|(err, index)| self.locate_parsed(err, index))
+    }
+
+    /// Converts `err`, found by parsing at the token at `index`, locating it
+    /// in the input
+    pub(crate) fn locate_parsed(&self, mut err: TokenParseError, index: usize) -> Error {
+        let location = match &mut err {
+            TokenParseError::UnexpectedTrailingTokens { .. } => self.trailing,
+            TokenParseError::InvalidEscape { offset, .. }
+            | TokenParseError::InvalidHexValue { offset }
+            | TokenParseError::LoneSurrogate { offset, .. } => {
+                let Some(Token::String(raw)) = self.tokens.get(index) else {
+                    unreachable!("escapes are only found in strings");
+                };
+                // the text of the string starts after its quote
+                let quote = self.locations[index];
+                let text = Location {
+                    offset: quote.offset + 1,
+                    column: quote.column + 1,
+                    ..quote
+                };
+                let location = text.within(raw, *offset);
+                *offset = location.offset;
+                Some(location)
+            }
+            // the end of the input when the tokens ran out
+            _ => Some(
+                self.locations
+                    .get(index)
+                    .copied()
+                    .unwrap_or_else(|| self.location(self.text.len())),
+            ),
+        };
+        let mut error = Error::from(err);
+        error.location = location;
+        error
+    }
+
+    /// Byte offset of the first token after the top-level value
+    pub(crate) fn trailing_offset(&self) -> Option<usize> {
+        self.trailing.map(|location| location.offset)
+    }
+
+    /// Converts `err`, found in the token at the byte `start` of the
+    /// buffered text, locating it in the input. Offsets in errors are into
+    /// the buffered text, which starts partway through the input.
+    fn locate(&self, err: TokenizeError, start: usize) -> Error {
+        let offset = err.offset(start, self.text.len());
+        let mut error = Error::from(err);
+        error.location = Some(self.location(offset));
+        // the rest of the input may not have been read yet
+        error.suggest_from(&self.text[offset.min(self.text.len())..]);
+        error
+    }
+
+    /// Location in the input of the byte `index` of the buffered text
+    fn location(&self, index: usize) -> Location {
+        let within = Location::new(&self.text, index);
+        Location {
+            offset: self.bytes_read - self.text.len() + index,
+            line: self.line + within.line,
+            column: match within.line {
+                0 => self.column + within.column,
+                _ => within.column,
+            },
         }
+    }
 
-        let ch = chars[*index];
-        match ch {
-            '"' if !is_escaping => break,
-            '\\' => is_escaping = !is_escaping,
-            _ => is_escaping = false,
+    /// Follows the nesting of the top-level value to find where it ends,
+    /// given the byte `start` of the token in the buffered text, failing when
+    /// it's nested deeper than [`ParseOptions::max_depth`]
+    fn track_depth(&mut self, token: &Token, start: usize) -> Result<(), Error> {
+        if self.value_done {
+            if self.trailing.is_none() {
+                self.trailing = Some(self.location(start));
+            }
+            return Ok(());
         }
+        nest(&mut self.depth, token, &self.options).map_err(|err| self.locate(err, start))?;
+        self.value_done = self.depth == 0;
+        Ok(())
+    }
+}
 
-        string.push(ch);
+/// Whether the error could be caused by the input ending partway through a
+/// token, in which case more input may complete it
+fn is_truncation(err: &TokenizeError) -> bool {
+    matches!(
+        err,
+        TokenizeError::ParseNumberError(_)
+            | TokenizeError::MissingIntegerDigits
+            | TokenizeError::MissingFractionDigits
+            | TokenizeError::MissingExponentDigits
+            | TokenizeError::UnclosedQuotes
+            | TokenizeError::UnfinishedLiteralValue
+            | TokenizeError::UnexpectedEof
+            | TokenizeError::UnclosedComment { .. }
+    )
+}
+
+/// Makes the token that starts at the byte offset `index` after any
+/// whitespace, leaving `index` at the token's last byte
+fn make_token<'a>(
+    input: &'a str,
+    index: &mut usize,
+    options: &ParseOptions,
+) -> Result<Token<'a>, TokenizeError> {
+    if options
+        .cancel
+        .as_ref()
+        .is_some_and(CancelToken::is_cancelled)
+    {
+        return Err(TokenizeError::Cancelled);
+    }
+    let bytes = input.as_bytes();
+    *index = skip_ignored(bytes, *index, options)?;
+    let Some(&byte) = bytes.get(*index) else {
+        return Err(TokenizeError::UnexpectedEof);
+    };
+    if options.allow_unquoted_keys && is_identifier_start(byte) {
+        if let Some(key) = unquoted_key(input, index, options)? {
+            return Ok(key);
+        }
     }
+    let token = match byte {
+        b'[' => Token::LeftBracket,
+        b']' => Token::RightBracket,
+        b'{' => Token::LeftBrace,
+        b'}' => Token::RightBrace,
+        b',' => Token::Comma,
+        b':' => Token::Colon,
+
+        b'n' => tokenize_literal(bytes, index, "null", Token::Null)?,
+        b't' => tokenize_literal(bytes, index, "true", Token::True)?,
+        b'f' => tokenize_literal(bytes, index, "false", Token::False)?,
+
+        b if b.is_ascii_digit() || b == b'-' => tokenize_float(input, index, options)?,
+        b'+' if options.allow_leading_plus => tokenize_float(input, index, options)?,
+        b'N' | b'I' if options.allow_non_finite_numbers => tokenize_float(input, index, options)?,
+
+        b'"' => tokenize_string(input, index, options)?,
+        b'\'' if options.allow_single_quotes => tokenize_string(input, index, options)?,
+
+        // a byte order mark that wasn't skipped
+        0xEF if *index == 0 && input.starts_with('\u{FEFF}') => {
+            return Err(TokenizeError::ByteOrderMark);
+        }
 
-    Ok(Token::String(string))
+        // the only place where a character beyond ASCII needs to be decoded
+        _ => {
+            let ch = input[*index..]
+                .chars()
+                .next()
+                .expect("a character starts here");
+            return Err(TokenizeError::CharNotRecognized(ch));
+        }
+    };
+
+    Ok(token)
 }
 
-fn tokenize_float(chars: &Vec<char>, index: &mut usize) -> Result<Token, TokenizeError> {
-    let mut unparsed_num = String::new();
-    let mut has_decimal = false;
+fn tokenize_literal<'a>(
+    bytes: &[u8],
+    index: &mut usize,
+    literal: &str,
+    token: Token<'a>,
+) -> Result<Token<'a>, TokenizeError> {
+    if !bytes[*index..].starts_with(literal.as_bytes()) {
+        return Err(TokenizeError::UnfinishedLiteralValue);
+    }
+    *index += literal.len() - 1; // index is incremented in the main loop
+    Ok(token)
+}
 
-    while *index < chars.len() {
-        let ch = chars[*index];
-        match ch {
-            c if c.is_ascii_digit() || c == '-' => unparsed_num.push(c),
-            c if c == '.' && !has_decimal => {
-                unparsed_num.push('.');
-                has_decimal = true;
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$'
+}
+
+/// A bare word starting at `index` that is followed by a colon, as a string
+/// token, or `None` when it isn't a key and is tokenized as usual
+fn unquoted_key<'a>(
+    input: &'a str,
+    index: &mut usize,
+    options: &ParseOptions,
+) -> Result<Option<Token<'a>>, TokenizeError> {
+    let bytes = input.as_bytes();
+    let end = bytes[*index..]
+        .iter()
+        .position(|&byte| !is_identifier_start(byte) && !byte.is_ascii_digit())
+        .map_or(bytes.len(), |length| *index + length);
+    let next = skip_ignored(bytes, end, options)?;
+    match bytes.get(next) {
+        Some(b':') => {
+            let key = &input[*index..end];
+            *index = end - 1; // index is incremented in the main loop
+            Ok(Some(Token::String(Cow::Borrowed(key))))
+        }
+        // the colon may be in the next chunk
+        None if !is_word_value(&input[*index..end], options) => Err(TokenizeError::UnexpectedEof),
+        _ => Ok(None),
+    }
+}
+
+/// Whether `word` is a complete value on its own, rather than the start of
+/// a key
+fn is_word_value(word: &str, options: &ParseOptions) -> bool {
+    matches!(word, "true" | "false" | "null")
+        || options.allow_non_finite_numbers && matches!(word, "NaN" | "Infinity")
+}
+
+fn tokenize_string<'a>(
+    input: &'a str,
+    index: &mut usize,
+    options: &ParseOptions,
+) -> Result<Token<'a>, TokenizeError> {
+    debug_assert!(matches!(input.as_bytes()[*index], b'"' | b'\''));
+    let end = closing_quote(input.as_bytes(), *index)?;
+    let string = &input[*index + 1..end];
+    check_control_characters(string, *index + 1, options)?;
+    check_unicode_escapes(string, *index + 1)?;
+    *index = end;
+
+    Ok(Token::String(Cow::Borrowed(string)))
+}
+
+/// Checks that each `\u` escape in the text of a string, which starts at the
+/// byte offset `start` in the input, has four hexadecimal digits, so that
+/// the error points at the escape rather than being found by the parse
+fn check_unicode_escapes(string: &str, start: usize) -> Result<(), TokenizeError> {
+    let bytes = string.as_bytes();
+    let mut index = 0;
+    while let Some(found) = bytes
+        .get(index..)
+        .and_then(|rest| rest.iter().position(|&byte| byte == b'\\'))
+    {
+        let backslash = index + found;
+        if bytes.get(backslash + 1) == Some(&b'u') {
+            let digits = bytes.get(backslash + 2..backslash + 6);
+            if !digits.is_some_and(|digits| digits.iter().all(u8::is_ascii_hexdigit)) {
+                return Err(TokenizeError::InvalidUnicodeEscape {
+                    offset: start + backslash,
+                });
             }
+        }
+        // the escaped character can't start another escape
+        index = backslash + 2;
+    }
+    Ok(())
+}
 
-            _ => break,
+/// Checks the text of a string, which starts at the byte offset `start` in
+/// the input, for control characters, which must be escaped unless
+/// [`ParseOptions::allow_control_characters`] is set
+pub(crate) fn check_control_characters(
+    string: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<(), TokenizeError> {
+    if options.allow_control_characters {
+        return Ok(());
+    }
+    let continuations = options.allow_line_continuations;
+    let mut is_escaping = false;
+    // whether the last byte was a `\r` that continued a line
+    let mut after_continuation = false;
+    for (i, byte) in string.bytes().enumerate() {
+        let is_line_break = matches!(byte, b'\n' | b'\r');
+        let continues_line =
+            continuations && (is_escaping && is_line_break || after_continuation && byte == b'\n');
+        if byte < 0x20 && !continues_line {
+            return Err(TokenizeError::ControlCharacter {
+                character: char::from(byte),
+                offset: start + i,
+            });
         }
-        *index += 1;
+        after_continuation = continuations && is_escaping && byte == b'\r';
+        is_escaping = !is_escaping && byte == b'\\';
+    }
+    Ok(())
+}
+
+/// Byte offset of the quote that closes the string opening at `start`, which
+/// is the same kind of quote as the one that opens it
+pub(crate) fn closing_quote(bytes: &[u8], start: usize) -> Result<usize, TokenizeError> {
+    let opening = bytes[start];
+    let mut index = start + 1;
+    loop {
+        let found = if opening == b'"' {
+            find_quote_or_backslash(bytes, index)
+        } else {
+            let rest = bytes.get(index..).unwrap_or_default();
+            let offset = rest
+                .iter()
+                .position(|&byte| byte == opening || byte == b'\\');
+            offset.map(|offset| index + offset)
+        };
+        match found {
+            None => return Err(TokenizeError::UnclosedQuotes),
+            Some(quote) if bytes[quote] == opening => return Ok(quote),
+            // the escaped character can't end the string
+            Some(backslash) => index = backslash + 2,
+        }
+    }
+}
+
+/// Byte offset just after the number that starts at `start`, which must
+/// follow the JSON grammar of an optional minus sign, an integer part, an
+/// optional fraction and an optional exponent
+pub(crate) fn number_end(bytes: &[u8], start: usize) -> Result<usize, TokenizeError> {
+    let digits_end = |index: usize| {
+        index
+            + bytes[index..]
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count()
+    };
+
+    let mut end = start + usize::from(bytes.get(start) == Some(&b'-'));
+    let integer_end = digits_end(end);
+    if integer_end == end {
+        return Err(TokenizeError::MissingIntegerDigits);
+    }
+    if bytes[end] == b'0' && integer_end > end + 1 {
+        return Err(TokenizeError::LeadingZero);
     }
+    end = integer_end;
+
+    if bytes.get(end) == Some(&b'.') {
+        let fraction_end = digits_end(end + 1);
+        if fraction_end == end + 1 {
+            return Err(TokenizeError::MissingFractionDigits);
+        }
+        end = fraction_end;
+    }
+
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        end += 1;
+        if matches!(bytes.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let exponent_end = digits_end(end);
+        if exponent_end == end {
+            return Err(TokenizeError::MissingExponentDigits);
+        }
+        end = exponent_end;
+    }
+
+    // a number can't run straight into another, ex. `1-2` or `1.2.3`
+    match bytes.get(end) {
+        Some(&byte @ (b'-' | b'+' | b'.' | b'e' | b'E')) => {
+            Err(TokenizeError::UnexpectedCharInNumber(char::from(byte)))
+        }
+        _ => Ok(end),
+    }
+}
+
+fn tokenize_float<'a>(
+    input: &str,
+    index: &mut usize,
+    options: &ParseOptions,
+) -> Result<Token<'a>, TokenizeError> {
+    if let Some((number, end)) = extended_number(input, *index, options)? {
+        *index = end - 1;
+        return Ok(Token::Number(number));
+    }
+
+    // a leading `+` isn't part of the number's text, and can't be followed
+    // by a `-`
+    let bytes = input.as_bytes();
+    let start = *index + usize::from(bytes[*index] == b'+');
+    if start > *index && bytes.get(start) == Some(&b'-') {
+        return Err(TokenizeError::MissingIntegerDigits);
+    }
+    let end = number_end(bytes, start)?;
+    let unparsed_num = input[start..end].to_string();
 
     // outer loop increments index
-    *index -= 1;
+    *index = end - 1;
+
+    parse_number(unparsed_num, options).map(Token::Number)
+}
+
+/// A number starting at `start`, after any sign, that isn't in the JSON
+/// grammar but that the options allow, `NaN`, `Infinity` or a hexadecimal
+/// integer, and the byte offset just after it
+fn extended_number(
+    input: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Option<(Number, usize)>, TokenizeError> {
+    let bytes = input.as_bytes();
+    let negative = bytes[start] == b'-';
+    let digits = start + usize::from(matches!(bytes[start], b'-' | b'+'));
+    let rest = &input[digits..];
+
+    if options.allow_non_finite_numbers {
+        if rest.starts_with("Infinity") {
+            let infinity = if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            };
+            return Ok(Some((Number::F64(infinity), digits + "Infinity".len())));
+        }
+        if rest.starts_with("NaN") {
+            return Ok(Some((Number::F64(f64::NAN), digits + "NaN".len())));
+        }
+        if matches!(bytes.get(digits), Some(b'N' | b'I')) {
+            return Err(TokenizeError::UnfinishedLiteralValue);
+        }
+    }
+
+    if options.allow_hex_numbers && (rest.starts_with("0x") || rest.starts_with("0X")) {
+        let hex = &rest[2..];
+        let length = hex.bytes().take_while(u8::is_ascii_hexdigit).count();
+        if length == 0 {
+            return Err(match hex.chars().next() {
+                Some(ch) => TokenizeError::CharNotRecognized(ch),
+                None => TokenizeError::UnexpectedEof,
+            });
+        }
+        let hex = &hex[..length];
+        let number = match (u64::from_str_radix(hex, 16), negative) {
+            (Ok(n), false) => Number::from(n),
+            (Ok(n), true) if n <= i64::MAX as u64 + 1 => Number::I64((n as i64).wrapping_neg()),
+            // too large for 64 bits, so only kept approximately
+            _ => {
+                let n = hex.bytes().fold(0.0, |n, digit| {
+                    n * 16.0 + f64::from(char::from(digit).to_digit(16).unwrap_or(0))
+                });
+                Number::F64(if negative { -n } else { n })
+            }
+        };
+        return Ok(Some((number, digits + 2 + length)));
+    }
+
+    Ok(None)
+}
 
-    match unparsed_num.parse() {
-        Ok(f) => Ok(Token::Number(f)),
-        Err(err) => Err(TokenizeError::ParseNumberError(err)),
+/// The value of `NaN`, `Infinity` or `-Infinity`, which are only numbers
+/// with [`ParseOptions::allow_non_finite_numbers`]
+pub(crate) fn non_finite(text: &str) -> Option<f64> {
+    match text {
+        "NaN" => Some(f64::NAN),
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+/// Whether `float`, parsed from the integer `text`, has exactly its value
+fn is_exact_integer(text: &str, float: f64) -> bool {
+    // floats are printed with every digit of their exact value
+    float.is_finite() && format!("{:.0}", float.abs()) == text.trim_start_matches('-')
+}
+
+/// Converts the text of a number token to the `Number` that stores it best
+pub(crate) fn parse_number(
+    unparsed_num: String,
+    options: &ParseOptions,
+) -> Result<Number, TokenizeError> {
+    let has_decimal = unparsed_num.contains('.');
+
+    // every valid number parses as a float, even when it is stored otherwise
+    let float = match unparsed_num.parse::<f64>() {
+        Ok(f) => f,
+        Err(err) => return Err(TokenizeError::ParseNumberError(err)),
+    };
+
+    let is_integer = !unparsed_num.contains(['.', 'e', 'E']);
+    if options.require_exact_integers && is_integer && !is_exact_integer(&unparsed_num, float) {
+        return Err(TokenizeError::PrecisionLoss(unparsed_num));
+    }
+
+    if options.preserve_number_literals {
+        return Ok(Number::Literal(unparsed_num));
+    }
+
+    // integers are kept exact when they fit in 64 bits
+    if !has_decimal {
+        if let Ok(n) = unparsed_num.parse::<i64>() {
+            return Ok(Number::I64(n));
+        }
+        if let Ok(n) = unparsed_num.parse::<u64>() {
+            return Ok(Number::U64(n));
+        }
+    }
+
+    // a number beyond the range of `f64`, ex. `1e400`, is kept as written
+    // rather than becoming infinite, which JSON can't represent
+    if cfg!(feature = "arbitrary_precision") || !float.is_finite() {
+        Ok(Number::Literal(unparsed_num))
+    } else {
+        Ok(Number::F64(float))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{tokenize, Token, TokenizeError};
+    use std::io::{self, Read};
+
+    use super::{ChunkTokenizer, ReaderTokens, Token, TokenizeError, Tokens, READ_CHUNK};
+    use crate::encoding::{Encoding, EncodingError};
+    use crate::parse::TokenSource;
+    use crate::{Error, Location, Number, ParseOptions, ReadError};
+
+    fn tokenize(input: &str) -> Result<Vec<Token<'_>>, Error> {
+        super::tokenize(input, &ParseOptions::default())
+    }
 
     #[test]
     fn just_comma() {
         let input = String::from(",");
         let expected = [Token::Comma];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -210,7 +1713,7 @@ mod tests {
             Token::Colon,
         ];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -220,7 +1723,7 @@ mod tests {
         let input = String::from("\"ken\"");
         let expected = [Token::string("ken")];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -228,9 +1731,9 @@ mod tests {
     #[test]
     fn escaped_quote() {
         let input = String::from(r#""the \" is OK""#);
-        let expected = [Token::String(String::from(r#"the \" is OK"#))];
+        let expected = [Token::string(r#"the \" is OK"#)];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -238,9 +1741,9 @@ mod tests {
     #[test]
     fn unclosed_string() {
         let input = String::from("\"unclosed");
-        let expected = Err(TokenizeError::UnclosedQuotes);
+        let expected = Err(TokenizeError::UnclosedQuotes.locate(&input, 0));
 
-        let actual = tokenize(input);
+        let actual = tokenize(&input);
 
         assert_eq!(actual, expected);
     }
@@ -250,7 +1753,7 @@ mod tests {
         let input = String::from("\"key\":");
         let expected = [Token::string("key"), Token::Colon];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -266,7 +1769,7 @@ mod tests {
             Token::RightBrace,
         ];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -276,13 +1779,13 @@ mod tests {
         let input = String::from("[123.4, 567.8]");
         let expected = [
             Token::LeftBracket,
-            Token::Number(123.4),
+            Token::Number(Number::F64(123.4)),
             Token::Comma,
-            Token::Number(567.8),
+            Token::Number(Number::F64(567.8)),
             Token::RightBracket,
         ];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -298,7 +1801,7 @@ mod tests {
             Token::RightBracket,
         ];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -308,7 +1811,7 @@ mod tests {
         let input = String::from("[\"\"]");
         let expected = [Token::LeftBracket, Token::string(""), Token::RightBracket];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -318,7 +1821,7 @@ mod tests {
         let input = String::from("null");
         let expected = [Token::Null];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -328,7 +1831,7 @@ mod tests {
         let input = String::from("false");
         let expected = [Token::False];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -338,7 +1841,7 @@ mod tests {
         let input = String::from("true");
         let expected = [Token::True];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -348,7 +1851,7 @@ mod tests {
         let input = String::from("true,");
         let expected = [Token::True, Token::Comma];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -356,9 +1859,9 @@ mod tests {
     #[test]
     fn integer() {
         let input = String::from("123");
-        let expected = [Token::Number(123.0)];
+        let expected = [Token::Number(Number::I64(123))];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -366,19 +1869,56 @@ mod tests {
     #[test]
     fn floating_point() {
         let input = String::from("1.23");
-        let expected = [Token::Number(1.23)];
+        let expected = [Token::Number(Number::F64(1.23))];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn number_grammar() {
+        let valid = [
+            ("0", Number::I64(0)),
+            ("-0", Number::I64(0)),
+            ("0.5", Number::F64(0.5)),
+            ("-12.25", Number::F64(-12.25)),
+            ("1e3", Number::F64(1e3)),
+            ("2.5E-3", Number::F64(2.5e-3)),
+            ("-1e+2", Number::F64(-1e2)),
+        ];
+        for (input, expected) in valid {
+            assert_eq!(
+                tokenize(input),
+                Ok(vec![Token::Number(expected)]),
+                "{input}"
+            );
+        }
+
+        let invalid = [
+            ("-", TokenizeError::MissingIntegerDigits),
+            ("-.5", TokenizeError::MissingIntegerDigits),
+            ("01", TokenizeError::LeadingZero),
+            ("-007", TokenizeError::LeadingZero),
+            ("1.", TokenizeError::MissingFractionDigits),
+            ("1.e5", TokenizeError::MissingFractionDigits),
+            ("1e", TokenizeError::MissingExponentDigits),
+            ("1E+", TokenizeError::MissingExponentDigits),
+            ("1-2", TokenizeError::UnexpectedCharInNumber('-')),
+            ("1.2.3", TokenizeError::UnexpectedCharInNumber('.')),
+            ("1e2e3", TokenizeError::UnexpectedCharInNumber('e')),
+        ];
+        for (input, expected) in invalid {
+            assert_eq!(tokenize(input), Err(expected.locate(input, 0)), "{input}");
+        }
+    }
+
     #[test]
     fn negative_int() {
         let input = String::from("-123");
-        let expected = [Token::Number(-123.0)];
+        let expected = [Token::Number(Number::I64(-123))];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -388,7 +1928,7 @@ mod tests {
         let input = String::from("[null]");
         let expected = [Token::LeftBracket, Token::Null, Token::RightBracket];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -404,7 +1944,279 @@ mod tests {
             Token::RightBracket,
         ];
 
-        let actual = tokenize(input).unwrap();
+        let actual = tokenize(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn unfinished_literal_at_end() {
+        let input = String::from("[nul");
+        let expected = Err(TokenizeError::UnfinishedLiteralValue.locate(&input, 1));
+
+        let actual = tokenize(&input);
+
+        assert_eq!(actual, expected);
+    }
+
+    /// Returns one byte per read, so that every token is split between reads
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    /// Tokenizes all of the text from `reader`, a chunk at a time
+    fn tokenize_reader(
+        mut reader: impl Read,
+        options: &ParseOptions,
+    ) -> Result<ChunkTokenizer, ReadError> {
+        let mut chunk = vec![0; READ_CHUNK];
+        let mut tokenizer = ChunkTokenizer::new(options.clone());
+        loop {
+            let len = reader.read(&mut chunk)?;
+            tokenizer.push(&chunk[..len])?;
+            if len == 0 {
+                return Ok(tokenizer);
+            }
+        }
+    }
+
+    #[test]
+    fn reader_matches_tokenize() {
+        let inputs = [
+            r#"{"key": [null, true, false, -12.5, 1234567, "é 😀 \" \\"]}"#,
+            "12345",
+            "[tru]",
+            "\"unclosed",
+            "[1, @]",
+            "[1,\n  \"é\",\n  @]",
+            "{\"a\":\n \"b\nc\"}",
+            "[1,\n 2",
+        ];
+        for input in inputs {
+            let expected = tokenize(input);
+            let actual = tokenize_reader(OneByteReader(input.as_bytes()), &ParseOptions::default());
+
+            match actual {
+                Ok(tokenizer) => assert_eq!(Ok(tokenizer.tokens), expected, "{input}"),
+                Err(ReadError::Parse(err)) => assert_eq!(Err(err), expected, "{input}"),
+                Err(err) => panic!("{err:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn displays_tokens_as_written() {
+        let input = r#"{"a\tb": [null, -1.5, true]}"#;
+        let tokens = super::tokenize(input, &ParseOptions::default()).unwrap();
+        let written: Vec<_> = tokens.iter().map(Token::to_string).collect();
+        assert_eq!(written.concat(), r#"{"a\tb":[null,-1.5,true]}"#);
+    }
+
+    #[test]
+    fn spans_and_locations() {
+        let input = "{\"é\": [1,\n  true]}";
+        let tokens = super::tokenize_spanned(input, &ParseOptions::default()).unwrap();
+
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|token| &input[token.span.clone()])
+            .collect();
+        assert_eq!(spans, ["{", "\"é\"", ":", "[", "1", ",", "true", "]", "}"]);
+        let locations: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.location.line, token.location.column))
+            .collect();
+        let expected = [
+            (0, 0),
+            (0, 1),
+            (0, 4),
+            (0, 6),
+            (0, 7),
+            (0, 8),
+            (1, 2),
+            (1, 6),
+            (1, 7),
+        ];
+        assert_eq!(locations, expected);
+        assert_eq!(tokens[6].location.offset, tokens[6].span.start);
+        assert_eq!(tokens[1].token, Token::string("é"));
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let cases = [
+            ("[1,\n  \"é\",\n  @]", 14, 2, 2),
+            ("[\"é\", tru]", 7, 0, 6),
+            ("{\"a\":\n \"b\nc\"}", 9, 1, 3),
+            ("[1,\n \"ab", 5, 1, 1),
+            ("\n\n  -", 4, 2, 2),
+        ];
+        for (input, offset, line, column) in cases {
+            let location = tokenize(input).unwrap_err().location;
+            let expected = Location {
+                offset,
+                line,
+                column,
+            };
+            assert_eq!(location, Some(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn reader_drops_parsed_tokens() {
+        let input = format!("[{}1]", "1,".repeat(100_000));
+        let mut tokens = ReaderTokens::new(input.as_bytes(), &ParseOptions::default());
+        let mut index = 0;
+        while tokens.take(index).is_some() {
+            assert!(tokens.tokenizer.tokens.len() <= 2 * READ_CHUNK, "{index}");
+            index += 1;
+        }
+        assert_eq!(index, 200_003);
+        assert!(tokens.error.is_none());
+    }
+
+    #[test]
+    fn reader_invalid_utf8() {
+        let input = b"[\"abc\xFF\"]";
+        let actual = tokenize_reader(OneByteReader(input), &ParseOptions::default());
+
+        let expected = EncodingError {
+            encoding: Encoding::Utf8,
+            offset: 5,
+        };
+        assert!(matches!(
+            actual,
+            Err(ReadError::Parse(error)) if error == expected.into()
+        ));
+    }
+
+    #[test]
+    fn integer_beyond_f64_precision() {
+        let input = String::from("9007199254740993");
+        let expected = [Token::Number(Number::I64(9007199254740993))];
+
+        let actual = tokenize(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn invalid_unicode_escapes() {
+        let cases = [
+            (r#"["ok", "a\u12G4"]"#, 9),
+            (r#""\u12""#, 1),
+            (r#"{"\\\uD83C\u": 1}"#, 10),
+        ];
+        for (input, offset) in cases {
+            let expected = TokenizeError::InvalidUnicodeEscape { offset }.locate(input, 0);
+            assert_eq!(tokenize(input), Err(expected), "{input}");
+        }
+        assert!(tokenize(r#""\\u \u00e9""#).is_ok());
+    }
+
+    #[test]
+    fn inexact_integers() {
+        let options = ParseOptions::new().require_exact_integers(true);
+        let exact = [
+            "0",
+            "-0",
+            "9007199254740992",
+            "-9007199254740992",
+            "18446744073709551616",
+            "1.5",
+            "1e30",
+        ];
+        for input in exact {
+            assert!(super::tokenize(input, &options).is_ok(), "{input}");
+        }
+
+        let inexact = [
+            "9007199254740993",
+            "-9223372036854775807",
+            "18446744073709551615",
+            "100000000000000000000001",
+        ];
+        for input in inexact {
+            let expected = TokenizeError::PrecisionLoss(input.to_string()).locate(input, 0);
+            assert_eq!(super::tokenize(input, &options), Err(expected), "{input}");
+            assert!(tokenize(input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn tokens_are_read_on_demand() {
+        let options = ParseOptions::default();
+        let mut tokens = Tokens::new("[1, @] \"never read", &options);
+        assert_eq!(tokens.next(), Some(Ok(Token::LeftBracket)));
+        assert_eq!(tokens.next(), Some(Ok(Token::Number(Number::I64(1)))));
+        assert_eq!(tokens.next(), Some(Ok(Token::Comma)));
+        let expected = TokenizeError::CharNotRecognized('@').locate("[1, @] \"never read", 4);
+        assert_eq!(tokens.next(), Some(Err(expected)));
+        assert_eq!(tokens.next(), None);
+
+        let tokens: Vec<_> = Tokens::new("\u{feff} {} ", &options).collect();
+        assert_eq!(tokens, [Ok(Token::LeftBrace), Ok(Token::RightBrace)]);
+    }
+
+    #[test]
+    fn keeps_literal_beyond_f64() {
+        let input = String::from("[1.5e400, -1e999]");
+        let expected = [
+            Token::LeftBracket,
+            Token::Number(Number::Literal(String::from("1.5e400"))),
+            Token::Comma,
+            Token::Number(Number::Literal(String::from("-1e999"))),
+            Token::RightBracket,
+        ];
+
+        let actual = tokenize(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn integer_beyond_i64() {
+        let input = String::from("18446744073709551615");
+        let expected = [Token::Number(Number::U64(u64::MAX))];
+
+        let actual = tokenize(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn integer_beyond_u64() {
+        let input = String::from("18446744073709551616");
+        let expected = [Token::Number(Number::F64(18446744073709551616.0))];
+
+        let actual = tokenize(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary_precision")]
+    fn keeps_literal_beyond_u64() {
+        let input = String::from("[18446744073709551616, 0.1000000000000000000001]");
+        let expected = [
+            Token::LeftBracket,
+            Token::Number(Number::Literal(String::from("18446744073709551616"))),
+            Token::Comma,
+            Token::Number(Number::Literal(String::from("0.1000000000000000000001"))),
+            Token::RightBracket,
+        ];
+
+        let actual = tokenize(&input).unwrap();
 
         assert_eq!(actual, expected);
     }
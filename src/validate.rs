@@ -0,0 +1,208 @@
+//! Checking that text is valid JSON without parsing it, see [`validate`]
+
+use crate::tokenize::{trailing_error, Lexer};
+use crate::{Error, ParseOptions, TokenParseError, TokenizeError};
+
+/// What the validator expects to come next, as in the pull parser
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// A value, or the end of the array that was just opened
+    ArrayStart,
+    /// A key, or the end of the object that was just opened
+    ObjectStart,
+    /// A key, after a comma
+    Key,
+    /// A value, after a key or comma or at the start
+    Value,
+    /// A comma or the end of the innermost container
+    AfterValue,
+    /// The document is complete, and only whitespace may follow
+    Done,
+}
+
+/// Checks that `input` is a single valid JSON document, reporting the same
+/// errors as [`JsonReader`](crate::pull::JsonReader) would.
+///
+/// No tokens or [`Value`](crate::Value) are built: strings and numbers are
+/// checked where they are in the input, which suits a service that only
+/// needs to know that a document is well-formed before passing it on.
+pub fn validate(input: &str) -> Result<(), Error> {
+    validate_with(input, &ParseOptions::default())
+}
+
+/// Checks that `input` is a single valid JSON document like [`validate`],
+/// accepting what the given options allow
+pub fn validate_with(input: &str, options: &ParseOptions) -> Result<(), Error> {
+    let mut lexer = Lexer::new(input, options.clone());
+    let mut state = State::Value;
+    // whether each open container is an object, innermost last
+    let mut open = Vec::new();
+    // an error found at the token starting at the byte `offset`
+    let error = |err: TokenParseError, offset| Err(err.locate(input, || offset));
+
+    loop {
+        // the text after the document isn't looked at when it's allowed,
+        // and only its first token is otherwise
+        if state == State::Done {
+            return match lexer.peek_char() {
+                Some(_) if !options.allow_trailing_content => {
+                    Err(trailing_error(input, lexer.offset(), options))
+                }
+                _ => Ok(()),
+            };
+        }
+        let Some((token, start)) = lexer.skip_token()? else {
+            let err = match (state, open.last()) {
+                (State::Done, _) => unreachable!("checked before reading a token"),
+                (_, None) => TokenParseError::EmptyInput,
+                (_, Some(false)) => TokenParseError::UnclosedBracket,
+                (_, Some(true)) => TokenParseError::UnclosedBrace,
+            };
+            return error(err, input.len());
+        };
+
+        let close = match (state, token) {
+            (State::Done, _) => unreachable!("checked before reading a token"),
+            (State::ArrayStart, ']') | (State::ObjectStart, '}') => true,
+            (State::ArrayStart | State::Value, '[' | '{') => {
+                open.push(token == '{');
+                if options.max_depth.is_some_and(|max| open.len() > max) {
+                    return Err(TokenizeError::TooDeep.locate(input, start));
+                }
+                state = if token == '{' {
+                    State::ObjectStart
+                } else {
+                    State::ArrayStart
+                };
+                continue;
+            }
+            (State::ArrayStart | State::Value, ']' | '}' | ',' | ':') => {
+                return error(TokenParseError::ExpectedValue, start);
+            }
+            (State::ArrayStart | State::Value, _) => false,
+            (State::ObjectStart | State::Key, '"') => {
+                match lexer.skip_token()? {
+                    Some((':', _)) => {}
+                    Some((_, start)) => return error(TokenParseError::ExpectedColon, start),
+                    None => return error(TokenParseError::UnclosedBrace, input.len()),
+                }
+                state = State::Value;
+                continue;
+            }
+            (State::ObjectStart | State::Key, _) => {
+                return error(TokenParseError::ExpectedProperty, start);
+            }
+            (State::AfterValue, ',') => {
+                let closing = match open.last() {
+                    Some(true) => '}',
+                    _ => ']',
+                };
+                if lexer.peek_char() == Some(closing) {
+                    if !options.allow_trailing_commas {
+                        return error(TokenParseError::TrailingComma, start);
+                    }
+                    // the container is closed by the next token
+                    continue;
+                }
+                state = match open.last() {
+                    Some(true) => State::Key,
+                    _ => State::Value,
+                };
+                continue;
+            }
+            (State::AfterValue, ']') if open.last() == Some(&false) => true,
+            (State::AfterValue, '}') if open.last() == Some(&true) => true,
+            (State::AfterValue, _) => return error(TokenParseError::ExpectedComma, start),
+        };
+
+        // a value was completed, either a scalar or a closed container
+        if close {
+            open.pop();
+        }
+        state = if open.is_empty() {
+            State::Done
+        } else {
+            State::AfterValue
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use crate::{TokenParseError, TokenizeError};
+
+    #[test]
+    fn accepts_valid_documents() {
+        let inputs = [
+            "null",
+            " 1.5 ",
+            r#""esc\"aped é""#,
+            "[]",
+            "{}",
+            r#"{"a": [1, -2, {"b": [true, false, null]}], "c": {"d": "e"}}"#,
+        ];
+        for input in inputs {
+            assert_eq!(validate(input), Ok(()), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        let cases = [
+            ("", TokenParseError::EmptyInput.locate("", || 0)),
+            (
+                "[1, 2",
+                TokenParseError::UnclosedBracket.locate("[1, 2", || 5),
+            ),
+            (
+                r#"{"a": 1"#,
+                TokenParseError::UnclosedBrace.locate(r#"{"a": 1"#, || 7),
+            ),
+            ("[1,]", TokenParseError::TrailingComma.locate("[1,]", || 2)),
+            (
+                "[1 2]",
+                TokenParseError::ExpectedComma.locate("[1 2]", || 3),
+            ),
+            (
+                "1 2",
+                TokenParseError::UnexpectedTrailingTokens { offset: 2 }.locate("1 2", || 2),
+            ),
+            (
+                r#"{"a" 1}"#,
+                TokenParseError::ExpectedColon.locate(r#"{"a" 1}"#, || 5),
+            ),
+            (
+                r#"{"a""#,
+                TokenParseError::UnclosedBrace.locate(r#"{"a""#, || 4),
+            ),
+            (
+                r#"{1: 2}"#,
+                TokenParseError::ExpectedProperty.locate(r#"{1: 2}"#, || 1),
+            ),
+            (
+                r#"[1}"#,
+                TokenParseError::ExpectedComma.locate(r#"[1}"#, || 2),
+            ),
+            (
+                r#""\uZZZZ""#,
+                TokenizeError::InvalidUnicodeEscape { offset: 1 }.locate(r#""\uZZZZ""#, 0),
+            ),
+            (
+                r#""open"#,
+                TokenizeError::UnclosedQuotes.locate(r#""open"#, 0),
+            ),
+            (
+                "nul",
+                TokenizeError::UnfinishedLiteralValue.locate("nul", 0),
+            ),
+            (
+                "1.2.3",
+                TokenizeError::UnexpectedCharInNumber('.').locate("1.2.3", 0),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(validate(input), Err(expected), "{input}");
+        }
+    }
+}
@@ -0,0 +1,501 @@
+#[cfg(any(test, feature = "proptest"))]
+mod arbitrary;
+#[cfg(feature = "arc_str")]
+mod dedup;
+mod equivalent;
+mod flatten;
+mod path;
+mod profile;
+mod redact;
+mod remove;
+mod shared;
+mod sort;
+mod truncate;
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "arc_str")]
+use std::sync::Arc;
+
+use crate::map::{DefaultMapKind, MapKind, ObjectMap};
+use crate::Number;
+
+#[cfg(feature = "proptest")]
+pub use arbitrary::ArbitraryOptions;
+pub use equivalent::EqOptions;
+pub use flatten::UnflattenError;
+pub use path::InvalidPath;
+pub(crate) use path::{pointer_index, split_pointer};
+pub use profile::{PathProfile, Profile};
+pub use redact::REDACTED;
+pub use shared::SharedValue;
+pub use truncate::ELIDED;
+
+/// Storage for strings and object keys
+///
+/// This is a `String` by default. With the `arc_str` feature enabled, it is an
+/// `Arc<str>` instead, so cloning a `Value` shares the string data rather than
+/// copying it. Both dereference to `str` and can be created with `.into()`
+/// from a `String` or `&str`, which keeps code working with either setting.
+pub type Str = StrStorage;
+
+#[cfg(not(feature = "arc_str"))]
+type StrStorage = String;
+#[cfg(feature = "arc_str")]
+type StrStorage = Arc<str>;
+
+/// Representation of a JSON value
+///
+/// The [`MapKind`] parameter chooses the map type used for objects, see
+/// [`DefaultMapKind`] for the type used when it is left out.
+#[derive(Default)]
+pub enum Value<K: MapKind = DefaultMapKind> {
+    /// literal characters `null`
+    #[default]
+    Null,
+
+    /// literal characters `true` or `false`
+    Boolean(bool),
+
+    /// characters within double quotes "..."
+    String(Str),
+
+    /// integers stored exactly, other numbers as a 64-bit floating point
+    Number(Number),
+
+    /// Zero to many JSON values
+    Array(Vec<Value<K>>),
+
+    /// String keys with JSON values
+    Object(K::Map<Value<K>>),
+}
+
+impl<K: MapKind> Value<K> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `i64` if it is an integer within range, see
+    /// [`Number::as_i64`]
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.as_i64()
+    }
+
+    /// Returns the number as a `u64` if it is a non-negative integer within
+    /// range, see [`Number::as_u64`]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    /// Returns the number as an `i128` if it is an integer within range, see
+    /// [`Number::as_i128`]
+    pub fn as_i128(&self) -> Option<i128> {
+        self.as_number()?.as_i128()
+    }
+
+    /// Returns the number as a `u128` if it is a non-negative integer within
+    /// range, see [`Number::as_u128`]
+    pub fn as_u128(&self) -> Option<u128> {
+        self.as_number()?.as_u128()
+    }
+
+    /// Returns the number as an `f64`, which may round large integers
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(self.as_number()?.as_f64())
+    }
+
+    /// Whether this is a number with no fractional part
+    pub fn is_integer(&self) -> bool {
+        self.as_number().is_some_and(Number::is_integer)
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value<K>>> {
+        match self {
+            Value::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&K::Map<Value<K>>> {
+        match self {
+            Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Moves the value out, leaving `Null` in its place
+    pub fn take(&mut self) -> Value<K> {
+        std::mem::replace(self, Value::Null)
+    }
+
+    /// Moves the string out if this is a string, leaving `Null` in its
+    /// place. Other values are left unchanged.
+    pub fn take_string(&mut self) -> Option<Str> {
+        match self.take() {
+            Value::String(s) => Some(s),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    /// Moves the elements out if this is an array, leaving `Null` in its
+    /// place. Other values are left unchanged.
+    pub fn take_array(&mut self) -> Option<Vec<Value<K>>> {
+        match self.take() {
+            Value::Array(array) => Some(array),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    /// Moves the map out if this is an object, leaving `Null` in its place.
+    /// Other values are left unchanged.
+    pub fn take_object(&mut self) -> Option<K::Map<Value<K>>> {
+        match self.take() {
+            Value::Object(map) => Some(map),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+}
+
+// The standard traits are implemented by hand because deriving them would
+// require `K` itself to implement them, while only the map type matters.
+
+impl<K: MapKind> Clone for Value<K> {
+    fn clone(&self) -> Self {
+        match self {
+            Value::Null => Value::Null,
+            Value::Boolean(b) => Value::Boolean(*b),
+            Value::String(s) => Value::String(s.clone()),
+            Value::Number(n) => Value::Number(n.clone()),
+            Value::Array(array) => Value::Array(array.clone()),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<K: MapKind> PartialEq for Value<K> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, value)| b.get(key).is_some_and(|other| value == other))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// See [`Number`] for how numbers are compared, in particular `NaN`
+impl<K: MapKind> Eq for Value<K> {}
+
+/// Values are hashed consistently with `PartialEq`. Numbers are hashed as
+/// described on [`Number`], and objects are hashed without regard to the
+/// order of their entries.
+impl<K: MapKind> Hash for Value<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Boolean(b) => b.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Number(n) => n.hash(state),
+            Value::Array(array) => array.hash(state),
+            Value::Object(map) => {
+                // entries are hashed on their own and combined with a
+                // commutative sum, since maps may iterate in any order
+                let entries = map.iter().fold(0u64, |sum, entry| {
+                    let mut hasher = DefaultHasher::new();
+                    entry.hash(&mut hasher);
+                    sum.wrapping_add(hasher.finish())
+                });
+                map.len().hash(state);
+                entries.hash(state);
+            }
+        }
+    }
+}
+
+impl<K: MapKind> Value<K> {
+    /// Position of the variant in the total order of values
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+}
+
+/// Values are ordered first by type, as
+/// `null < boolean < number < string < array < object`, then by value:
+///
+/// - numbers as described on [`Number`]
+/// - strings and arrays lexicographically
+/// - objects by their entries sorted by key, compared as a list of
+///   key/value pairs
+impl<K: MapKind> Ord for Value<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a.cmp(b),
+            (Value::Object(a), Value::Object(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort_unstable_by_key(|(key, _)| *key);
+                b.sort_unstable_by_key(|(key, _)| *key);
+                a.cmp(&b)
+            }
+            (a, b) => a.type_rank().cmp(&b.type_rank()),
+        }
+    }
+}
+
+impl<K: MapKind> PartialOrd for Value<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: MapKind> fmt::Debug for Value<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("Null"),
+            Value::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            Value::String(s) => f.debug_tuple("String").field(s).finish(),
+            Value::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            Value::Array(array) => f.debug_tuple("Array").field(array).finish(),
+            Value::Object(map) => f.debug_tuple("Object").field(&DebugMap::<K>(map)).finish(),
+        }
+    }
+}
+
+struct DebugMap<'a, K: MapKind>(&'a K::Map<Value<K>>);
+
+impl<K: MapKind> fmt::Debug for DebugMap<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+impl Value {
+    pub(crate) fn object<const N: usize>(pairs: [(&'static str, Self); N]) -> Self {
+        let owned_pairs = pairs.map(|(key, value)| (Str::from(key), value));
+        let map = crate::Map::from(owned_pairs);
+        Self::Object(map)
+    }
+
+    pub(crate) fn string(s: &str) -> Self {
+        Self::String(Str::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::collections::{BTreeSet, HashSet};
+
+    use crate::{parse, Number, OrderedMap, OrderedMapKind, Value};
+
+    #[test]
+    fn sorts_by_type_then_value() {
+        let mut values: Vec<Value> = [
+            "[1]", "{}", "\"b\"", "2", "true", "\"a\"", "null", "-1", "[]",
+        ]
+        .into_iter()
+        .map(|input| parse(String::from(input)).unwrap())
+        .collect();
+        values.sort();
+
+        let sorted: Vec<String> = values.iter().map(Value::to_string).collect();
+        let expected = [
+            "null", "true", "-1", "2", "\"a\"", "\"b\"", "[]", "[1]", "{}",
+        ];
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn orders_objects_by_sorted_entries() {
+        let first: Value<OrderedMapKind> = Value::Object(OrderedMap::from([
+            ("b".into(), 1.into()),
+            ("a".into(), 2.into()),
+        ]));
+        let second: Value<OrderedMapKind> = Value::Object(OrderedMap::from([
+            ("a".into(), 2.into()),
+            ("b".into(), 2.into()),
+        ]));
+        let reordered: Value<OrderedMapKind> = Value::Object(OrderedMap::from([
+            ("a".into(), 2.into()),
+            ("b".into(), 1.into()),
+        ]));
+
+        assert!(first < second);
+        assert_eq!(first.cmp(&reordered), Ordering::Equal);
+
+        let set = BTreeSet::from([first, second, reordered]);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn take_leaves_null() {
+        let mut value = parse(String::from(r#"{"name": "ken", "tags": [1, 2]}"#)).unwrap();
+
+        let mut object = value.take_object().unwrap();
+        assert!(value.is_null());
+
+        let name = object.get_mut("name").unwrap().take_string();
+        let tags = object.get_mut("tags").unwrap().take_array();
+
+        assert_eq!(name.as_deref(), Some("ken"));
+        assert_eq!(tags, Some(vec![Value::from(1), Value::from(2)]));
+        let expected = Value::object([("name", Value::Null), ("tags", Value::Null)]);
+        assert_eq!(Value::Object(object), expected);
+    }
+
+    #[test]
+    fn take_wrong_type_is_unchanged() {
+        let mut value = Value::string("ken");
+
+        assert_eq!(value.take_array(), None);
+        assert_eq!(value.take_object(), None);
+        assert_eq!(value, Value::string("ken"));
+    }
+
+    #[test]
+    fn hash_set_deduplicates_values() {
+        let inputs = [
+            r#"{"a": [1, 2.5], "b": null}"#,
+            r#"{"b": null, "a": [1, 2.5]}"#,
+            r#"{"a": [1, 2.5], "b": false}"#,
+        ];
+        let set: HashSet<Value> = inputs
+            .into_iter()
+            .map(|input| parse(String::from(input)).unwrap())
+            .collect();
+
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn hash_ignores_object_order() {
+        let first: Value<OrderedMapKind> = Value::Object(OrderedMap::from([
+            ("a".into(), Value::Null),
+            ("b".into(), 1.into()),
+        ]));
+        let second: Value<OrderedMapKind> = Value::Object(OrderedMap::from([
+            ("b".into(), 1.into()),
+            ("a".into(), Value::Null),
+        ]));
+
+        let set = HashSet::from([first, second]);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn hash_numbers_by_value() {
+        let set: HashSet<Value> = HashSet::from([
+            Value::Number(Number::I64(5)),
+            Value::Number(Number::U64(5)),
+            Value::Number(Number::F64(0.0)),
+            Value::Number(Number::F64(-0.0)),
+        ]);
+
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn numeric_accessors() {
+        let value: Value = Value::from(-3);
+
+        assert_eq!(value.as_i64(), Some(-3));
+        assert_eq!(value.as_u64(), None);
+        assert_eq!(value.as_f64(), Some(-3.0));
+        assert!(value.is_integer());
+    }
+
+    #[test]
+    fn numeric_accessors_on_float() {
+        let value: Value = Value::Number(Number::F64(2.5));
+
+        assert_eq!(value.as_i64(), None);
+        assert_eq!(value.as_f64(), Some(2.5));
+        assert!(!value.is_integer());
+    }
+
+    #[test]
+    fn numeric_accessors_on_non_number() {
+        let value = Value::string("1");
+
+        assert_eq!(value.as_i64(), None);
+        assert_eq!(value.as_f64(), None);
+        assert!(!value.is_integer());
+        assert_eq!(value.as_string(), Some("1"));
+    }
+
+    #[test]
+    #[cfg(feature = "arc_str")]
+    fn clone_shares_strings() {
+        use std::sync::Arc;
+
+        let value = Value::object([("key", Value::string("value"))]);
+        let cloned = value.clone();
+
+        let (Value::Object(map), Value::Object(cloned_map)) = (&value, &cloned) else {
+            panic!("expected objects");
+        };
+        let (key, Value::String(string)) = map.iter().next().unwrap() else {
+            panic!("expected a string");
+        };
+        let (cloned_key, Value::String(cloned_string)) = cloned_map.iter().next().unwrap() else {
+            panic!("expected a string");
+        };
+        assert!(Arc::ptr_eq(key, cloned_key));
+        assert!(Arc::ptr_eq(string, cloned_string));
+    }
+}
@@ -0,0 +1,184 @@
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::BoxedStrategy;
+
+use crate::map::MapKind;
+use crate::{Number, Str};
+
+use super::Value;
+
+/// Limits on the documents generated by [`Value`]'s implementation of
+/// proptest's `Arbitrary`, passed to `any_with::<Value>(options)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryOptions {
+    /// Most levels of arrays and objects nested inside each other
+    pub max_depth: u32,
+
+    /// Number of values that a document is aimed to have in total. Arrays
+    /// and objects stop being generated once it's reached.
+    pub max_size: u32,
+
+    /// Most elements of an array or entries of an object
+    pub max_items: usize,
+
+    /// Longest string or object key, in characters
+    pub max_string_len: usize,
+}
+
+/// Documents nested at most 4 deep, with about 64 values in total
+impl Default for ArbitraryOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_size: 64,
+            max_items: 8,
+            max_string_len: 16,
+        }
+    }
+}
+
+/// Generates any value that can be written as JSON and parsed back to an
+/// equal value. Numbers are always finite.
+///
+/// Strings and keys are drawn from all of Unicode, favoring the characters
+/// that JSON escapes.
+impl<K: MapKind + 'static> Arbitrary for Value<K> {
+    type Parameters = ArbitraryOptions;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(options: ArbitraryOptions) -> Self::Strategy {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Boolean),
+            number().prop_map(Value::Number),
+            string(options.max_string_len).prop_map(Value::String),
+        ];
+        let max_items = options.max_items;
+        let max_string_len = options.max_string_len;
+        leaf.prop_recursive(
+            options.max_depth,
+            options.max_size,
+            max_items as u32,
+            move |inner| {
+                prop_oneof![
+                    vec(inner.clone(), 0..=max_items).prop_map(Value::Array),
+                    vec((string(max_string_len), inner), 0..=max_items)
+                        .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+                ]
+            },
+        )
+        .boxed()
+    }
+}
+
+fn number() -> impl Strategy<Value = Number> {
+    let float = prop::num::f64::POSITIVE
+        | prop::num::f64::NEGATIVE
+        | prop::num::f64::NORMAL
+        | prop::num::f64::SUBNORMAL
+        | prop::num::f64::ZERO;
+    prop_oneof![
+        any::<i64>().prop_map(Number::I64),
+        (i64::MAX as u64 + 1..=u64::MAX).prop_map(Number::U64),
+        float.prop_map(Number::F64),
+        // whole floats, which have to be written as floats to parse back
+        any::<i32>().prop_map(|n| Number::F64(n.into())),
+    ]
+}
+
+fn string(max_len: usize) -> impl Strategy<Value = Str> {
+    vec(any::<char>(), 0..=max_len).prop_map(|chars| chars.into_iter().collect::<String>().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::differential::parse_everywhere;
+    use crate::lazy::LazyValue;
+    use crate::tape::Tape;
+    use crate::{
+        parse, parse_bytes, parse_reader, tokenize_spanned, validate, BorrowedValue, ParseOptions,
+        PushParser, Value,
+    };
+
+    proptest! {
+        #[test]
+        fn round_trips(value: Value) {
+            let text = value.to_string();
+            prop_assert_eq!(parse(&text).unwrap(), value);
+        }
+
+        #[test]
+        fn every_backend_parses_serialized_values(value: Value) {
+            let text = value.to_string();
+            prop_assert_eq!(validate(&text), Ok(()));
+            prop_assert_eq!(&BorrowedValue::parse(&text).unwrap().to_value(), &value);
+            prop_assert_eq!(&LazyValue::parse(&text).unwrap().to_value().unwrap(), &value);
+            prop_assert_eq!(&Tape::parse(&text).unwrap().to_value(), &value);
+            prop_assert_eq!(&parse_bytes(text.as_bytes()).unwrap(), &value);
+            prop_assert_eq!(&parse_reader(text.as_bytes()).unwrap(), &value);
+            let mut parser = PushParser::new();
+            parser.feed(text.as_bytes()).unwrap();
+            prop_assert_eq!(&parser.finish().unwrap(), &value);
+        }
+
+        #[test]
+        fn tokens_cover_serialized_values(value: Value) {
+            // compact output has no whitespace, so the tokens make up all of it
+            let text = value.to_string();
+            let tokens = tokenize_spanned(&text, &ParseOptions::default()).unwrap();
+            let rebuilt: String = tokens.into_iter().map(|token| &text[token.span]).collect();
+            prop_assert_eq!(rebuilt, text);
+        }
+
+        #[test]
+        fn backends_agree(input in text()) {
+            let results = parse_everywhere(&input, &ParseOptions::default());
+            // only the values are compared, since the kind of error for text
+            // this far from JSON can depend on the order things are checked in
+            let expected = results[0].1.clone().ok();
+            for (backend, result) in results {
+                let value = match result {
+                    Ok(None) => expected.clone(),
+                    result => result.ok(),
+                };
+                prop_assert_eq!(value, expected.clone(), "{:?}", backend);
+            }
+        }
+    }
+
+    /// Text made of pieces of JSON, which is sometimes valid but mostly
+    /// close to it
+    fn text() -> impl Strategy<Value = String> {
+        const PIECES: &[&str] = &[
+            "{",
+            "}",
+            "[",
+            "]",
+            ",",
+            ":",
+            " ",
+            "\"",
+            "\\",
+            "\"a\"",
+            "\"\\u00e9\"",
+            "\"\\ud800\"",
+            "0",
+            "-",
+            "1",
+            ".5",
+            "e3",
+            "true",
+            "false",
+            "null",
+            "nul",
+            "é",
+            "\u{feff}",
+            "/",
+        ];
+        proptest::collection::vec(proptest::sample::select(PIECES), 0..16)
+            .prop_map(|pieces| pieces.concat())
+    }
+}
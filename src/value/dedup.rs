@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::map::MapKind;
+
+use super::Value;
+
+impl<K: MapKind> Value<K> {
+    /// Makes equal strings and object keys throughout the document share a
+    /// single allocation, which can shrink machine-generated documents with
+    /// many repeated keys or values considerably.
+    ///
+    /// Only strings are shared. Objects and arrays are owned by their parent,
+    /// so identical subtrees still each have their own containers, unless
+    /// the document is copied with [`Value::to_shared`].
+    pub fn dedup(&mut self) {
+        let mut strings = HashSet::new();
+        self.dedup_with(&mut strings);
+    }
+
+    fn dedup_with(&mut self, strings: &mut HashSet<Arc<str>>) {
+        match self {
+            Value::String(s) => *s = intern(s, strings),
+            Value::Array(array) => {
+                for value in array {
+                    value.dedup_with(strings);
+                }
+            }
+            Value::Object(map) => {
+                // keys can't be replaced in place, so the map is rebuilt in
+                // the same order
+                *map = std::mem::take(map)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        value.dedup_with(strings);
+                        (intern(&key, strings), value)
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn intern(s: &Arc<str>, strings: &mut HashSet<Arc<str>>) -> Arc<str> {
+    match strings.get(s) {
+        Some(existing) => Arc::clone(existing),
+        None => {
+            strings.insert(Arc::clone(s));
+            Arc::clone(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{parse, Value};
+
+    fn string(value: &Value) -> &Arc<str> {
+        match value {
+            Value::String(s) => s,
+            _ => panic!("expected a string"),
+        }
+    }
+
+    #[test]
+    fn shares_equal_strings() {
+        let mut value = parse(String::from(
+            r#"[{"status": "active"}, {"status": "active"}, "active"]"#,
+        ))
+        .unwrap();
+        value.dedup();
+
+        let Value::Array(array) = &value else {
+            panic!("expected an array");
+        };
+        let (Value::Object(first), Value::Object(second)) = (&array[0], &array[1]) else {
+            panic!("expected objects");
+        };
+        let (first_key, first_value) = first.iter().next().unwrap();
+        let (second_key, second_value) = second.iter().next().unwrap();
+
+        assert!(Arc::ptr_eq(first_key, second_key));
+        assert!(Arc::ptr_eq(string(first_value), string(second_value)));
+        assert!(Arc::ptr_eq(string(first_value), string(&array[2])));
+    }
+
+    #[test]
+    fn dedup_keeps_content() {
+        let input = r#"{"a": ["x", "x", {"a": "a"}], "b": null}"#;
+        let mut value = parse(String::from(input)).unwrap();
+        value.dedup();
+
+        assert_eq!(value, parse(String::from(input)).unwrap());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::map::{MapKind, ObjectMap};
+
+use super::Value;
+
+/// Configuration for [`Value::equivalent`]
+///
+/// With every option turned off, `equivalent` is the same as `==`. Object key
+/// order is never significant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EqOptions {
+    /// Compare arrays as multisets, so `[1, 2, 2]` is equivalent to
+    /// `[2, 1, 2]` but not to `[1, 2]`
+    pub ignore_array_order: bool,
+
+    /// Treat an object entry whose value is `null` the same as a missing
+    /// key, so `{"a": 1, "b": null}` is equivalent to `{"a": 1}`
+    pub null_as_missing: bool,
+}
+
+impl<K: MapKind> Value<K> {
+    /// Compares two values structurally, with some differences that are
+    /// usually insignificant (such as the order of array elements) ignored
+    /// according to `options`
+    pub fn equivalent(&self, other: &Value<K>, options: EqOptions) -> bool {
+        match (self, other) {
+            (Value::Array(a), Value::Array(b)) if options.ignore_array_order => {
+                a.len() == b.len() && same_elements(a, b, options)
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.equivalent(b, options))
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                let matches = |a: &K::Map<Value<K>>, b: &K::Map<Value<K>>| {
+                    a.iter().all(|(key, value)| match b.get(key) {
+                        Some(other) => value.equivalent(other, options),
+                        None => options.null_as_missing && value.is_null(),
+                    })
+                };
+                // without `null_as_missing` every key must be in both, so
+                // checking the lengths avoids a second pass
+                if options.null_as_missing {
+                    matches(a, b) && matches(b, a)
+                } else {
+                    a.len() == b.len() && matches(a, b)
+                }
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Matches every element of `a` to a different equivalent element of `b`
+fn same_elements<K: MapKind>(a: &[Value<K>], b: &[Value<K>], options: EqOptions) -> bool {
+    let mut unmatched: Vec<&Value<K>> = b.iter().collect();
+    a.iter().all(|value| {
+        match unmatched
+            .iter()
+            .position(|other| value.equivalent(other, options))
+        {
+            Some(index) => {
+                unmatched.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EqOptions;
+    use crate::{parse, Value};
+
+    fn value(input: &str) -> Value {
+        parse(String::from(input)).unwrap()
+    }
+
+    fn check(a: &str, b: &str, options: EqOptions, expected: bool) {
+        assert_eq!(value(a).equivalent(&value(b), options), expected);
+        assert_eq!(value(b).equivalent(&value(a), options), expected);
+    }
+
+    #[test]
+    fn default_is_equality() {
+        let options = EqOptions::default();
+
+        check(
+            r#"{"a": [1, 2], "b": null}"#,
+            r#"{"b": null, "a": [1, 2]}"#,
+            options,
+            true,
+        );
+        check("[1, 2]", "[2, 1]", options, false);
+        check(r#"{"a": 1, "b": null}"#, r#"{"a": 1}"#, options, false);
+    }
+
+    #[test]
+    fn arrays_as_multisets() {
+        let options = EqOptions {
+            ignore_array_order: true,
+            ..EqOptions::default()
+        };
+
+        check("[1, 2, 2]", "[2, 1, 2]", options, true);
+        check("[1, 2, 2]", "[1, 1, 2]", options, false);
+        check("[1, 2]", "[1, 2, 2]", options, false);
+        check(
+            r#"[{"a": [3, 4]}, 5]"#,
+            r#"[5, {"a": [4, 3]}]"#,
+            options,
+            true,
+        );
+    }
+
+    #[test]
+    fn null_as_missing() {
+        let options = EqOptions {
+            null_as_missing: true,
+            ..EqOptions::default()
+        };
+
+        check(r#"{"a": 1, "b": null}"#, r#"{"a": 1}"#, options, true);
+        check(r#"{"a": null}"#, r#"{"b": null}"#, options, true);
+        check(r#"{"a": 1, "b": false}"#, r#"{"a": 1}"#, options, false);
+        check(r#"[{"a": {"b": null}}]"#, r#"[{"a": {}}]"#, options, true);
+        check("[null]", "[]", options, false);
+    }
+}
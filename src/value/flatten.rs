@@ -0,0 +1,277 @@
+use std::fmt;
+
+use crate::map::{MapKind, ObjectMap, OrderedMap};
+
+use super::path::{split_path, Segment};
+use super::Value;
+
+/// One of the possible errors that could occur while unflattening an object
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UnflattenError {
+    /// Only an object of flattened keys can be unflattened
+    NotAnObject,
+
+    /// Key could not be split into `.key` and `[index]` segments
+    InvalidKey(String),
+
+    /// Key refers to a location that another key already assigned
+    Conflict(String),
+}
+
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnflattenError::NotAnObject => f.write_str("only an object can be unflattened"),
+            UnflattenError::InvalidKey(key) => write!(f, "invalid flattened key {key:?}"),
+            UnflattenError::Conflict(key) => {
+                write!(f, "flattened key {key:?} conflicts with another key")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnflattenError {}
+
+impl<K: MapKind> Value<K> {
+    /// Flattens nested objects and arrays into a single-level object.
+    ///
+    /// Object keys are joined with `.` and array indexes are written as
+    /// `[index]`, so `{"a": {"b": [1]}}` becomes `{"a.b[0]": 1}`. Empty
+    /// objects and arrays are kept as values so that [`Value::unflatten`]
+    /// can restore them. Non-container values are stored under the key `""`.
+    ///
+    /// Keys that themselves contain `.`, `[` or `]` cannot be told apart from
+    /// nesting, so such documents will not round-trip.
+    pub fn flatten(&self) -> Value<K> {
+        let mut map = K::Map::default();
+        flatten_into(self, String::new(), &mut map);
+        Value::Object(map)
+    }
+
+    /// Reverses [`Value::flatten`], rebuilding nested objects and arrays from
+    /// dotted/bracketed keys. Array indexes that are never assigned are filled
+    /// with `null`.
+    pub fn unflatten(&self) -> Result<Value<K>, UnflattenError> {
+        let map = match self {
+            Value::Object(map) => map,
+            _ => return Err(UnflattenError::NotAnObject),
+        };
+
+        let mut root = Node::Vacant;
+        for (key, value) in map.iter() {
+            let segments =
+                split_path(key).map_err(|_| UnflattenError::InvalidKey(key.to_string()))?;
+            root.insert(&segments, value.clone(), key)?;
+        }
+
+        Ok(match root {
+            // `{}` flattens to `{}`
+            Node::Vacant => Value::Object(K::Map::default()),
+            node => node.into_value(),
+        })
+    }
+}
+
+fn flatten_into<K: MapKind>(value: &Value<K>, prefix: String, map: &mut K::Map<Value<K>>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object.iter() {
+                let path = if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_into(value, path, map);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (i, value) in array.iter().enumerate() {
+                flatten_into(value, format!("{prefix}[{i}]"), map);
+            }
+        }
+        // the root `{}` is already represented by the empty output object
+        Value::Object(_) if prefix.is_empty() => {}
+        value => {
+            map.insert(prefix.into(), value.clone());
+        }
+    }
+}
+
+/// Intermediate tree used while unflattening, which distinguishes locations
+/// that have not been assigned yet from explicit `null` values
+enum Node<K: MapKind> {
+    Vacant,
+    Leaf(Value<K>),
+    Object(OrderedMap<String, Node<K>>),
+    Array(Vec<Node<K>>),
+}
+
+impl<K: MapKind> Node<K> {
+    fn insert(
+        &mut self,
+        segments: &[Segment],
+        value: Value<K>,
+        key: &str,
+    ) -> Result<(), UnflattenError> {
+        let conflict = || UnflattenError::Conflict(key.to_string());
+
+        let (first, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => {
+                return match self {
+                    Node::Vacant => {
+                        *self = Node::Leaf(value);
+                        Ok(())
+                    }
+                    _ => Err(conflict()),
+                }
+            }
+        };
+
+        match first {
+            Segment::Key(name) => {
+                if let Node::Vacant = self {
+                    *self = Node::Object(OrderedMap::new());
+                }
+                match self {
+                    Node::Object(map) => {
+                        if !map.contains_key(name) {
+                            map.insert(name.clone(), Node::Vacant);
+                        }
+                        map.get_mut(name)
+                            .expect("entry was just inserted")
+                            .insert(rest, value, key)
+                    }
+                    _ => Err(conflict()),
+                }
+            }
+            Segment::Index(index) => {
+                if let Node::Vacant = self {
+                    *self = Node::Array(Vec::new());
+                }
+                match self {
+                    Node::Array(array) => {
+                        if array.len() <= *index {
+                            array.resize_with(index + 1, || Node::Vacant);
+                        }
+                        array[*index].insert(rest, value, key)
+                    }
+                    _ => Err(conflict()),
+                }
+            }
+        }
+    }
+
+    fn into_value(self) -> Value<K> {
+        match self {
+            Node::Vacant => Value::Null,
+            Node::Leaf(value) => value,
+            Node::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, node)| (key.into(), node.into_value()))
+                    .collect(),
+            ),
+            Node::Array(array) => Value::Array(array.into_iter().map(Node::into_value).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Number, Value};
+
+    use super::UnflattenError;
+
+    fn check(input: Value, expected: Value) {
+        let flattened = input.flatten();
+        assert_eq!(flattened, expected);
+
+        let unflattened = flattened.unflatten().unwrap();
+        assert_eq!(unflattened, input);
+    }
+
+    #[test]
+    fn flattens_nested_object() {
+        let input = Value::object([("a", Value::object([("b", Value::Number(Number::I64(1)))]))]);
+        let expected = Value::object([("a.b", Value::Number(Number::I64(1)))]);
+
+        check(input, expected);
+    }
+
+    #[test]
+    fn flattens_array_in_object() {
+        let input = Value::object([(
+            "a",
+            Value::object([(
+                "b",
+                Value::Array(vec![Value::Number(Number::I64(1)), Value::Null]),
+            )]),
+        )]);
+        let expected = Value::object([
+            ("a.b[0]", Value::Number(Number::I64(1))),
+            ("a.b[1]", Value::Null),
+        ]);
+
+        check(input, expected);
+    }
+
+    #[test]
+    fn flattens_top_level_array() {
+        let input = Value::Array(vec![
+            Value::Array(vec![Value::Boolean(true)]),
+            Value::object([("key", Value::string("value"))]),
+        ]);
+        let expected = Value::object([
+            ("[0][0]", Value::Boolean(true)),
+            ("[1].key", Value::string("value")),
+        ]);
+
+        check(input, expected);
+    }
+
+    #[test]
+    fn keeps_empty_containers() {
+        let input = Value::object([("a", Value::object([])), ("b", Value::Array(vec![]))]);
+        let expected = Value::object([("a", Value::object([])), ("b", Value::Array(vec![]))]);
+
+        check(input, expected);
+    }
+
+    #[test]
+    fn flattens_scalar_and_empty_root() {
+        check(Value::Null, Value::object([("", Value::Null)]));
+        check(Value::object([]), Value::object([]));
+        check(
+            Value::Array(vec![]),
+            Value::object([("", Value::Array(vec![]))]),
+        );
+    }
+
+    #[test]
+    fn unflatten_fills_missing_indexes() {
+        let input = Value::object([("[2]", Value::Number(Number::I64(3)))]);
+        let expected = Value::Array(vec![
+            Value::Null,
+            Value::Null,
+            Value::Number(Number::I64(3)),
+        ]);
+
+        assert_eq!(input.unflatten().unwrap(), expected);
+    }
+
+    #[test]
+    fn unflatten_conflicting_keys() {
+        let input = Value::object([("a", Value::Null), ("a.b", Value::Null)]);
+        let actual = input.unflatten().unwrap_err();
+
+        assert!(matches!(actual, UnflattenError::Conflict(_)));
+    }
+
+    #[test]
+    fn unflatten_not_an_object() {
+        let input: Value = Value::Null;
+        let actual = input.unflatten().unwrap_err();
+
+        assert_eq!(actual, UnflattenError::NotAnObject);
+    }
+}
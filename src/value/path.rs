@@ -0,0 +1,202 @@
+use std::fmt;
+
+use crate::map::{MapKind, ObjectMap};
+
+use super::Value;
+
+/// A path could not be split into `.key` and `[index]` segments
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InvalidPath(pub String);
+
+impl fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidPath {}
+
+/// One step of a path such as `a.b[0]`, the notation used by
+/// [`Value::flatten`](super::Value::flatten)
+#[derive(Debug, PartialEq)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+pub(crate) fn split_path(path: &str) -> Result<Vec<Segment>, InvalidPath> {
+    let invalid = || InvalidPath(path.to_string());
+
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+    // a name is expected at the start and after each `.`
+    let mut expect_name = true;
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            '[' => {
+                chars.next();
+                let mut digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) if c.is_ascii_digit() => digits.push(c),
+                        _ => return Err(invalid()),
+                    }
+                }
+                let index = digits.parse().map_err(|_| invalid())?;
+                segments.push(Segment::Index(index));
+                expect_name = false;
+            }
+            '.' if !expect_name => {
+                chars.next();
+                expect_name = true;
+            }
+            _ if expect_name => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    if c == ']' {
+                        return Err(invalid());
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Key(name));
+                expect_name = false;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    // a trailing `.` is missing its name, the empty path is the root itself
+    if expect_name && !path.is_empty() {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+/// Splits a JSON Pointer (RFC 6901) such as `/a/b~1c/0` into its unescaped
+/// tokens, ex. `["a", "b/c", "0"]`
+///
+/// Whether a token is an object key or an array index depends on the value it
+/// is applied to, see [`pointer_index`].
+pub(crate) fn split_pointer(pointer: &str) -> Result<Vec<String>, InvalidPath> {
+    let invalid = || InvalidPath(pointer.to_string());
+
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(pointer) = pointer.strip_prefix('/') else {
+        return Err(invalid());
+    };
+
+    pointer
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(ch) = chars.next() {
+                match ch {
+                    '~' => match chars.next() {
+                        Some('0') => unescaped.push('~'),
+                        Some('1') => unescaped.push('/'),
+                        _ => return Err(invalid()),
+                    },
+                    ch => unescaped.push(ch),
+                }
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// Reads a pointer token as an array index, which must be digits without a
+/// leading zero
+pub(crate) fn pointer_index(token: &str) -> Option<usize> {
+    let is_digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl<K: MapKind> Value<K> {
+    pub(super) fn get_tokens(&self, tokens: &[String]) -> Option<&Value<K>> {
+        tokens.iter().try_fold(self, |value, token| match value {
+            Value::Object(map) => map.get(token),
+            Value::Array(array) => array.get(pointer_index(token)?),
+            _ => None,
+        })
+    }
+
+    pub(super) fn get_segments_mut(&mut self, segments: &[Segment]) -> Option<&mut Value<K>> {
+        segments
+            .iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Value::Object(map), Segment::Key(key)) => map.get_mut(key),
+                (Value::Array(array), Segment::Index(index)) => array.get_mut(*index),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pointer_index, split_path, split_pointer, InvalidPath, Segment};
+
+    #[test]
+    fn splits_paths() {
+        let expected = vec![
+            Segment::Key(String::from("a")),
+            Segment::Index(10),
+            Segment::Index(0),
+            Segment::Key(String::from("b")),
+        ];
+
+        assert_eq!(split_path("a[10][0].b").unwrap(), expected);
+    }
+
+    #[test]
+    fn splits_empty_path() {
+        assert_eq!(split_path("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn splits_pointers() {
+        let expected = vec![String::from("a"), String::from("b/c~"), String::from("")];
+
+        assert_eq!(split_pointer("/a/b~1c~0/").unwrap(), expected);
+        assert_eq!(split_pointer("").unwrap(), Vec::<String>::new());
+        assert_eq!(split_pointer("a/b"), Err(InvalidPath(String::from("a/b"))));
+        assert_eq!(
+            split_pointer("/a~2"),
+            Err(InvalidPath(String::from("/a~2")))
+        );
+    }
+
+    #[test]
+    fn pointer_indices() {
+        assert_eq!(pointer_index("0"), Some(0));
+        assert_eq!(pointer_index("12"), Some(12));
+        assert_eq!(pointer_index("01"), None);
+        assert_eq!(pointer_index("-"), None);
+        assert_eq!(pointer_index(""), None);
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        for path in ["a.", ".a", "a..b", "a[x]", "a[0", "a]", "[0]b"] {
+            assert_eq!(
+                split_path(path),
+                Err(InvalidPath(String::from(path))),
+                "{path}"
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::map::{MapKind, ObjectMap, OrderedMap};
+
+use super::Value;
+
+/// How often each path appears in a document and which types its values
+/// have, see [`Value::profile`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    /// Statistics for each path, in the order the paths were first seen
+    pub paths: OrderedMap<String, PathProfile>,
+}
+
+/// Statistics for one path of a [`Profile`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathProfile {
+    /// Number of values found at the path
+    pub count: usize,
+
+    /// Number of values of each type: `null`, `boolean`, `integer` (numbers
+    /// without a fractional part), `number`, `string`, `array` or `object`
+    pub types: BTreeMap<&'static str, usize>,
+}
+
+impl<K: MapKind> Value<K> {
+    /// Counts how many times every path appears in the document and which
+    /// types its values have.
+    ///
+    /// Paths use the notation of [`Value::flatten`] except that array indexes
+    /// are left out, so the fields of every record in an array are counted
+    /// together, ex. `users[].email`. The root is the path `""`.
+    pub fn profile(&self) -> Profile {
+        let mut profile = Profile::default();
+        profile_into(self, String::new(), &mut profile);
+        profile
+    }
+}
+
+fn profile_into<K: MapKind>(value: &Value<K>, path: String, profile: &mut Profile) {
+    let type_name = match value {
+        Value::Null => "null",
+        Value::Boolean(_) => "boolean",
+        Value::Number(n) if n.is_integer() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+
+    // recorded before the children so that parents are listed first
+    if !profile.paths.contains_key(&path) {
+        profile.paths.insert(path.clone(), PathProfile::default());
+    }
+    let stats = profile.paths.get_mut(&path).expect("inserted above");
+    stats.count += 1;
+    *stats.types.entry(type_name).or_default() += 1;
+
+    match value {
+        Value::Array(array) => {
+            for value in array {
+                profile_into(value, format!("{path}[]"), profile);
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map.iter() {
+                let path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                };
+                profile_into(value, path, profile);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes one line per path, ex. `users[].email: 3 (null: 1, string: 2)`
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (path, stats) in &self.paths {
+            let path = if path.is_empty() { "(root)" } else { path };
+            write!(f, "{path}: {} (", stats.count)?;
+            for (i, (type_name, count)) in stats.types.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{type_name}: {count}")?;
+            }
+            writeln!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{OrderedMapKind, Value};
+
+    #[test]
+    fn profiles_records() {
+        let value: Value<OrderedMapKind> = r#"{
+            "users": [
+                {"id": 1, "email": "a@example.com", "tags": ["x"]},
+                {"id": 2, "email": null},
+                {"id": 3.5, "email": "c@example.com", "tags": []}
+            ]
+        }"#
+        .parse()
+        .unwrap();
+
+        let expected = "\
+(root): 1 (object: 1)
+users: 1 (array: 1)
+users[]: 3 (object: 3)
+users[].id: 3 (integer: 2, number: 1)
+users[].email: 3 (null: 1, string: 2)
+users[].tags: 2 (array: 2)
+users[].tags[]: 1 (string: 1)
+";
+        assert_eq!(value.profile().to_string(), expected);
+    }
+
+    #[test]
+    fn profile_counts() {
+        let value: Value = "[1, [2, true]]".parse().unwrap();
+        let profile = value.profile();
+
+        let stats = profile.paths.get("[]").unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.types.get("integer"), Some(&1));
+        assert_eq!(stats.types.get("array"), Some(&1));
+        assert_eq!(profile.paths.get("[][]").unwrap().count, 2);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::map::{MapKind, ObjectMap};
+
+use super::path::{split_path, InvalidPath};
+use super::Value;
+
+/// Text that replaces redacted values by default
+pub const REDACTED: &str = "***";
+
+impl<K: MapKind> Value<K> {
+    /// Replaces the value of every object entry whose key is one of `keys`
+    /// with the string `"***"`, at any depth.
+    ///
+    /// Redacted values are replaced as a whole, so nothing inside of them is
+    /// visited.
+    pub fn redact(&mut self, keys: &[&str]) {
+        self.redact_with(keys, &Value::String(REDACTED.into()));
+    }
+
+    /// Same as [`Value::redact`] but with a custom replacement, such as `null`
+    pub fn redact_with(&mut self, keys: &[&str], replacement: &Value<K>) {
+        match self {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if keys.contains(&&**key) {
+                        *value = replacement.clone();
+                    } else {
+                        value.redact_with(keys, replacement);
+                    }
+                }
+            }
+            Value::Array(array) => {
+                for value in array {
+                    value.redact_with(keys, replacement);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces the values at each of `paths` with the string `"***"`.
+    ///
+    /// Paths use the same notation as [`Value::flatten`], ex. `users[0].token`.
+    /// Paths that do not exist in the document are ignored.
+    pub fn redact_paths(&mut self, paths: &[&str]) -> Result<(), InvalidPath> {
+        self.redact_paths_with(paths, &Value::String(REDACTED.into()))
+    }
+
+    /// Same as [`Value::redact_paths`] but with a custom replacement, such as
+    /// `null`
+    pub fn redact_paths_with(
+        &mut self,
+        paths: &[&str],
+        replacement: &Value<K>,
+    ) -> Result<(), InvalidPath> {
+        // validate every path up front so a typo doesn't leave a partial redaction
+        let paths = paths
+            .iter()
+            .map(|path| split_path(path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for segments in paths {
+            if let Some(value) = self.get_segments_mut(&segments) {
+                *value = replacement.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::InvalidPath;
+    use crate::Value;
+
+    fn credentials() -> Value {
+        Value::object([
+            ("user", Value::string("davimiku")),
+            ("password", Value::string("hunter2")),
+            (
+                "sessions",
+                Value::Array(vec![Value::object([
+                    ("token", Value::string("abc123")),
+                    ("active", Value::Boolean(true)),
+                ])]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn redacts_keys_at_any_depth() {
+        let mut value = credentials();
+        value.redact(&["password", "token"]);
+
+        let expected = Value::object([
+            ("user", Value::string("davimiku")),
+            ("password", Value::string("***")),
+            (
+                "sessions",
+                Value::Array(vec![Value::object([
+                    ("token", Value::string("***")),
+                    ("active", Value::Boolean(true)),
+                ])]),
+            ),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn redacts_whole_subtree() {
+        let mut value = credentials();
+        value.redact_with(&["sessions"], &Value::Null);
+
+        let expected = Value::object([
+            ("user", Value::string("davimiku")),
+            ("password", Value::string("hunter2")),
+            ("sessions", Value::Null),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn redacts_paths() {
+        let mut value = credentials();
+        value
+            .redact_paths(&["password", "sessions[0].token", "sessions[1].token"])
+            .unwrap();
+
+        let expected = Value::object([
+            ("user", Value::string("davimiku")),
+            ("password", Value::string("***")),
+            (
+                "sessions",
+                Value::Array(vec![Value::object([
+                    ("token", Value::string("***")),
+                    ("active", Value::Boolean(true)),
+                ])]),
+            ),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn redact_invalid_path() {
+        let mut value = credentials();
+        let actual = value.redact_paths(&["password", "sessions[x]"]);
+
+        assert_eq!(actual, Err(InvalidPath(String::from("sessions[x]"))));
+        assert_eq!(value, credentials());
+    }
+}
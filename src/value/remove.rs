@@ -0,0 +1,127 @@
+use crate::map::{MapKind, ObjectMap};
+
+use super::path::{pointer_index, split_path, split_pointer, InvalidPath, Segment};
+use super::Value;
+
+impl<K: MapKind> Value<K> {
+    /// Removes and returns the value at a JSON Pointer (RFC 6901), ex.
+    /// `/users/0/token`.
+    ///
+    /// Removing an array element shifts the elements after it down by one.
+    /// Returns `None` if nothing is at the pointer, including for the empty
+    /// pointer because the root itself can't be removed.
+    pub fn remove_pointer(&mut self, pointer: &str) -> Result<Option<Value<K>>, InvalidPath> {
+        let tokens = split_pointer(pointer)?;
+        let Some((last, parents)) = tokens.split_last() else {
+            return Ok(None);
+        };
+
+        let parent = parents.iter().try_fold(self, |value, token| match value {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(array) => array.get_mut(pointer_index(token)?),
+            _ => None,
+        });
+        let removed = parent.and_then(|parent| match parent {
+            Value::Object(map) => map.remove(last),
+            Value::Array(array) => remove_index(array, pointer_index(last)?),
+            _ => None,
+        });
+        Ok(removed)
+    }
+
+    /// Removes and returns the value at a path in the notation of
+    /// [`Value::flatten`], ex. `users[0].token`.
+    ///
+    /// Behaves the same as [`Value::remove_pointer`] otherwise.
+    pub fn remove_path(&mut self, path: &str) -> Result<Option<Value<K>>, InvalidPath> {
+        let segments = split_path(path)?;
+        let Some((last, parents)) = segments.split_last() else {
+            return Ok(None);
+        };
+
+        let removed = self
+            .get_segments_mut(parents)
+            .and_then(|parent| match (parent, last) {
+                (Value::Object(map), Segment::Key(key)) => map.remove(key),
+                (Value::Array(array), Segment::Index(index)) => remove_index(array, *index),
+                _ => None,
+            });
+        Ok(removed)
+    }
+}
+
+fn remove_index<T>(array: &mut Vec<T>, index: usize) -> Option<T> {
+    (index < array.len()).then(|| array.remove(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::InvalidPath;
+    use crate::{parse, Value};
+
+    fn document() -> Value {
+        parse(String::from(
+            r#"{"a": {"b": [10, 11, 12, 13]}, "c/d": 1, "list": [{"id": 1}, {"id": 2}]}"#,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn removes_array_element_by_pointer() {
+        let mut value = document();
+        let removed = value.remove_pointer("/a/b/1").unwrap();
+
+        assert_eq!(removed, Some(Value::from(11)));
+        let expected = parse(String::from(
+            r#"{"a": {"b": [10, 12, 13]}, "c/d": 1, "list": [{"id": 1}, {"id": 2}]}"#,
+        ))
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn removes_escaped_key_by_pointer() {
+        let mut value = document();
+
+        assert_eq!(value.remove_pointer("/c~1d").unwrap(), Some(Value::from(1)));
+        assert_eq!(value.remove_pointer("/c~1d").unwrap(), None);
+    }
+
+    #[test]
+    fn removes_by_path() {
+        let mut value = document();
+
+        let removed = value.remove_path("list[0]").unwrap();
+        assert_eq!(removed, Some(Value::object([("id", Value::from(1))])));
+
+        let removed = value.remove_path("list[0].id").unwrap();
+        assert_eq!(removed, Some(Value::from(2)));
+    }
+
+    #[test]
+    fn remove_missing() {
+        let mut value = document();
+
+        assert_eq!(value.remove_pointer("/a/b/4").unwrap(), None);
+        assert_eq!(value.remove_pointer("/a/b/01").unwrap(), None);
+        assert_eq!(value.remove_pointer("/a/x/0").unwrap(), None);
+        assert_eq!(value.remove_pointer("").unwrap(), None);
+        assert_eq!(value.remove_path("a.b.c").unwrap(), None);
+        assert_eq!(value.remove_path("").unwrap(), None);
+        assert_eq!(value, document());
+    }
+
+    #[test]
+    fn remove_invalid() {
+        let mut value = document();
+
+        assert_eq!(
+            value.remove_pointer("a/b"),
+            Err(InvalidPath(String::from("a/b")))
+        );
+        assert_eq!(
+            value.remove_path("a..b"),
+            Err(InvalidPath(String::from("a..b")))
+        );
+    }
+}
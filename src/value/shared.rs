@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::Discriminant;
+use std::sync::Arc;
+
+use crate::map::{MapKind, ObjectMap};
+use crate::Number;
+
+use super::Value;
+
+/// A JSON value whose strings, arrays and objects are reference counted, so
+/// that identical subtrees can be stored once and shared, see
+/// [`Value::to_shared`]
+///
+/// Objects keep their entries sorted by key, so that objects with the same
+/// entries are shared however their maps iterate, and converting back with
+/// [`SharedValue::to_value`] inserts keys in that order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    Null,
+    Boolean(bool),
+    Number(Number),
+    String(Arc<str>),
+    Array(Arc<[SharedValue]>),
+    Object(Arc<[Entry]>),
+}
+
+/// A key of a shared object with its value
+type Entry = (Arc<str>, SharedValue);
+
+impl SharedValue {
+    /// The value of the entry with the given key, if this is an object
+    pub fn get(&self, key: &str) -> Option<&SharedValue> {
+        match self {
+            SharedValue::Object(entries) => entries
+                .binary_search_by(|(entry_key, _)| (**entry_key).cmp(key))
+                .ok()
+                .map(|index| &entries[index].1),
+            _ => None,
+        }
+    }
+
+    /// Whether both are the same allocation, rather than just equal, which
+    /// is always true of equal arrays, objects and strings from the same
+    /// call to [`Value::to_shared`]
+    pub fn ptr_eq(&self, other: &SharedValue) -> bool {
+        match (self, other) {
+            (SharedValue::String(a), SharedValue::String(b)) => Arc::ptr_eq(a, b),
+            (SharedValue::Array(a), SharedValue::Array(b)) => Arc::ptr_eq(a, b),
+            (SharedValue::Object(a), SharedValue::Object(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Copies the value back into a [`Value`], where nothing is shared
+    pub fn to_value<K: MapKind>(&self) -> Value<K> {
+        match self {
+            SharedValue::Null => Value::Null,
+            SharedValue::Boolean(b) => Value::Boolean(*b),
+            SharedValue::Number(n) => Value::Number(n.clone()),
+            SharedValue::String(s) => Value::String((**s).into()),
+            SharedValue::Array(array) => Value::Array(array.iter().map(Self::to_value).collect()),
+            SharedValue::Object(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| ((**key).into(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<K: MapKind> Value<K> {
+    /// Copies the document into a [`SharedValue`] where identical strings,
+    /// arrays and objects are stored once and shared wherever they appear,
+    /// which can shrink machine-generated documents with repeated blocks
+    /// considerably.
+    ///
+    /// Subtrees are shared when they're written the same, so numbers need
+    /// the same variant and text: `1` and `1.0` aren't shared.
+    pub fn to_shared(&self) -> SharedValue {
+        Interner::default().share(self)
+    }
+}
+
+/// The arrays, objects and strings shared so far. Since the children of a
+/// shared container are already shared, equal containers have children at
+/// the same addresses, so containers are looked up by those addresses
+/// rather than by comparing whole subtrees.
+#[derive(Default)]
+struct Interner {
+    strings: HashSet<Arc<str>>,
+    arrays: HashMap<Vec<Key>, Arc<[SharedValue]>>,
+    objects: HashMap<Vec<(*const u8, Key)>, Arc<[Entry]>>,
+}
+
+/// Identifies a shared value for looking up the container it's in
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Null,
+    Boolean(bool),
+    Number(Discriminant<Number>, String),
+    Shared(*const u8),
+}
+
+impl Interner {
+    fn share<K: MapKind>(&mut self, value: &Value<K>) -> SharedValue {
+        match value {
+            Value::Null => SharedValue::Null,
+            Value::Boolean(b) => SharedValue::Boolean(*b),
+            Value::Number(n) => SharedValue::Number(n.clone()),
+            Value::String(s) => SharedValue::String(self.string(s)),
+            Value::Array(array) => {
+                let items: Vec<SharedValue> = array.iter().map(|item| self.share(item)).collect();
+                let key = items.iter().map(key).collect();
+                let shared = self.arrays.entry(key).or_insert_with(|| items.into());
+                SharedValue::Array(Arc::clone(shared))
+            }
+            Value::Object(map) => {
+                let mut entries: Vec<Entry> = map
+                    .iter()
+                    .map(|(key, value)| (self.string(key), self.share(value)))
+                    .collect();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                let key = entries
+                    .iter()
+                    .map(|(name, value)| (Arc::as_ptr(name).cast::<u8>(), key(value)))
+                    .collect();
+                let shared = self.objects.entry(key).or_insert_with(|| entries.into());
+                SharedValue::Object(Arc::clone(shared))
+            }
+        }
+    }
+
+    fn string(&mut self, s: &str) -> Arc<str> {
+        match self.strings.get(s) {
+            Some(existing) => Arc::clone(existing),
+            None => {
+                let shared: Arc<str> = s.into();
+                self.strings.insert(Arc::clone(&shared));
+                shared
+            }
+        }
+    }
+}
+
+/// The key of a value that has already been shared, so that its strings
+/// and containers are identified by address
+fn key(value: &SharedValue) -> Key {
+    match value {
+        SharedValue::Null => Key::Null,
+        SharedValue::Boolean(b) => Key::Boolean(*b),
+        // `1` and `1.0` are equal numbers, but aren't written the same
+        SharedValue::Number(n) => Key::Number(std::mem::discriminant(n), n.to_string()),
+        SharedValue::String(s) => Key::Shared(Arc::as_ptr(s).cast()),
+        SharedValue::Array(array) => Key::Shared(Arc::as_ptr(array).cast()),
+        SharedValue::Object(entries) => Key::Shared(Arc::as_ptr(entries).cast()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedValue;
+    use crate::{parse, BTreeMapKind, DefaultMapKind, Value};
+
+    #[test]
+    fn shares_identical_subtrees() {
+        let input = r#"[
+            {"user": {"name": "a", "tags": [1, 2]}, "ok": true},
+            {"ok": true, "user": {"tags": [1, 2], "name": "a"}},
+            {"user": {"name": "a", "tags": [1, 2.0]}, "ok": true},
+            [1, 2]
+        ]"#;
+        let value = parse(input).unwrap();
+        let shared = value.to_shared();
+
+        let SharedValue::Array(items) = &shared else {
+            panic!("expected an array");
+        };
+        assert!(items[0].ptr_eq(&items[1]));
+        assert!(!items[0].ptr_eq(&items[2]));
+        let tags = |item: &SharedValue| item.get("user").unwrap().get("tags").unwrap().clone();
+        assert!(tags(&items[0]).ptr_eq(&items[3]));
+        assert!(!tags(&items[2]).ptr_eq(&items[3]));
+        let name = |item: &SharedValue| item.get("user").unwrap().get("name").unwrap().clone();
+        assert!(name(&items[0]).ptr_eq(&name(&items[2])));
+    }
+
+    #[test]
+    fn converts_back_unchanged() {
+        let input = r#"{"d": [], "a": [{"b": null}, {"b": null}, 1.5, "x"], "c": {"b": null}}"#;
+        let value = parse(input).unwrap();
+        let shared = value.to_shared();
+
+        assert_eq!(shared.to_value::<DefaultMapKind>(), value);
+        assert_eq!(
+            shared.to_value::<BTreeMapKind>().to_string(),
+            r#"{"a":[{"b":null},{"b":null},1.5,"x"],"c":{"b":null},"d":[]}"#
+        );
+        assert_eq!(Value::<DefaultMapKind>::Null.to_shared(), SharedValue::Null);
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+
+use crate::map::MapKind;
+
+use super::path::{split_pointer, InvalidPath};
+use super::Value;
+
+impl<K: MapKind> Value<K> {
+    /// Sorts the elements of an array with a comparison function. The sort is
+    /// stable, and values other than arrays are left unchanged.
+    pub fn sort_array_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&Value<K>, &Value<K>) -> Ordering,
+    {
+        if let Value::Array(array) = self {
+            array.sort_by(compare);
+        }
+    }
+
+    /// Sorts the elements of an array by the value at a JSON Pointer within
+    /// each element, ex. `/user/name`.
+    ///
+    /// Keys are compared with the total order of [`Value`], so keys of
+    /// different types are grouped by type. Elements without a value at the
+    /// pointer are sorted first. The sort is stable.
+    pub fn sort_array_by_key(&mut self, pointer: &str) -> Result<(), InvalidPath> {
+        let tokens = split_pointer(pointer)?;
+        self.sort_array_by(|a, b| a.get_tokens(&tokens).cmp(&b.get_tokens(&tokens)));
+        Ok(())
+    }
+
+    /// Orders the entries of an object by key. With `recursive`, objects
+    /// nested at any depth (including within arrays) are sorted as well.
+    ///
+    /// This only has a visible effect for maps that keep insertion order,
+    /// such as [`OrderedMap`](crate::OrderedMap).
+    pub fn sort_object_keys(&mut self, recursive: bool) {
+        match self {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = std::mem::take(map).into_iter().collect();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                if recursive {
+                    for (_, value) in &mut entries {
+                        value.sort_object_keys(true);
+                    }
+                }
+                *map = entries.into_iter().collect();
+            }
+            Value::Array(array) if recursive => {
+                for value in array {
+                    value.sort_object_keys(true);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::InvalidPath;
+    use crate::{parse, OrderedMapKind, Value};
+
+    fn parse_ordered(input: &str) -> Value<OrderedMapKind> {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn sorts_array_by_comparison() {
+        let mut value = parse(String::from("[3, 1, 2]")).unwrap();
+        value.sort_array_by(|a, b| b.cmp(a));
+
+        assert_eq!(value, parse(String::from("[3, 2, 1]")).unwrap());
+    }
+
+    #[test]
+    fn sorts_array_by_key() {
+        let mut value = parse(String::from(
+            r#"[{"n": {"v": 2}, "i": 0}, {"n": {"v": "a"}}, {"i": 1}, {"n": {"v": 1}}, {"n": {"v": 2}, "i": 2}]"#,
+        ))
+        .unwrap();
+        value.sort_array_by_key("/n/v").unwrap();
+
+        let expected = parse(String::from(
+            r#"[{"i": 1}, {"n": {"v": 1}}, {"n": {"v": 2}, "i": 0}, {"n": {"v": 2}, "i": 2}, {"n": {"v": "a"}}]"#,
+        ))
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn sort_array_by_invalid_key() {
+        let mut value = parse(String::from("[]")).unwrap();
+
+        assert_eq!(
+            value.sort_array_by_key("n"),
+            Err(InvalidPath(String::from("n")))
+        );
+    }
+
+    #[test]
+    fn sorts_object_keys() {
+        let mut value = parse_ordered(r#"{"b": 1, "a": {"d": 2, "c": 3}}"#);
+        value.sort_object_keys(false);
+
+        assert_eq!(value.to_string(), r#"{"a":{"d":2,"c":3},"b":1}"#);
+    }
+
+    #[test]
+    fn sorts_object_keys_recursively() {
+        let mut value = parse_ordered(r#"{"b": [{"z": 1, "y": 2}], "a": {"d": 2, "c": 3}}"#);
+        value.sort_object_keys(true);
+
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":{"c":3,"d":2},"b":[{"y":2,"z":1}]}"#
+        );
+    }
+}
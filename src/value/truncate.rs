@@ -0,0 +1,118 @@
+use crate::map::{MapKind, ObjectMap};
+
+use super::Value;
+
+/// Marker that takes the place of content removed by [`Value::truncate`]
+pub const ELIDED: &str = "…";
+
+impl<K: MapKind> Value<K> {
+    /// Creates a shortened copy for logging and previews of large documents,
+    /// with `"…"` in place of whatever was left out:
+    ///
+    /// - objects and arrays nested more than `max_depth` levels deep (the
+    ///   root being the first level) are replaced by `"…"`, unless empty
+    /// - arrays keep their first `max_items` elements followed by `"…"`, and
+    ///   objects their first `max_items` entries followed by `"…": "…"`
+    /// - strings keep their first `max_string_len` characters followed by `…`
+    ///
+    /// Object keys are not shortened.
+    pub fn truncate(&self, max_depth: usize, max_items: usize, max_string_len: usize) -> Value<K> {
+        let limits = Limits {
+            max_items,
+            max_string_len,
+        };
+        truncate(self, max_depth, &limits)
+    }
+}
+
+struct Limits {
+    max_items: usize,
+    max_string_len: usize,
+}
+
+fn elided<K: MapKind>() -> Value<K> {
+    Value::String(ELIDED.into())
+}
+
+fn truncate<K: MapKind>(value: &Value<K>, depth: usize, limits: &Limits) -> Value<K> {
+    match value {
+        Value::Array(array) if !array.is_empty() && depth == 0 => elided(),
+        Value::Object(map) if !map.is_empty() && depth == 0 => elided(),
+        Value::Array(array) => {
+            let mut truncated: Vec<Value<K>> = array
+                .iter()
+                .take(limits.max_items)
+                .map(|value| truncate(value, depth - 1, limits))
+                .collect();
+            if array.len() > limits.max_items {
+                truncated.push(elided());
+            }
+            Value::Array(truncated)
+        }
+        Value::Object(map) => {
+            let mut truncated: K::Map<Value<K>> = map
+                .iter()
+                .take(limits.max_items)
+                .map(|(key, value)| (key.clone(), truncate(value, depth - 1, limits)))
+                .collect();
+            if map.len() > limits.max_items {
+                truncated.insert(ELIDED.into(), elided());
+            }
+            Value::Object(truncated)
+        }
+        Value::String(s) => match s.char_indices().nth(limits.max_string_len) {
+            Some((end, _)) => Value::String(format!("{}{ELIDED}", &s[..end]).into()),
+            None => value.clone(),
+        },
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{OrderedMapKind, Value};
+
+    fn check(input: &str, limits: (usize, usize, usize), expected: &str) {
+        let value: Value<OrderedMapKind> = input.parse().unwrap();
+        let (max_depth, max_items, max_string_len) = limits;
+
+        let truncated = value.truncate(max_depth, max_items, max_string_len);
+        assert_eq!(truncated.to_string(), expected);
+    }
+
+    #[test]
+    fn within_limits_is_unchanged() {
+        let input = r#"{"a":[1,{"b":"text"}],"c":null}"#;
+
+        check(input, (3, 2, 4), input);
+    }
+
+    #[test]
+    fn truncates_strings() {
+        check(
+            r#"["short","longer text","héllo wörld"]"#,
+            (1, 10, 5),
+            r#"["short","longe…","héllo…"]"#,
+        );
+    }
+
+    #[test]
+    fn truncates_items() {
+        check("[1,2,3,4]", (1, 2, 10), r#"[1,2,"…"]"#);
+        check(
+            r#"{"a":1,"b":2,"c":3}"#,
+            (1, 2, 10),
+            r#"{"a":1,"b":2,"…":"…"}"#,
+        );
+    }
+
+    #[test]
+    fn truncates_depth() {
+        check(
+            r#"{"a":{"b":{"c":1}},"d":[],"e":[[1]]}"#,
+            (2, 10, 10),
+            r#"{"a":{"b":"…"},"d":[],"e":["…"]}"#,
+        );
+        check("[1]", (0, 10, 10), r#""…""#);
+    }
+}
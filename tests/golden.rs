@@ -0,0 +1,115 @@
+//! Golden-file tests. Every `.json` file under `tests/data` is parsed, and
+//! the result is compared with the `.expected` file next to it: the value
+//! written back as compact JSON with its object keys sorted, or `error: `
+//! followed by the error's message.
+//!
+//! The directory a file is in chooses the options it's parsed with: `strict`
+//! for the defaults, `relaxed` and `json5` for those profiles. A new case is
+//! added by creating its `.json` file and running
+//!
+//! ```sh
+//! UPDATE_EXPECT=1 cargo test --test golden
+//! ```
+//!
+//! which writes the `.expected` files from the current output instead of
+//! comparing against them. Check the diff before committing it.
+//!
+//! The output has to be the same whichever of the crate's features are
+//! enabled, so numbers that `arbitrary_precision` keeps as written, such as
+//! `1e3`, are better tested in unit tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use json_parser_lib::{parse_bytes_with, BTreeMapKind, ParseOptions, ParseProfile, Value};
+
+const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+
+#[test]
+fn golden_files() {
+    let update = std::env::var_os("UPDATE_EXPECT").is_some();
+    let mut checked = 0;
+    let mut failures = Vec::new();
+
+    for (profile, options) in profiles() {
+        for input in json_files(&Path::new(DATA).join(profile)) {
+            let actual = output(&fs::read(&input).unwrap(), &options);
+            let expected_path = input.with_extension("expected");
+            checked += 1;
+            if update {
+                fs::write(&expected_path, &actual).unwrap();
+                continue;
+            }
+            match fs::read_to_string(&expected_path) {
+                Ok(expected) if expected == actual => {}
+                Ok(expected) => failures.push(format!(
+                    "{}\n  expected: {}\n  actual:   {}",
+                    input.display(),
+                    expected.trim_end(),
+                    actual.trim_end()
+                )),
+                Err(_) => failures.push(format!(
+                    "{} has no .expected file, run with UPDATE_EXPECT=1 to create it",
+                    input.display()
+                )),
+            }
+        }
+    }
+
+    assert!(checked > 0, "no .json files found in {DATA}");
+    assert!(
+        failures.is_empty(),
+        "{} of {checked} golden files differ:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+fn profiles() -> [(&'static str, ParseOptions); 3] {
+    [
+        ("strict", ParseOptions::default()),
+        (
+            "relaxed",
+            ParseOptions::new().profile(ParseProfile::Relaxed),
+        ),
+        ("json5", ParseOptions::new().profile(ParseProfile::Json5)),
+    ]
+}
+
+/// The `.json` files in `dir`, sorted so that failures are listed in a
+/// stable order
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn output(input: &[u8], options: &ParseOptions) -> String {
+    match parse_bytes_with(input, options) {
+        Ok(value) => format!("{}\n", sorted(value)),
+        Err(error) => format!("error: {error}\n"),
+    }
+}
+
+/// Copies the value into `BTreeMap` objects, so that keys are written in the
+/// same order every time
+fn sorted(value: Value) -> Value<BTreeMapKind> {
+    match value {
+        Value::Null => Value::Null,
+        Value::Boolean(b) => Value::Boolean(b),
+        Value::Number(n) => Value::Number(n),
+        Value::String(s) => Value::String(s),
+        Value::Array(array) => Value::Array(array.into_iter().map(sorted).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, sorted(value)))
+                .collect(),
+        ),
+    }
+}